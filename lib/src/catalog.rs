@@ -0,0 +1,8 @@
+pub mod error;
+pub mod legacy;
+pub mod manifest;
+pub mod snapshot;
+
+pub use error::{Error, Result};
+pub use manifest::{CollectionRecord, DatasetRecord, Manifest, MANIFEST_FILE, SCHEMA_VERSION};
+pub use snapshot::{Snapshot, SnapshotFile, SNAPSHOT_VERSION};
@@ -0,0 +1,71 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::catalog::manifest::SCHEMA_VERSION;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why the persisted catalog could not be read or written.
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file starts like a catalog but its contents do not decode.
+    Decode {
+        path: PathBuf,
+        reason: String,
+    },
+    Encode(String),
+    /// The file is not a catalog manifest at all.
+    NotACatalog(PathBuf),
+    /// Written by a build with a schema this one does not know.
+    UnsupportedVersion {
+        path: PathBuf,
+        version: u32,
+    },
+}
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn decode(path: impl Into<PathBuf>, reason: impl ToString) -> Self {
+        Error::Decode {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "Unable to access {}: {source}", path.display()),
+            Error::Decode { path, reason } => {
+                write!(f, "Unable to decode {}: {reason}", path.display())
+            }
+            Error::Encode(reason) => write!(f, "Unable to encode the catalog: {reason}"),
+            Error::NotACatalog(path) => write!(f, "{} is not a catalog file", path.display()),
+            Error::UnsupportedVersion { path, version } => write!(
+                f,
+                "{} has catalog version {version}, this build reads versions up to {SCHEMA_VERSION}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Per-directory catalog files written before the manifest existed.
+//!
+//! Each collection and dataset had a `ds.bin` in its own directory of the data
+//! directory, with no version marker:
+//!
+//! 1. `last_modified` was a string, the fetch time for datasets.
+//! 2. `last_modified` became a timestamp and the HTTP validators were added.
+//! 3. Datasets gained their extracted metadata.
+//!
+//! A file is decoded with the newest layout that consumes it exactly.
+
+use std::path::Path;
+
+use bincode::Decode;
+
+use crate::catalog::error::{Error, Result};
+use crate::catalog::manifest::{CollectionRecord, DatasetRecord, Manifest};
+use crate::net::{document_modified, parse_document_modified, Validators};
+use crate::types::DatasetMetadata;
+
+pub const LEGACY_FILE: &str = "ds.bin";
+
+#[derive(Decode)]
+struct DatasetV1 {
+    url: String,
+    html: String,
+    alias: String,
+    content: String,
+    description: String,
+    _last_modified: String,
+}
+
+#[derive(Decode)]
+struct CollectionV1 {
+    url: String,
+    html: String,
+    alias: String,
+    descriptor: String,
+    last_modified: String,
+    datasets: Vec<DatasetV1>,
+}
+
+#[derive(Decode)]
+struct DatasetV2 {
+    url: String,
+    html: String,
+    alias: String,
+    content: String,
+    description: String,
+    last_modified: i64,
+    validators: Validators,
+}
+
+#[derive(Decode)]
+struct CollectionV2 {
+    url: String,
+    html: String,
+    alias: String,
+    descriptor: String,
+    last_modified: i64,
+    validators: Validators,
+    datasets: Vec<DatasetV2>,
+}
+
+#[derive(Decode)]
+struct CollectionV3 {
+    url: String,
+    html: String,
+    alias: String,
+    descriptor: String,
+    last_modified: i64,
+    validators: Validators,
+    datasets: Vec<DatasetRecord>,
+}
+
+impl From<DatasetV1> for DatasetRecord {
+    fn from(d: DatasetV1) -> Self {
+        // The stored date was when the page was fetched, the page itself knows better.
+        let last_modified = {
+            let package = sxd_html::parse_html(d.html.as_str());
+            document_modified(&package.as_document(), d.url.as_str()).unwrap_or(0)
+        };
+        DatasetRecord {
+            last_modified,
+            validators: Validators::default(),
+            metadata: DatasetMetadata::from_html(d.html.as_str()),
+            url: d.url,
+            html: d.html,
+            alias: d.alias,
+            content: d.content,
+            description: d.description,
+        }
+    }
+}
+
+impl From<DatasetV2> for DatasetRecord {
+    fn from(d: DatasetV2) -> Self {
+        DatasetRecord {
+            metadata: DatasetMetadata::from_html(d.html.as_str()),
+            url: d.url,
+            html: d.html,
+            alias: d.alias,
+            content: d.content,
+            description: d.description,
+            last_modified: d.last_modified,
+            validators: d.validators,
+        }
+    }
+}
+
+impl From<CollectionV1> for CollectionRecord {
+    fn from(c: CollectionV1) -> Self {
+        CollectionRecord {
+            last_modified: parse_document_modified(c.last_modified.as_str()).unwrap_or(0),
+            validators: Validators::default(),
+            datasets: c.datasets.into_iter().map(DatasetRecord::from).collect(),
+            url: c.url,
+            html: c.html,
+            alias: c.alias,
+            descriptor: c.descriptor,
+        }
+    }
+}
+
+impl From<CollectionV2> for CollectionRecord {
+    fn from(c: CollectionV2) -> Self {
+        CollectionRecord {
+            url: c.url,
+            html: c.html,
+            alias: c.alias,
+            descriptor: c.descriptor,
+            last_modified: c.last_modified,
+            validators: c.validators,
+            datasets: c.datasets.into_iter().map(DatasetRecord::from).collect(),
+        }
+    }
+}
+
+impl From<CollectionV3> for CollectionRecord {
+    fn from(c: CollectionV3) -> Self {
+        CollectionRecord {
+            url: c.url,
+            html: c.html,
+            alias: c.alias,
+            descriptor: c.descriptor,
+            last_modified: c.last_modified,
+            validators: c.validators,
+            datasets: c.datasets,
+        }
+    }
+}
+
+/// Decodes `bytes` as a `T` only if it uses all of them.
+fn exactly<T: Decode>(bytes: &[u8]) -> Option<T> {
+    match bincode::decode_from_slice(bytes, bincode::config::standard()) {
+        Ok((value, read)) if read == bytes.len() => Some(value),
+        _ => None,
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| Error::io(path, e))
+}
+
+fn collection(path: &Path) -> Result<CollectionRecord> {
+    let bytes = read(path)?;
+    exactly::<CollectionV3>(&bytes)
+        .map(CollectionRecord::from)
+        .or_else(|| exactly::<CollectionV2>(&bytes).map(CollectionRecord::from))
+        .or_else(|| exactly::<CollectionV1>(&bytes).map(CollectionRecord::from))
+        .ok_or_else(|| Error::decode(path, "not a collection of any known layout"))
+}
+
+fn dataset(path: &Path) -> Result<DatasetRecord> {
+    let bytes = read(path)?;
+    exactly::<DatasetRecord>(&bytes)
+        .or_else(|| exactly::<DatasetV2>(&bytes).map(DatasetRecord::from))
+        .or_else(|| exactly::<DatasetV1>(&bytes).map(DatasetRecord::from))
+        .ok_or_else(|| Error::decode(path, "not a dataset of any known layout"))
+}
+
+fn subdirectories(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut directories: Vec<std::path::PathBuf> = std::fs::read_dir(path)
+        .map_err(|e| Error::io(path, e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    directories.sort();
+    Ok(directories)
+}
+
+/// Gathers the per-directory files under `data_directory` into a manifest,
+/// `None` when there are none.
+///
+/// Datasets stored in their own directory replace the copy embedded in their
+/// collection's file, which was written earlier.
+pub fn load(data_directory: &Path) -> Result<Option<Manifest>> {
+    if !data_directory.is_dir() {
+        return Ok(None);
+    }
+
+    let mut collections = Vec::new();
+    for directory in subdirectories(data_directory)? {
+        let path = directory.join(LEGACY_FILE);
+        if !path.is_file() {
+            continue;
+        }
+        let mut collection = collection(&path)?;
+
+        for directory in subdirectories(&directory)? {
+            let path = directory.join(LEGACY_FILE);
+            if !path.is_file() {
+                continue;
+            }
+            let dataset = dataset(&path)?;
+            match collection
+                .datasets
+                .iter_mut()
+                .find(|d| d.alias == dataset.alias)
+            {
+                Some(embedded) => *embedded = dataset,
+                None => collection.datasets.push(dataset),
+            }
+        }
+        collections.push(collection);
+    }
+
+    if collections.is_empty() {
+        return Ok(None);
+    }
+    log::info!(
+        "Migrated {} collections from {} files in {}",
+        collections.len(),
+        LEGACY_FILE,
+        data_directory.display()
+    );
+    Ok(Some(Manifest { collections }))
+}
@@ -0,0 +1,191 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use bincode::{Decode, Encode};
+
+use crate::catalog::error::{Error, Result};
+use crate::catalog::legacy;
+use crate::files::write_atomic;
+use crate::net::Validators;
+use crate::types::{Collection, Dataset, DatasetMetadata};
+
+/// Version of the layout below. Changing any record, `Validators` or
+/// `DatasetMetadata` means bumping it and teaching [`Manifest::decode`] to read
+/// the previous version.
+///
+/// Versions 1 to 3 were per-directory `ds.bin` files, read by [`legacy`].
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Name of the manifest inside the data directory.
+pub const MANIFEST_FILE: &str = "catalog.bin";
+
+const MAGIC: [u8; 8] = *b"CRCNSCAT";
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct Header {
+    magic: [u8; 8],
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct DatasetRecord {
+    pub url: String,
+    pub html: String,
+    pub alias: String,
+    pub content: String,
+    pub description: String,
+    pub last_modified: i64,
+    pub validators: Validators,
+    pub metadata: DatasetMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct CollectionRecord {
+    pub url: String,
+    pub html: String,
+    pub alias: String,
+    pub descriptor: String,
+    pub last_modified: i64,
+    pub validators: Validators,
+    pub datasets: Vec<DatasetRecord>,
+}
+
+/// The whole scraped catalog as it is stored in `data/catalog.bin`: a header
+/// with the schema version followed by the collections and their datasets.
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode)]
+pub struct Manifest {
+    pub collections: Vec<CollectionRecord>,
+}
+
+impl Manifest {
+    pub fn from_collections(collections: &[Collection]) -> Self {
+        let collections = collections
+            .iter()
+            .map(|c| CollectionRecord {
+                url: c.url.clone(),
+                html: c.html.clone(),
+                alias: c.alias.clone(),
+                descriptor: c.descriptor.clone(),
+                last_modified: c.last_modified,
+                validators: c.validators.clone(),
+                datasets: c
+                    .datasets
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(DatasetRecord::from)
+                    .collect(),
+            })
+            .collect();
+        Manifest { collections }
+    }
+
+    pub fn into_collections(self) -> Vec<Collection> {
+        self.collections
+            .into_iter()
+            .map(|c| Collection {
+                url: c.url,
+                html: c.html,
+                alias: c.alias,
+                descriptor: c.descriptor,
+                last_modified: c.last_modified,
+                validators: c.validators,
+                datasets: Arc::new(Mutex::new(
+                    c.datasets.into_iter().map(Dataset::from).collect(),
+                )),
+            })
+            .collect()
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let header = Header {
+            magic: MAGIC,
+            version: SCHEMA_VERSION,
+        };
+        let mut encoded = bincode::encode_to_vec(header, bincode::config::standard())
+            .map_err(|e| Error::Encode(e.to_string()))?;
+        encoded.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .map_err(|e| Error::Encode(e.to_string()))?,
+        );
+        Ok(encoded)
+    }
+
+    /// Decodes a manifest read from `path`, which is only used in errors.
+    pub fn decode(bytes: &[u8], path: &Path) -> Result<Self> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(Error::NotACatalog(path.to_path_buf()));
+        }
+        let (header, read): (Header, usize) =
+            bincode::decode_from_slice(bytes, bincode::config::standard())
+                .map_err(|e| Error::decode(path, e))?;
+        let body = &bytes[read..];
+
+        match header.version {
+            SCHEMA_VERSION => {
+                let (manifest, read): (Manifest, usize) =
+                    bincode::decode_from_slice(body, bincode::config::standard())
+                        .map_err(|e| Error::decode(path, e))?;
+                if read != body.len() {
+                    return Err(Error::decode(
+                        path,
+                        format!("{} trailing bytes", body.len() - read),
+                    ));
+                }
+                Ok(manifest)
+            }
+            version => Err(Error::UnsupportedVersion {
+                path: path.to_path_buf(),
+                version,
+            }),
+        }
+    }
+
+    /// Reads the catalog persisted in `data_directory`.
+    ///
+    /// Without a manifest, per-directory files left by older versions are
+    /// migrated. `None` when nothing was ever persisted there.
+    pub fn load(data_directory: &Path) -> Result<Option<Self>> {
+        let path = data_directory.join(MANIFEST_FILE);
+        match std::fs::read(&path) {
+            Ok(bytes) => Manifest::decode(&bytes, &path).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => legacy::load(data_directory),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn save(&self, data_directory: &Path) -> Result<()> {
+        let path = data_directory.join(MANIFEST_FILE);
+        write_atomic(&path, &self.encode()?).map_err(|e| Error::io(path, e))
+    }
+}
+
+impl From<&Dataset> for DatasetRecord {
+    fn from(d: &Dataset) -> Self {
+        DatasetRecord {
+            url: d.url.clone(),
+            html: d.html.clone(),
+            alias: d.alias.clone(),
+            content: d.content.clone(),
+            description: d.description.clone(),
+            last_modified: d.last_modified,
+            validators: d.validators.clone(),
+            metadata: d.metadata.clone(),
+        }
+    }
+}
+
+impl From<DatasetRecord> for Dataset {
+    fn from(d: DatasetRecord) -> Self {
+        Dataset {
+            url: d.url,
+            html: d.html,
+            alias: d.alias,
+            content: d.content,
+            description: d.description,
+            last_modified: d.last_modified,
+            validators: d.validators,
+            metadata: d.metadata,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use bincode::{Decode, Encode};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::catalog::error::{Error, Result};
+use crate::catalog::manifest::{CollectionRecord, DatasetRecord, Manifest};
+use crate::files::listing::{CHECKSUMS_FILE, FILELIST_FILE};
+use crate::files::write_atomic;
+use crate::types::{Collection, SyncReport};
+
+/// Version of the snapshot body, independent of the manifest's.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+const MAGIC: [u8; 8] = *b"CRCNSSNP";
+
+/// Per-dataset files kept next to the catalog that travel with a snapshot.
+pub const METADATA_FILES: [&str; 2] = [FILELIST_FILE, CHECKSUMS_FILE];
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct Header {
+    magic: [u8; 8],
+    version: u32,
+    created: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotFile {
+    pub collection: String,
+    pub dataset: String,
+    /// One of [`METADATA_FILES`].
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+/// The catalog and the dataset filelists of one machine, to carry to another.
+///
+/// On disk it is an uncompressed header naming the format, its version and when
+/// it was taken, followed by the gzipped bincode body.
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode)]
+pub struct Snapshot {
+    /// Unix timestamp of the export.
+    pub created: i64,
+    pub manifest: Manifest,
+    pub files: Vec<SnapshotFile>,
+}
+
+impl Snapshot {
+    /// Takes `collections` along with the filelists already fetched into
+    /// `data_directory`.
+    pub fn capture(collections: &[Collection], data_directory: &Path) -> Result<Self> {
+        let manifest = Manifest::from_collections(collections);
+
+        let mut files = Vec::new();
+        for collection in manifest.collections.iter() {
+            for dataset in collection.datasets.iter() {
+                for name in METADATA_FILES {
+                    let path = data_directory
+                        .join(&collection.alias)
+                        .join(&dataset.alias)
+                        .join(name);
+                    match std::fs::read(&path) {
+                        Ok(contents) => files.push(SnapshotFile {
+                            collection: collection.alias.clone(),
+                            dataset: dataset.alias.clone(),
+                            name: name.to_string(),
+                            contents,
+                        }),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => return Err(Error::io(path, e)),
+                    }
+                }
+            }
+        }
+
+        Ok(Snapshot {
+            created: chrono::Utc::now().timestamp(),
+            manifest,
+            files,
+        })
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let header = Header {
+            magic: MAGIC,
+            version: SNAPSHOT_VERSION,
+            created: self.created,
+        };
+        let mut encoded = bincode::encode_to_vec(header, bincode::config::standard())
+            .map_err(|e| Error::Encode(e.to_string()))?;
+
+        let body = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| Error::Encode(e.to_string()))?;
+        let mut encoder = GzEncoder::new(&mut encoded, flate2::Compression::default());
+        encoder
+            .write_all(&body)
+            .and_then(|_| encoder.finish().map(|_| ()))
+            .map_err(|e| Error::Encode(e.to_string()))?;
+
+        Ok(encoded)
+    }
+
+    /// Decodes a snapshot read from `path`, which is only used in errors.
+    pub fn decode(bytes: &[u8], path: &Path) -> Result<Self> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(Error::NotACatalog(path.to_path_buf()));
+        }
+        let (header, read): (Header, usize) =
+            bincode::decode_from_slice(bytes, bincode::config::standard())
+                .map_err(|e| Error::decode(path, e))?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedVersion {
+                path: path.to_path_buf(),
+                version: header.version,
+            });
+        }
+
+        let mut body = Vec::new();
+        GzDecoder::new(&bytes[read..])
+            .read_to_end(&mut body)
+            .map_err(|e| Error::decode(path, e))?;
+        let (snapshot, _): (Snapshot, usize) =
+            bincode::decode_from_slice(&body, bincode::config::standard())
+                .map_err(|e| Error::decode(path, e))?;
+        Ok(snapshot)
+    }
+
+    pub fn export(&self, path: &Path) -> Result<()> {
+        write_atomic(path, &self.encode()?).map_err(|e| Error::io(path, e))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+        Snapshot::decode(&bytes, path)
+    }
+
+    /// Merges the snapshot into the catalog persisted in `data_directory`.
+    ///
+    /// Collections and datasets missing locally are added, those present on both
+    /// sides keep whichever copy was modified last, local ones on a tie. Filelists
+    /// are written for the datasets taken from the snapshot and wherever none was
+    /// fetched yet. Nothing is ever removed.
+    pub fn import(self, data_directory: &Path) -> Result<SyncReport> {
+        let mut manifest = Manifest::load(data_directory)?.unwrap_or_default();
+        let mut report = SyncReport::default();
+        let mut taken: Vec<(String, String)> = Vec::new();
+
+        for incoming in self.manifest.collections {
+            let Some(local) = manifest
+                .collections
+                .iter_mut()
+                .find(|c| c.alias == incoming.alias)
+            else {
+                for dataset in incoming.datasets.iter() {
+                    report.added.push(name(&incoming, dataset));
+                    taken.push((incoming.alias.clone(), dataset.alias.clone()));
+                }
+                manifest.collections.push(incoming);
+                continue;
+            };
+
+            for dataset in incoming.datasets.iter() {
+                match local.datasets.iter_mut().find(|d| d.alias == dataset.alias) {
+                    Some(existing) if existing.last_modified < dataset.last_modified => {
+                        report.updated.push(name(&incoming, dataset));
+                        *existing = dataset.clone();
+                    }
+                    Some(_) => {
+                        report.unchanged += 1;
+                        continue;
+                    }
+                    None => {
+                        report.added.push(name(&incoming, dataset));
+                        local.datasets.push(dataset.clone());
+                    }
+                }
+                taken.push((incoming.alias.clone(), dataset.alias.clone()));
+            }
+
+            if local.last_modified < incoming.last_modified {
+                let datasets = std::mem::take(&mut local.datasets);
+                *local = CollectionRecord {
+                    datasets,
+                    ..incoming
+                };
+            }
+        }
+
+        for file in self.files {
+            let path = data_directory
+                .join(&file.collection)
+                .join(&file.dataset)
+                .join(&file.name);
+            // Never write outside the data directory, whatever the snapshot says.
+            if !METADATA_FILES.contains(&file.name.as_str())
+                || [&file.collection, &file.dataset]
+                    .iter()
+                    .any(|s| s.is_empty() || s.contains(['/', '\\']) || s.starts_with('.'))
+            {
+                log::warn!("Skipping {} from the snapshot", path.display());
+                continue;
+            }
+            let from_snapshot = taken.contains(&(file.collection.clone(), file.dataset.clone()));
+            if from_snapshot || !path.exists() {
+                write_atomic(&path, &file.contents).map_err(|e| Error::io(&path, e))?;
+            }
+        }
+
+        manifest.save(data_directory)?;
+        report.sort();
+        Ok(report)
+    }
+}
+
+fn name(collection: &CollectionRecord, dataset: &DatasetRecord) -> String {
+    format!("{}/{}", collection.alias, dataset.alias)
+}
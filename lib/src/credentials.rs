@@ -0,0 +1,5 @@
+pub mod account;
+pub mod store;
+
+pub use account::{current, set_current, Credentials};
+pub use store::CredentialStore;
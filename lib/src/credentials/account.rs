@@ -0,0 +1,52 @@
+use std::fmt;
+use std::sync::RwLock;
+
+/// A CRCNS account, as posted to the download portal.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Credentials {
+            username: username.trim().to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// `CRCNS_USERNAME` and `CRCNS_PASSWORD`, when both are set.
+    pub fn from_env() -> Option<Self> {
+        let username = std::env::var("CRCNS_USERNAME").ok()?;
+        let password = std::env::var("CRCNS_PASSWORD").ok()?;
+        Some(Credentials::new(username.as_str(), password.as_str()))
+    }
+}
+
+// Keeps the password out of logs and panics.
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"********")
+            .finish()
+    }
+}
+
+static CURRENT: RwLock<Option<Credentials>> = RwLock::new(None);
+
+/// The account files are downloaded with: the one logged in from the GUI, the
+/// environment otherwise.
+pub fn current() -> Option<Credentials> {
+    CURRENT
+        .read()
+        .unwrap()
+        .clone()
+        .or_else(Credentials::from_env)
+}
+
+/// Switches account, `None` logs out.
+pub fn set_current(credentials: Option<Credentials>) {
+    *CURRENT.write().unwrap() = credentials;
+}
@@ -0,0 +1,103 @@
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::PathBuf;
+
+use crate::credentials::Credentials;
+use crate::settings::config_directory;
+
+const SERVICE: &str = "crcns-lens";
+const ACCOUNT: &str = "portal";
+
+/// Where credentials are remembered between runs.
+///
+/// The OS keyring is preferred, machines without one (headless Linux boxes with no
+/// secret service running) fall back to a file only readable by its owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialStore {
+    Keyring,
+    File(PathBuf),
+}
+
+impl CredentialStore {
+    /// The keyring when it answers, the `credentials` file of the configuration
+    /// directory otherwise.
+    pub fn detect() -> Self {
+        match keyring_entry().and_then(|entry| entry.get_password()) {
+            Ok(_) | Err(keyring::Error::NoEntry) => CredentialStore::Keyring,
+            Err(e) => {
+                log::info!("No usable keyring ({e}), storing credentials in a file");
+                CredentialStore::File(credentials_filepath())
+            }
+        }
+    }
+
+    pub fn load(&self) -> Result<Option<Credentials>> {
+        let secret = match self {
+            CredentialStore::Keyring => match keyring_entry().and_then(|e| e.get_password()) {
+                Ok(secret) => secret,
+                Err(keyring::Error::NoEntry) => return Ok(None),
+                Err(e) => return Err(Error::other(e)),
+            },
+            CredentialStore::File(filepath) => match std::fs::read_to_string(filepath) {
+                Ok(secret) => secret,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e),
+            },
+        };
+
+        // The username cannot contain a newline, the password may.
+        match secret.split_once('\n') {
+            Some((username, password)) => Ok(Some(Credentials::new(username, password))),
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                "stored credentials are malformed",
+            )),
+        }
+    }
+
+    pub fn save(&self, credentials: &Credentials) -> Result<()> {
+        let secret = format!("{}\n{}", credentials.username, credentials.password);
+
+        match self {
+            CredentialStore::Keyring => keyring_entry()
+                .and_then(|e| e.set_password(secret.as_str()))
+                .map_err(Error::other),
+            CredentialStore::File(filepath) => {
+                if let Some(parent) = filepath.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create(true).truncate(true);
+                #[cfg(unix)]
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+                let mut file = options.open(filepath)?;
+                // The mode only applies to new files, tighten one left readable.
+                #[cfg(unix)]
+                file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+                file.write_all(secret.as_bytes())
+            }
+        }
+    }
+
+    /// Forgets the stored credentials, succeeding when there were none.
+    pub fn delete(&self) -> Result<()> {
+        match self {
+            CredentialStore::Keyring => match keyring_entry().and_then(|e| e.delete_password()) {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(Error::other(e)),
+            },
+            CredentialStore::File(filepath) => match std::fs::remove_file(filepath) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+fn keyring_entry() -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, ACCOUNT)
+}
+
+/// `credentials` in the configuration directory.
+fn credentials_filepath() -> PathBuf {
+    config_directory().join("credentials")
+}
@@ -0,0 +1,7 @@
+pub mod job;
+pub mod manager;
+pub mod plan;
+
+pub use job::{Job, JobState};
+pub use manager::{DownloadConfig, DownloadManager};
+pub use plan::{DownloadPlan, PlanRules};
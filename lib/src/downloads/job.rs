@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use bincode::{Decode, Encode};
+
+use crate::net::Expected;
+use crate::types::File;
+
+/// Where a job is in its life.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Failed(String),
+    Done,
+}
+
+impl JobState {
+    /// Finished one way or another, nothing will happen unless the user retries.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done | JobState::Failed(_))
+    }
+}
+
+/// One file to fetch from the portal.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Job {
+    pub id: u64,
+    pub collection_alias: String,
+    pub dataset_alias: String,
+    /// Path relative to the dataset, as listed in `filelist.txt`.
+    pub remote_path: String,
+    pub local_path: String,
+    /// `0` when unknown.
+    pub remote_size: u64,
+    /// Empty when unknown.
+    pub remote_md5: String,
+    /// Higher runs first, ties run in the order they were queued.
+    pub priority: i32,
+    pub state: JobState,
+    /// Bytes on disk for this job, updated while it runs.
+    pub downloaded: u64,
+}
+
+impl Job {
+    /// Priority of `filelist.txt` and `checksums.md5`, everything else waits on them.
+    pub const METADATA_PRIORITY: i32 = 10;
+
+    pub fn new(
+        collection_alias: &str,
+        dataset_alias: &str,
+        remote_path: &str,
+        local_path: PathBuf,
+    ) -> Self {
+        Job {
+            id: 0,
+            collection_alias: collection_alias.to_string(),
+            dataset_alias: dataset_alias.to_string(),
+            remote_path: remote_path.to_string(),
+            local_path: local_path.to_str().unwrap().to_string(),
+            remote_size: 0,
+            remote_md5: String::new(),
+            priority: 0,
+            state: JobState::Queued,
+            downloaded: 0,
+        }
+    }
+
+    pub fn from_file(collection_alias: &str, dataset_alias: &str, file: &File) -> Self {
+        let mut job = Job::new(
+            collection_alias,
+            dataset_alias,
+            file.remote_path.as_str(),
+            PathBuf::from(file.local_path.as_str()),
+        );
+        job.remote_size = file.remote_size;
+        job.remote_md5 = file.remote_md5.clone();
+        job
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Path requested from the portal.
+    pub fn portal_path(&self) -> String {
+        format!("{}/{}", self.dataset_alias, self.remote_path)
+    }
+
+    pub fn expected(&self) -> Expected {
+        Expected::new(self.remote_size, self.remote_md5.as_str())
+    }
+
+    /// Two jobs for the same file are never queued side by side.
+    pub fn same_file(&self, other: &Job) -> bool {
+        self.local_path == other.local_path
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        (self.remote_size > 0).then(|| self.downloaded as f32 / self.remote_size as f32)
+    }
+}
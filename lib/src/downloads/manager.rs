@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bincode::{Decode, Encode};
+use tokio::sync::watch;
+
+use crate::downloads::job::{Job, JobState};
+use crate::files::{part, write_atomic};
+use crate::global;
+use crate::net::{self, download_with, Bandwidth, Transfer};
+use crate::types::Endpoints;
+
+/// Limits shared by every job of a [`DownloadManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct DownloadConfig {
+    pub max_concurrent: usize,
+    /// Combined rate of all running jobs, `None` for no limit.
+    pub max_bytes_per_second: Option<u64>,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        DownloadConfig {
+            max_concurrent: 2,
+            max_bytes_per_second: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    Run,
+    Pause,
+    Cancel,
+    /// The manager is shutting down, the job runs again on the next start.
+    Stop,
+}
+
+enum Outcome {
+    Finished(net::Result<u64>),
+    Stopped(Control),
+}
+
+#[derive(Encode, Decode)]
+struct Persisted {
+    next_id: u64,
+    config: DownloadConfig,
+    jobs: Vec<Job>,
+}
+
+struct Running {
+    control: watch::Sender<Control>,
+    downloaded: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    jobs: Vec<Job>,
+    next_id: u64,
+    config: DownloadConfig,
+    endpoints: Option<Endpoints>,
+    filepath: Option<PathBuf>,
+    running: HashMap<u64, Running>,
+}
+
+/// Owns every download of the application.
+///
+/// Jobs are queued with [`DownloadManager::enqueue`] and run by priority once
+/// [`DownloadManager::start`] is called, at most `max_concurrent` at a time.
+/// The queue is written to disk on every change and picked up again on the next start.
+#[derive(Clone)]
+pub struct DownloadManager {
+    inner: Arc<Mutex<Inner>>,
+    bandwidth: Arc<Bandwidth>,
+    changed: Arc<watch::Sender<u64>>,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        DownloadManager {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            bandwidth: Arc::new(Bandwidth::default()),
+            changed: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl DownloadManager {
+    /// A manager persisting its queue to `filepath`, restoring what is already there.
+    pub fn load(filepath: PathBuf) -> Self {
+        let manager = DownloadManager::default();
+        let mut inner = manager.inner.lock().unwrap();
+
+        match std::fs::read(filepath.clone()) {
+            Ok(content) => {
+                match bincode::decode_from_slice::<Persisted, _>(
+                    &content[..],
+                    bincode::config::standard(),
+                ) {
+                    Ok((persisted, _len)) => {
+                        inner.next_id = persisted.next_id;
+                        inner.config = persisted.config;
+                        inner.jobs = persisted.jobs;
+                    }
+                    Err(e) => log::warn!("Ignoring download queue {}: {e}", filepath.display()),
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => log::warn!("Unable to read {}: {e}", filepath.display()),
+        }
+
+        for job in inner.jobs.iter_mut() {
+            // Whatever was running when the application stopped resumes from its part.
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+            }
+            if !job.state.is_finished() {
+                job.downloaded = part_len(job);
+            }
+        }
+
+        inner.filepath = Some(filepath);
+        manager
+            .bandwidth
+            .set_limit(inner.config.max_bytes_per_second);
+        drop(inner);
+        manager
+    }
+
+    /// Starts running queued jobs against `endpoints`.
+    pub fn start(&self, endpoints: Endpoints) {
+        self.inner.lock().unwrap().endpoints = Some(endpoints);
+        self.schedule();
+    }
+
+    /// Stops running jobs and starts no new ones until [`DownloadManager::start`]
+    /// is called again. Interrupted jobs stay queued, in the persisted queue too.
+    pub fn stop(&self) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.endpoints = None;
+            for running in inner.running.values() {
+                let _ = running.control.send(Control::Stop);
+            }
+        }
+        self.changed();
+    }
+
+    pub fn config(&self) -> DownloadConfig {
+        self.inner.lock().unwrap().config
+    }
+
+    pub fn set_config(&self, config: DownloadConfig) {
+        self.bandwidth.set_limit(config.max_bytes_per_second);
+        self.inner.lock().unwrap().config = config;
+        self.schedule();
+    }
+
+    /// Queues `job`, returning its id. A file that is already queued or running is
+    /// not queued twice, the existing job takes the higher of both priorities.
+    pub fn enqueue(&self, job: Job) -> u64 {
+        let id = {
+            let mut inner = self.inner.lock().unwrap();
+            match inner.jobs.iter_mut().find(|j| j.same_file(&job)) {
+                Some(existing) => {
+                    existing.priority = existing.priority.max(job.priority);
+                    if existing.state.is_finished() {
+                        existing.state = JobState::Queued;
+                        existing.downloaded = part_len(existing);
+                    }
+                    existing.id
+                }
+                None => {
+                    let mut job = job;
+                    inner.next_id += 1;
+                    job.id = inner.next_id;
+                    job.state = JobState::Queued;
+                    job.downloaded = part_len(&job);
+                    let id = job.id;
+                    inner.jobs.push(job);
+                    id
+                }
+            }
+        };
+        self.schedule();
+        id
+    }
+
+    /// Snapshot of every job, running ones with their current progress.
+    pub fn jobs(&self) -> Vec<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .jobs
+            .iter()
+            .map(|job| with_progress(&inner, job))
+            .collect()
+    }
+
+    pub fn job(&self, id: u64) -> Option<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .jobs
+            .iter()
+            .find(|j| j.id == id)
+            .map(|job| with_progress(&inner, job))
+    }
+
+    /// The job downloading into `local_path`, if any.
+    pub fn job_for(&self, local_path: &str) -> Option<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .jobs
+            .iter()
+            .find(|j| j.local_path == local_path)
+            .map(|job| with_progress(&inner, job))
+    }
+
+    /// Stops a job, keeping what was downloaded so far.
+    pub fn pause(&self, id: u64) {
+        self.update(id, |inner, job| match job.state {
+            JobState::Queued => job.state = JobState::Paused,
+            JobState::Running => {
+                if let Some(running) = inner.running.get(&id) {
+                    let _ = running.control.send(Control::Pause);
+                }
+            }
+            _ => (),
+        });
+    }
+
+    pub fn resume(&self, id: u64) {
+        self.update(id, |_, job| {
+            if job.state == JobState::Paused {
+                job.state = JobState::Queued;
+            }
+        });
+        self.schedule();
+    }
+
+    /// Stops and forgets a job, discarding what was downloaded so far.
+    pub fn cancel(&self, id: u64) {
+        let removed = {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(running) = inner.running.get(&id) {
+                let _ = running.control.send(Control::Cancel);
+                None
+            } else {
+                let position = inner.jobs.iter().position(|j| j.id == id);
+                position.map(|p| inner.jobs.remove(p))
+            }
+        };
+        if let Some(job) = removed {
+            let _ = std::fs::remove_file(part::part_path(Path::new(job.local_path.as_str())));
+            self.changed();
+        }
+    }
+
+    pub fn retry(&self, id: u64) {
+        self.update(id, |_, job| {
+            if let JobState::Failed(_) = job.state {
+                job.state = JobState::Queued;
+            }
+        });
+        self.schedule();
+    }
+
+    pub fn retry_failed(&self) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            for job in inner.jobs.iter_mut() {
+                if let JobState::Failed(_) = job.state {
+                    job.state = JobState::Queued;
+                }
+            }
+        }
+        self.schedule();
+    }
+
+    pub fn set_priority(&self, id: u64, priority: i32) {
+        self.update(id, |_, job| job.priority = priority);
+    }
+
+    /// Forgets every job that completed successfully.
+    pub fn clear_finished(&self) {
+        self.inner
+            .lock()
+            .unwrap()
+            .jobs
+            .retain(|j| j.state != JobState::Done);
+        self.changed();
+    }
+
+    /// Waits for job `id` to complete, an error with the reason when it fails or
+    /// is cancelled.
+    pub async fn wait(&self, id: u64) -> net::Result<()> {
+        let mut changed = self.changed.subscribe();
+        loop {
+            match self.job(id).map(|j| j.state) {
+                Some(JobState::Done) => return Ok(()),
+                Some(JobState::Failed(reason)) => return Err(net::Error::Download(reason)),
+                None => return Err(net::Error::Download("cancelled".to_string())),
+                Some(_) => (),
+            }
+            if changed.changed().await.is_err() {
+                return Err(net::Error::Download("download manager stopped".to_string()));
+            }
+        }
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&Inner, &mut Job)) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let Some(position) = inner.jobs.iter().position(|j| j.id == id) else {
+                return;
+            };
+            let mut job = inner.jobs[position].clone();
+            f(&inner, &mut job);
+            inner.jobs[position] = job;
+        }
+        self.changed();
+    }
+
+    /// Starts the highest priority queued jobs while there is room.
+    fn schedule(&self) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let Some(endpoints) = inner.endpoints.clone() else {
+                drop(inner);
+                self.changed();
+                return;
+            };
+
+            while inner.running.len() < inner.config.max_concurrent.max(1) {
+                let next = inner
+                    .jobs
+                    .iter_mut()
+                    .filter(|j| j.state == JobState::Queued)
+                    .max_by(|a, b| a.priority.cmp(&b.priority).then(b.id.cmp(&a.id)));
+                let Some(job) = next else {
+                    break;
+                };
+
+                job.state = JobState::Running;
+                let job = job.clone();
+                let (control, control_receiver) = watch::channel(Control::Run);
+                let downloaded = Arc::new(AtomicU64::new(job.downloaded));
+                inner.running.insert(
+                    job.id,
+                    Running {
+                        control,
+                        downloaded: downloaded.clone(),
+                    },
+                );
+                self.spawn(job, endpoints.clone(), control_receiver, downloaded);
+            }
+        }
+        self.changed();
+    }
+
+    fn spawn(
+        &self,
+        job: Job,
+        endpoints: Endpoints,
+        mut control: watch::Receiver<Control>,
+        downloaded: Arc<AtomicU64>,
+    ) {
+        let manager = self.clone();
+        let transfer = Transfer {
+            downloaded,
+            bandwidth: Some(self.bandwidth.clone()),
+        };
+
+        tokio::spawn(async move {
+            let local_path = PathBuf::from(job.local_path.as_str());
+            let portal_path = job.portal_path();
+            let expected = job.expected();
+            let download = download_with(
+                &endpoints,
+                portal_path.as_str(),
+                &local_path,
+                &expected,
+                &transfer,
+            );
+
+            let outcome = tokio::select! {
+                result = download => Outcome::Finished(result),
+                control = stopped(&mut control) => Outcome::Stopped(control),
+            };
+
+            manager.finish(job.id, outcome);
+        });
+    }
+
+    fn finish(&self, id: u64, outcome: Outcome) {
+        let mut downloaded = None;
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.running.remove(&id);
+            let Some(position) = inner.jobs.iter().position(|j| j.id == id) else {
+                return;
+            };
+
+            match outcome {
+                Outcome::Finished(Ok(size)) => {
+                    let job = &mut inner.jobs[position];
+                    job.state = JobState::Done;
+                    job.downloaded = size;
+                    downloaded = Some(PathBuf::from(job.local_path.as_str()));
+                }
+                Outcome::Finished(Err(e)) => {
+                    let job = &mut inner.jobs[position];
+                    global::report_error(format!("{}: {e}", job.remote_path));
+                    job.state = JobState::Failed(e.to_string());
+                    job.downloaded = part_len(job);
+                }
+                Outcome::Stopped(Control::Cancel) => {
+                    let job = inner.jobs.remove(position);
+                    let _ =
+                        std::fs::remove_file(part::part_path(Path::new(job.local_path.as_str())));
+                }
+                Outcome::Stopped(Control::Stop) => {
+                    let job = &mut inner.jobs[position];
+                    job.state = JobState::Queued;
+                    job.downloaded = part_len(job);
+                }
+                Outcome::Stopped(_) => {
+                    let job = &mut inner.jobs[position];
+                    job.state = JobState::Paused;
+                    job.downloaded = part_len(job);
+                }
+            }
+        }
+        if let Some(path) = downloaded {
+            global::touch_file(&path);
+            global::enforce_quota(vec![path]);
+        }
+        self.schedule();
+    }
+
+    /// Persists the queue and wakes up whoever waits for a job.
+    fn changed(&self) {
+        self.persist();
+        self.changed.send_modify(|version| *version += 1);
+    }
+
+    fn persist(&self) {
+        let inner = self.inner.lock().unwrap();
+        let Some(filepath) = inner.filepath.clone() else {
+            return;
+        };
+
+        let persisted = Persisted {
+            next_id: inner.next_id,
+            config: inner.config,
+            jobs: inner.jobs.clone(),
+        };
+        drop(inner);
+
+        let result = bincode::encode_to_vec(persisted, bincode::config::standard())
+            .map_err(|e| e.to_string())
+            .and_then(|encoded| write_atomic(&filepath, &encoded).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Unable to persist {}: {e}", filepath.display());
+        }
+    }
+}
+
+fn with_progress(inner: &Inner, job: &Job) -> Job {
+    let mut job = job.clone();
+    if let Some(running) = inner.running.get(&job.id) {
+        job.downloaded = running.downloaded.load(Ordering::Relaxed);
+    }
+    job
+}
+
+fn part_len(job: &Job) -> u64 {
+    std::fs::metadata(part::part_path(Path::new(job.local_path.as_str())))
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Resolves once the job is told to pause or cancel.
+async fn stopped(control: &mut watch::Receiver<Control>) -> Control {
+    loop {
+        if control.changed().await.is_err() {
+            return std::future::pending().await;
+        }
+        let value = *control.borrow();
+        if value != Control::Run {
+            return value;
+        }
+    }
+}
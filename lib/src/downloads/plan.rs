@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::downloads::{DownloadManager, Job};
+use crate::types::{File, FileStatus};
+
+/// Which listed files of a dataset to download.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanRules {
+    /// Glob patterns over the dataset path, e.g. `*.xml` or `ec012ec.11/ec012ec.188/*`.
+    /// Every file is included when empty.
+    pub include: Vec<String>,
+    /// Applied after `include`.
+    pub exclude: Vec<String>,
+    /// Files that no longer fit are skipped, smaller ones after them may still fit.
+    pub max_bytes: Option<u64>,
+}
+
+impl PlanRules {
+    /// Rules from whitespace or comma separated pattern lists, `max_bytes` of 0 is
+    /// no budget.
+    pub fn parse(include: &str, exclude: &str, max_bytes: u64) -> Self {
+        let patterns = |text: &str| {
+            text.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        PlanRules {
+            include: patterns(include),
+            exclude: patterns(exclude),
+            max_bytes: (max_bytes > 0).then_some(max_bytes),
+        }
+    }
+}
+
+/// Files selected by [`PlanRules`], to review before they are queued.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadPlan {
+    pub selected: Vec<File>,
+    /// Matched but left out by the size budget.
+    pub over_budget: Vec<File>,
+    /// Matched but already on disk and intact.
+    pub present: usize,
+}
+
+impl DownloadPlan {
+    pub fn build(files: &[File], rules: &PlanRules) -> Result<Self, globset::Error> {
+        let include = glob_set(&rules.include)?;
+        let exclude = glob_set(&rules.exclude)?;
+        let mut plan = DownloadPlan::default();
+        let mut bytes = 0;
+
+        for file in files {
+            let path = file.remote_path.as_str();
+            if (!rules.include.is_empty() && !include.is_match(path)) || exclude.is_match(path) {
+                continue;
+            }
+            if file.status == FileStatus::Ok {
+                plan.present += 1;
+                continue;
+            }
+            if rules
+                .max_bytes
+                .is_some_and(|max| bytes + file.remote_size > max)
+            {
+                plan.over_budget.push(file.clone());
+                continue;
+            }
+            bytes += file.remote_size;
+            plan.selected.push(file.clone());
+        }
+
+        Ok(plan)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.selected.iter().map(|f| f.remote_size).sum()
+    }
+
+    pub fn estimated_duration(&self, bytes_per_second: u64) -> Duration {
+        Duration::from_secs(self.bytes().div_ceil(bytes_per_second.max(1)))
+    }
+
+    /// Queues the selected files, returning the job ids.
+    pub fn enqueue(
+        &self,
+        downloads: &DownloadManager,
+        collection_alias: &str,
+        dataset_alias: &str,
+    ) -> Vec<u64> {
+        self.selected
+            .iter()
+            .map(|file| downloads.enqueue(Job::from_file(collection_alias, dataset_alias, file)))
+            .collect()
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
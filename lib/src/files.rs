@@ -1,4 +1,18 @@
-pub mod get_file;
+pub mod archive;
+pub mod extracted;
 pub mod handlers;
+pub mod listing;
+pub mod part;
+pub mod reconcile;
+pub mod verify;
+pub mod write_atomic;
 
-pub use get_file::get_file;
+pub use archive::{extract_members, list_members, ArchiveKind, ArchiveMember, ExtractedMember};
+pub use extracted::{dataset_directory, extracted_file, ExtractedFiles, EXTRACTED_FILE};
+pub use listing::{normalize_path, parse_checksums, parse_filelist, Filelist, ListedFile, Listing};
+pub use part::part_path;
+pub use reconcile::{
+    apply_mirrors, file_extension, reconcile, Completeness, HashCache, Reconciliation,
+};
+pub use verify::verify_file;
+pub use write_atomic::write_atomic;
@@ -0,0 +1,198 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::files::{normalize_path, part_path};
+
+/// Archive formats whose members can be listed and extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// A regular file inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// Normalized path inside the archive, see [`normalize_path`].
+    pub path: String,
+    pub size: u64,
+}
+
+/// An extracted member and where it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedMember {
+    pub member: ArchiveMember,
+    pub path: PathBuf,
+}
+
+/// Regular files of `archive`, in archive order, without extracting them. Links
+/// and members whose path leaves the archive are skipped.
+pub fn list_members(archive: &Path) -> Result<Vec<ArchiveMember>> {
+    let mut members = Vec::new();
+    match kind(archive)? {
+        ArchiveKind::Zip => {
+            let mut zip = open_zip(archive)?;
+            for i in 0..zip.len() {
+                let entry = zip.by_index(i).map_err(zip_error)?;
+                if let Some(member) = zip_member(&entry) {
+                    members.push(member);
+                }
+            }
+        }
+        tar => {
+            let mut tar = open_tar(archive, tar)?;
+            for entry in tar.entries()? {
+                if let Some(member) = tar_member(&entry?) {
+                    members.push(member);
+                }
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// Extracts the `selected` members of `archive` under `destination`, keeping their
+/// path inside the archive. `progress` is called with the bytes written so far and
+/// the total size of the selection.
+pub fn extract_members(
+    archive: &Path,
+    destination: &Path,
+    selected: &[ArchiveMember],
+    mut progress: impl FnMut(u64, u64),
+) -> Result<Vec<ExtractedMember>> {
+    let total: u64 = selected.iter().map(|m| m.size).sum();
+    let mut written = 0;
+    let mut extracted = Vec::new();
+    let mut extract = |member: ArchiveMember, reader: &mut dyn Read| -> Result<()> {
+        let path = destination.join(&member.path);
+        copy(reader, &path, |n| {
+            written += n;
+            progress(written, total);
+        })?;
+        extracted.push(ExtractedMember { member, path });
+        Ok(())
+    };
+
+    match kind(archive)? {
+        ArchiveKind::Zip => {
+            let mut zip = open_zip(archive)?;
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i).map_err(zip_error)?;
+                match zip_member(&entry) {
+                    Some(member) if selected.contains(&member) => extract(member, &mut entry)?,
+                    _ => (),
+                }
+            }
+        }
+        tar => {
+            let mut tar = open_tar(archive, tar)?;
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                match tar_member(&entry) {
+                    Some(member) if selected.contains(&member) => extract(member, &mut entry)?,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    if let Some(missing) = selected
+        .iter()
+        .find(|m| !extracted.iter().any(|e| &e.member == *m))
+    {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{} is not in {}", missing.path, archive.display()),
+        ));
+    }
+    Ok(extracted)
+}
+
+fn kind(archive: &Path) -> Result<ArchiveKind> {
+    ArchiveKind::from_path(archive).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not an archive", archive.display()),
+        )
+    })
+}
+
+fn open_tar(archive: &Path, kind: ArchiveKind) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = fs::File::open(archive)?;
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(std::io::BufReader::new(file))),
+        _ => Box::new(std::io::BufReader::new(file)),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn tar_member<R: Read>(entry: &tar::Entry<R>) -> Option<ArchiveMember> {
+    if !entry.header().entry_type().is_file() {
+        return None;
+    }
+    let path = entry.path().ok()?;
+    Some(ArchiveMember {
+        path: normalize_path(path.to_str()?)?,
+        size: entry.size(),
+    })
+}
+
+fn open_zip(archive: &Path) -> Result<zip::ZipArchive<fs::File>> {
+    zip::ZipArchive::new(fs::File::open(archive)?).map_err(zip_error)
+}
+
+fn zip_member(entry: &zip::read::ZipFile) -> Option<ArchiveMember> {
+    if !entry.is_file() {
+        return None;
+    }
+    Some(ArchiveMember {
+        path: normalize_path(entry.name())?,
+        size: entry.size(),
+    })
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    match e {
+        zip::result::ZipError::Io(e) => e,
+        e => Error::new(ErrorKind::InvalidData, e),
+    }
+}
+
+/// Writes `reader` to `path` through a `.part`, so `path` is only ever complete.
+fn copy(reader: &mut dyn Read, path: &Path, mut progress: impl FnMut(u64)) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let part = part_path(path);
+    let mut file = fs::File::create(&part)?;
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buffer[..n])?;
+        progress(n as u64);
+    }
+    file.sync_all()?;
+    fs::rename(part, path)
+}
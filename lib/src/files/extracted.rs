@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use bincode::{Decode, Encode};
+
+use crate::files::{file_extension, write_atomic, ExtractedMember};
+use crate::types::{File, FileStatus};
+
+/// Record of the files extracted from the archives of a dataset, kept in the
+/// dataset directory next to its listing.
+pub const EXTRACTED_FILE: &str = "extracted.bin";
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+struct ExtractedEntry {
+    size: u64,
+    /// Dataset path of the archive.
+    archive: String,
+}
+
+/// Files extracted from the archives of a dataset keyed by their dataset path.
+///
+/// The portal listings do not mention them, without this record they would show
+/// up as untracked once the listing is loaded again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct ExtractedFiles {
+    entries: BTreeMap<String, ExtractedEntry>,
+}
+
+impl ExtractedFiles {
+    /// The record of `dataset_directory`, empty when nothing was extracted there.
+    pub fn load(dataset_directory: &Path) -> std::io::Result<Self> {
+        let filepath = dataset_directory.join(EXTRACTED_FILE);
+        match std::fs::read(&filepath) {
+            Ok(content) => bincode::decode_from_slice(&content, bincode::config::standard())
+                .map(|(extracted, _)| extracted)
+                .map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: {e}", filepath.display()),
+                    )
+                }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(ExtractedFiles::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, dataset_directory: &Path) -> std::io::Result<()> {
+        let encoded = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        write_atomic(&dataset_directory.join(EXTRACTED_FILE), &encoded)
+    }
+
+    /// Records `file`, replacing an earlier extraction to the same path. Listed
+    /// files are ignored.
+    pub fn add(&mut self, file: &File) {
+        if let Some(archive) = &file.archive {
+            self.entries.insert(
+                file.remote_path.clone(),
+                ExtractedEntry {
+                    size: file.remote_size,
+                    archive: archive.clone(),
+                },
+            );
+        }
+    }
+
+    /// The recorded files as working files of the dataset in `dataset_directory`,
+    /// their status left to the next reconciliation.
+    pub fn files(&self, dataset_directory: &Path) -> Vec<File> {
+        self.entries
+            .iter()
+            .map(|(remote_path, entry)| File {
+                extension: file_extension(remote_path),
+                remote_path: remote_path.clone(),
+                remote_size: entry.size,
+                local_path: dataset_directory
+                    .join(remote_path)
+                    .to_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                archive: Some(entry.archive.clone()),
+                ..File::default()
+            })
+            .collect()
+    }
+
+    /// Appends to `files` the recorded files it does not list yet.
+    pub fn merge_into(&self, dataset_directory: &Path, files: &mut Vec<File>) {
+        for file in self.files(dataset_directory) {
+            if !files.iter().any(|f| f.local_path == file.local_path) {
+                files.push(file);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A member listed next to the archive, under the dataset path it was extracted to.
+pub fn extracted_file(archive: &File, extracted: &ExtractedMember) -> File {
+    let remote_path = match archive.remote_path.rsplit_once('/') {
+        Some((directory, _)) => format!("{directory}/{}", extracted.member.path),
+        None => extracted.member.path.clone(),
+    };
+    File {
+        extension: file_extension(&remote_path),
+        remote_path,
+        remote_size: extracted.member.size,
+        local_path: extracted.path.to_str().unwrap_or_default().to_string(),
+        local_size: extracted.member.size,
+        status: FileStatus::Ok,
+        archive: Some(archive.remote_path.clone()),
+        ..File::default()
+    }
+}
+
+/// Directory of the dataset `file` belongs to, its local path without its dataset
+/// path.
+pub fn dataset_directory(file: &File) -> Option<&Path> {
+    let depth = file
+        .remote_path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .count();
+    Path::new(file.local_path.as_str()).ancestors().nth(depth)
+}
@@ -1,3 +1,9 @@
+pub mod archive;
 pub mod filelist;
+pub mod reconcile;
+pub mod verify;
 
+pub use archive::extract_archive;
 pub use filelist::load_or_download_filelist;
+pub use reconcile::reconcile_dataset;
+pub use verify::{redownload_mismatches, verify_dataset};
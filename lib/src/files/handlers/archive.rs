@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use crate::{
+    files::{dataset_directory, extract_members, extracted_file, ArchiveMember, ExtractedFiles},
+    global,
+    types::{File, Report},
+};
+
+/// Extracts the `selected` members of a downloaded archive next to it, records them
+/// in the dataset's [`ExtractedFiles`] and adds them to the working files. Progress
+/// is published under the archive's local path.
+pub async fn extract_archive(archive: File, selected: Vec<ArchiveMember>) {
+    let key = archive.local_path.clone();
+    let local_path = PathBuf::from(archive.local_path.as_str());
+    let destination = local_path.parent().map(PathBuf::from).unwrap_or_default();
+
+    global::set_progress(&key, Some(0.0));
+    let result = {
+        let (key, destination) = (key.clone(), destination.clone());
+        tokio::task::spawn_blocking(move || {
+            extract_members(&local_path, &destination, &selected, |written, total| {
+                global::set_progress(&key, Some(written as f32 / total.max(1) as f32));
+            })
+        })
+        .await
+        .expect("archive extraction panicked")
+    };
+    global::set_progress(&key, None);
+
+    match result {
+        Ok(extracted) => {
+            let count = extracted.len();
+            let files: Vec<File> = extracted
+                .iter()
+                .map(|member| extracted_file(&archive, member))
+                .collect();
+            if let Some(directory) = dataset_directory(&archive) {
+                let recorded = ExtractedFiles::load(directory).and_then(|mut record| {
+                    files.iter().for_each(|file| record.add(file));
+                    record.save(directory)
+                });
+                if let Err(e) = recorded {
+                    global::report_error(format!("{}: {e}", archive.remote_path));
+                }
+            }
+            global::add_state_dataset_files(files);
+            global::report(Report::Success(format!(
+                "Extracted {count} files from {}",
+                archive.remote_path
+            )));
+        }
+        Err(e) => global::report_error(format!("{}: {e}", archive.remote_path)),
+    }
+}
@@ -1,105 +1,80 @@
 use crate::{
+    downloads::Job,
+    files::{
+        listing::{Listing, CHECKSUMS_FILE, FILELIST_FILE},
+        ExtractedFiles,
+    },
     global,
-    types::{Collection, Dataset, File},
+    net::{portal_page, Result},
+    types::{Collection, Dataset, File, Report},
 };
-use std::{
-    io::BufRead,
-    path::PathBuf,
-    str::FromStr,
-    sync::{Arc, Mutex},
-};
-use tokio::sync::mpsc;
+use std::{path::PathBuf, str::FromStr};
 
-pub async fn load_or_download_filelist(collection: Collection, dataset: Dataset) {
+pub async fn load_or_download_filelist(collection: Collection, dataset: Dataset) -> Result<()> {
     let state = global::get_state();
 
     let dir_path = state
-        .working_directory
-        .join("data")
+        .workspace
+        .data_directory()
         .join(collection.alias.clone())
         .join(dataset.alias.clone());
 
     download_file(
-        String::from_str("filelist.txt").unwrap(),
+        String::from_str(FILELIST_FILE).unwrap(),
         dir_path.clone(),
         collection.clone(),
         dataset.clone(),
     )
-    .await;
-
-    let filelist_filepath = dir_path.join("filelist.txt");
-    let filelist_file = std::fs::File::open(filelist_filepath).unwrap();
-    let filelist_reader = std::io::BufReader::new(filelist_file);
-
-    let mut remote_paths: Vec<String> = Vec::new();
-    let mut remote_sizes: Vec<u64> = Vec::new();
-
-    for line in filelist_reader.lines() {
-        let line = line.unwrap();
-        if line.starts_with('#') || line.trim().is_empty() {
-            continue;
-        }
-
-        let fields: Vec<&str> = line.split_whitespace().collect();
-
-        if fields.clone().len() < 2 {
-            continue;
-        }
-
-        remote_paths.push(fields[0].to_string());
-        remote_sizes.push(fields[1].parse::<u64>().unwrap());
-    }
+    .await?;
 
     download_file(
-        String::from_str("checksums.md5").unwrap(),
+        String::from_str(CHECKSUMS_FILE).unwrap(),
         dir_path.clone(),
         collection.clone(),
         dataset.clone(),
     )
-    .await;
-
-    let checksums_filepath = dir_path.join("checksums.md5");
-    let checksums_file = std::fs::File::open(checksums_filepath).unwrap();
-    let checksums_reader = std::io::BufReader::new(checksums_file);
-
-    let mut remote_md5s: Vec<String> = Vec::new();
-
-    for line in checksums_reader.lines() {
-        let line = line.unwrap();
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let fields: Vec<&str> = line.split_whitespace().collect();
-
-        if fields.clone().len() < 2 {
-            continue;
-        }
-
-        remote_md5s.push(fields[0].to_string());
+    .await?;
+
+    let listing = Listing::read(&dir_path)?;
+    if !listing.is_consistent() {
+        global::report(Report::Warning(format!(
+            "{}: {} files have no checksum, {} checksums list no file",
+            dataset.alias,
+            listing.only_in_filelist.len(),
+            listing.only_in_checksums.len()
+        )));
+    }
+    if !listing.skipped.is_empty() {
+        global::report(Report::Warning(format!(
+            "{}: skipped {} {FILELIST_FILE} lines without a size, {}",
+            dataset.alias,
+            listing.skipped.len(),
+            listing.skipped.join("; ")
+        )));
     }
 
-    let mut files: Vec<File> = Vec::new();
-
-    for f in 0..remote_md5s.len() {
-        let local_path = dir_path
-            .join(remote_paths[f].clone())
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        files.push(File {
-            remote_md5: remote_md5s[f].clone(),
-            remote_path: remote_paths[f].clone(),
-            remote_size: remote_sizes[f],
-            local_path,
-            local_size: 0,
-            local_md5: "".to_string(),
-            extension: "".to_string(),
-        });
+    let mut files = listing
+        .files
+        .into_iter()
+        .map(|listed| File {
+            local_path: dir_path.join(&listed.path).to_str().unwrap().to_string(),
+            remote_md5: listed.md5.unwrap_or_default(),
+            remote_size: listed.size.unwrap_or(0),
+            remote_path: listed.path,
+            ..File::default()
+        })
+        .collect::<Vec<File>>();
+    match ExtractedFiles::load(&dir_path) {
+        Ok(extracted) => extracted.merge_into(&dir_path, &mut files),
+        Err(e) => global::report(Report::Warning(format!(
+            "{}: extracted files are untracked, {e}",
+            dataset.alias
+        ))),
     }
 
     global::set_state_dataset_files(files);
+
+    Ok(())
 }
 
 async fn download_file(
@@ -107,19 +82,29 @@ async fn download_file(
     dir_path: PathBuf,
     collection: Collection,
     dataset: Dataset,
-) {
+) -> Result<()> {
     let filelist_path = dir_path.join(filename.clone());
 
+    // Older versions saved the portal login page in place of the listing.
+    if let Ok(content) = std::fs::read(&filelist_path) {
+        if portal_page::looks_like_html(&content) {
+            log::warn!("Discarding {}, it is an HTML page", filelist_path.display());
+            std::fs::remove_file(&filelist_path)?;
+        }
+    }
+
     if !filelist_path.exists() {
-        let _ = tokio::spawn(async move {
-            dataset
-                .get_crcns_file(
-                    collection.alias.clone().as_str(),
-                    filename.as_str(),
-                    Arc::new(Mutex::new(mpsc::unbounded_channel())),
-                )
-                .await;
-        })
-        .await;
+        let downloads = global::get_state().downloads;
+        let job = Job::new(
+            collection.alias.as_str(),
+            dataset.alias.as_str(),
+            filename.as_str(),
+            filelist_path,
+        )
+        .with_priority(Job::METADATA_PRIORITY);
+        let id = downloads.enqueue(job);
+        downloads.wait(id).await?;
     }
+
+    Ok(())
 }
@@ -0,0 +1,74 @@
+use crate::{
+    files::{apply_mirrors, reconcile, reconcile::HASH_CACHE_FILE, HashCache},
+    global,
+    types::{Collection, Dataset, Report},
+};
+
+/// Matches the working files against the dataset directory, filling their local
+/// fields and status, and reports how complete the dataset is.
+pub async fn reconcile_dataset(collection: Collection, dataset: Dataset) {
+    let workspace = global::get_state().workspace;
+    let data_directory = workspace.data_directory();
+    let mirrors = workspace.mirrors();
+    let cache_filepath = data_directory.join(HASH_CACHE_FILE);
+    let mut cache = HashCache::load(&cache_filepath).unwrap_or_else(|e| {
+        global::report(Report::Warning(format!("Hashing again, {e}")));
+        HashCache::default()
+    });
+
+    let files = global::get_state_dataset_files();
+    let result = async {
+        let mut reconciliation = reconcile(
+            &data_directory,
+            collection.alias.as_str(),
+            dataset.alias.as_str(),
+            &files,
+            &mut cache,
+        )
+        .await?;
+        if !mirrors.is_empty() {
+            apply_mirrors(&mut reconciliation, &data_directory, &mirrors, &mut cache).await?;
+        }
+        cache.retain_mirrored(&mirrors).await;
+        Ok::<_, crate::net::Error>(reconciliation)
+    }
+    .await;
+    let reconciliation = match result {
+        Ok(reconciliation) => reconciliation,
+        Err(e) => {
+            global::report_error(format!("{}: {e}", dataset.alias));
+            return;
+        }
+    };
+
+    if let Err(e) = cache.save(&cache_filepath) {
+        global::report_error(format!("Unable to save {}: {e}", cache_filepath.display()));
+    }
+
+    let completeness = reconciliation.completeness;
+    let untracked = reconciliation.untracked.len();
+    global::set_verified_copies(
+        collection.alias.as_str(),
+        dataset.alias.as_str(),
+        &reconciliation.files,
+    );
+    global::set_state_dataset_files(reconciliation.files);
+    global::set_state_untracked_files(reconciliation.untracked);
+
+    let summary = format!(
+        "{}: {} of {} files on disk ({:.0}%), {} untracked",
+        dataset.alias,
+        completeness.ok,
+        completeness.listed,
+        completeness.fraction() * 100.0,
+        untracked
+    );
+    if completeness.mismatched > 0 {
+        global::report(Report::Warning(format!(
+            "{summary}, {} mismatched",
+            completeness.mismatched
+        )));
+    } else {
+        global::report(Report::Info(summary));
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    downloads::Job,
+    files::verify_file,
+    global,
+    types::{Collection, Dataset, FileStatus, Report},
+};
+
+/// Hashes every listed file of the working dataset, updating their status as it goes.
+pub async fn verify_dataset(dataset: Dataset) {
+    let files = global::get_state_dataset_files();
+    let (mut ok, mut mismatch, mut missing, mut partial) = (0, 0, 0, 0);
+
+    for file in files {
+        let file = match verify_file(&file).await {
+            Ok(file) => file,
+            Err(e) => {
+                global::report_error(format!("{}: {e}", file.remote_path));
+                continue;
+            }
+        };
+
+        match file.status {
+            FileStatus::Ok => ok += 1,
+            FileStatus::Mismatch => mismatch += 1,
+            FileStatus::Missing => missing += 1,
+            FileStatus::Partial => partial += 1,
+            FileStatus::Unknown => (),
+        }
+        global::set_state_dataset_file(file);
+    }
+
+    let summary = format!(
+        "{}: {ok} ok, {mismatch} mismatched, {missing} missing, {partial} partial",
+        dataset.alias
+    );
+    if mismatch > 0 {
+        global::report(Report::Warning(summary));
+    } else {
+        global::report(Report::Success(summary));
+    }
+}
+
+/// Deletes the mismatched copies of the working dataset and queues them again.
+pub fn redownload_mismatches(collection: Collection, dataset: Dataset) {
+    let downloads = global::get_state().downloads;
+
+    for mut file in global::get_state_dataset_files() {
+        if file.status != FileStatus::Mismatch {
+            continue;
+        }
+
+        if let Err(e) = std::fs::remove_file(file.local_path.as_str()) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                global::report_error(format!("{}: {e}", file.local_path));
+                continue;
+            }
+        }
+
+        downloads.enqueue(Job::from_file(
+            collection.alias.as_str(),
+            dataset.alias.as_str(),
+            &file,
+        ));
+        file.status = FileStatus::Missing;
+        global::set_state_dataset_file(file);
+    }
+}
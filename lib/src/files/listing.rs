@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::net::{Error, Result};
+
+pub const FILELIST_FILE: &str = "filelist.txt";
+pub const CHECKSUMS_FILE: &str = "checksums.md5";
+
+/// A file of a dataset as listed by the portal, either field may be missing when
+/// the file only appears in one of the two listings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedFile {
+    /// Normalized path relative to the dataset, see [`normalize_path`].
+    pub path: String,
+    pub size: Option<u64>,
+    /// Lowercase hex digest.
+    pub md5: Option<String>,
+}
+
+/// `filelist.txt` and `checksums.md5` of a dataset joined by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listing {
+    /// Every path of either listing, sorted.
+    pub files: Vec<ListedFile>,
+    pub only_in_filelist: Vec<String>,
+    pub only_in_checksums: Vec<String>,
+    /// `filelist.txt` lines left out because they have no size, see [`Filelist`].
+    pub skipped: Vec<String>,
+}
+
+/// A parsed `filelist.txt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filelist {
+    /// Sizes keyed by normalized path.
+    pub sizes: BTreeMap<String, u64>,
+    /// Lines without a size, as `line <n>: <line>`.
+    pub skipped: Vec<String>,
+}
+
+impl Listing {
+    pub fn join(sizes: BTreeMap<String, u64>, md5s: BTreeMap<String, String>) -> Self {
+        let mut listing = Listing::default();
+        let mut md5s = md5s;
+
+        for (path, size) in sizes {
+            let md5 = md5s.remove(&path);
+            if md5.is_none() {
+                listing.only_in_filelist.push(path.clone());
+            }
+            listing.files.push(ListedFile {
+                path,
+                size: Some(size),
+                md5,
+            });
+        }
+        for (path, md5) in md5s {
+            listing.only_in_checksums.push(path.clone());
+            listing.files.push(ListedFile {
+                path,
+                size: None,
+                md5: Some(md5),
+            });
+        }
+
+        listing.files.sort_by(|a, b| a.path.cmp(&b.path));
+        listing
+    }
+
+    /// Reads both listings from a dataset directory.
+    pub fn read(directory: &Path) -> Result<Self> {
+        let filelist_filepath = directory.join(FILELIST_FILE);
+        let checksums_filepath = directory.join(CHECKSUMS_FILE);
+        let filelist = parse_filelist(
+            &std::fs::read_to_string(&filelist_filepath)?,
+            filelist_filepath.display(),
+        )?;
+        let md5s = parse_checksums(
+            &std::fs::read_to_string(&checksums_filepath)?,
+            checksums_filepath.display(),
+        )?;
+        let mut listing = Listing::join(filelist.sizes, md5s);
+        listing.skipped = filelist.skipped;
+        Ok(listing)
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.only_in_filelist.is_empty() && self.only_in_checksums.is_empty()
+    }
+}
+
+/// Sizes keyed by path from a `filelist.txt`, one `<path> <size>` per line. The
+/// size may also come first, paths may contain spaces, `#` starts a comment.
+/// Lines without a size are skipped, a path leaving the dataset is an error.
+pub fn parse_filelist(content: &str, source: impl ToString) -> Result<Filelist> {
+    let mut filelist = Filelist::default();
+
+    for (n, line) in lines(content) {
+        let (path, size) = match line.rsplit_once(char::is_whitespace) {
+            Some((path, size)) if size.parse::<u64>().is_ok() => (path, size),
+            _ => match line.split_once(char::is_whitespace) {
+                Some((size, path)) if size.parse::<u64>().is_ok() => (path, size),
+                _ => {
+                    filelist.skipped.push(format!("line {n}: {line}"));
+                    continue;
+                }
+            },
+        };
+        let path = normalize(path, &source, n)?;
+        filelist.sizes.insert(path, size.parse().unwrap());
+    }
+
+    Ok(filelist)
+}
+
+/// Digests keyed by path from a `checksums.md5`, in the `md5sum` format
+/// (`<md5>  <path>`, `<md5> *<path>` for binary mode) or the BSD one
+/// (`MD5 (<path>) = <md5>`).
+pub fn parse_checksums(content: &str, source: impl ToString) -> Result<BTreeMap<String, String>> {
+    let mut md5s = BTreeMap::new();
+
+    for (n, line) in lines(content) {
+        let entry = match line.strip_prefix("MD5 (") {
+            Some(rest) => rest
+                .rsplit_once(") = ")
+                .map(|(path, md5)| (md5.trim(), path)),
+            None => line
+                .split_once(char::is_whitespace)
+                .map(|(md5, path)| (md5, path.trim_start().trim_start_matches('*'))),
+        };
+        let (md5, path) = match entry {
+            Some((md5, path)) if is_md5(md5) => (md5, path),
+            _ => {
+                return Err(Error::parse(
+                    source.to_string(),
+                    format!("line {n}: no md5 in {line:?}"),
+                ))
+            }
+        };
+        let path = normalize(path, &source, n)?;
+        md5s.insert(path, md5.to_ascii_lowercase());
+    }
+
+    Ok(md5s)
+}
+
+/// `/` separated path relative to the dataset: backslashes, `./`, leading and
+/// repeated separators are dropped. `None` when it is empty or leaves the dataset.
+pub fn normalize_path(raw: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in raw.trim().split(['/', '\\']) {
+        match part {
+            "" | "." => (),
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Non-empty lines that are not comments, trimmed and numbered from 1.
+fn lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn normalize(path: &str, source: &impl ToString, n: usize) -> Result<String> {
+    normalize_path(path).ok_or_else(|| {
+        Error::parse(
+            source.to_string(),
+            format!("line {n}: invalid path {path:?}"),
+        )
+    })
+}
+
+fn is_md5(digest: &str) -> bool {
+    digest.len() == 32 && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
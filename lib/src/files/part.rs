@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::Result;
+
+/// `<filepath>.part`, where a download lives until it is complete and verified.
+pub fn part_path(filepath: &Path) -> PathBuf {
+    let mut filename = filepath
+        .file_name()
+        .map(|f| f.to_os_string())
+        .unwrap_or_default();
+    filename.push(".part");
+    filepath.with_file_name(filename)
+}
+
+/// Bytes already downloaded into `part_filepath`, zero when it does not exist.
+pub async fn part_len(part_filepath: &Path) -> u64 {
+    match fs::metadata(part_filepath).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Opens `part_filepath` for writing, appending to what is there when `resume` is set
+/// and starting over otherwise.
+pub async fn open_part(part_filepath: &Path, resume: bool) -> Result<File> {
+    if let Some(parent) = part_filepath.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.create(true);
+    if resume {
+        options.append(true);
+    } else {
+        options.write(true).truncate(true);
+    }
+    options.open(part_filepath).await
+}
+
+/// Moves a finished download into place. The rename is atomic on the same filesystem,
+/// so `filepath` either does not exist or is complete.
+pub async fn commit_part(part_filepath: &Path, filepath: &Path) -> Result<()> {
+    fs::rename(part_filepath, filepath).await
+}
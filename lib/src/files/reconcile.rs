@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use bincode::{Decode, Encode};
+
+use crate::catalog::snapshot::METADATA_FILES;
+use crate::files::{
+    part_path, verify::hashed_status, write_atomic, ExtractedFiles, EXTRACTED_FILE,
+};
+use crate::net::{md5_file, Result};
+use crate::storage::{relative_path, DatasetUsage, LocalFile, Mirrors};
+use crate::types::{File, FileStatus};
+
+pub const HASH_CACHE_FILE: &str = "hashes.bin";
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+struct CachedHash {
+    size: u64,
+    modified: i64,
+    md5: String,
+}
+
+/// md5 of local files keyed by their path relative to the data directory, or by
+/// their absolute path for mirror copies, valid while their size and modification
+/// time are unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct HashCache {
+    entries: BTreeMap<String, CachedHash>,
+}
+
+impl HashCache {
+    pub fn load(filepath: &Path) -> std::io::Result<Self> {
+        match std::fs::read(filepath) {
+            Ok(content) => bincode::decode_from_slice(&content, bincode::config::standard())
+                .map(|(cache, _)| cache)
+                .map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: {e}", filepath.display()),
+                    )
+                }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashCache::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, filepath: &Path) -> std::io::Result<()> {
+        let encoded = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        write_atomic(filepath, &encoded)
+    }
+
+    pub fn get(&self, file: &LocalFile) -> Option<&str> {
+        self.entries
+            .get(&file.relative_path)
+            .filter(|c| c.size == file.size && c.modified == file.modified)
+            .map(|c| c.md5.as_str())
+    }
+
+    pub fn insert(&mut self, file: &LocalFile, md5: String) {
+        self.entries.insert(
+            file.relative_path.clone(),
+            CachedHash {
+                size: file.size,
+                modified: file.modified,
+                md5,
+            },
+        );
+    }
+
+    /// Drops the entries of files that are gone from `usage`'s dataset.
+    pub fn retain(&mut self, usage: &DatasetUsage) {
+        let prefix = format!("{}/{}/", usage.collection, usage.dataset);
+        let present: HashSet<&str> = usage
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        self.entries
+            .retain(|path, _| !path.starts_with(&prefix) || present.contains(path.as_str()));
+    }
+
+    /// Drops the entries of mirror copies that are gone or whose mirror is no
+    /// longer one of `mirrors`.
+    pub async fn retain_mirrored(&mut self, mirrors: &Mirrors) {
+        let mut gone = Vec::new();
+        for key in self.entries.keys() {
+            let path = Path::new(key.as_str());
+            if !path.is_absolute() {
+                continue;
+            }
+            let mirrored = mirrors.roots.iter().any(|root| path.starts_with(root));
+            if !mirrored || !tokio::fs::metadata(path).await.is_ok_and(|m| m.is_file()) {
+                gone.push(key.clone());
+            }
+        }
+        for key in gone {
+            self.entries.remove(&key);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// How much of a dataset's listing is on disk and intact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Completeness {
+    pub listed: usize,
+    pub ok: usize,
+    pub mismatched: usize,
+    pub partial: usize,
+    pub missing: usize,
+    /// Listed bytes of the files that are ok.
+    pub ok_bytes: u64,
+    pub listed_bytes: u64,
+}
+
+impl Completeness {
+    /// Share of the listed bytes that are on disk and intact, by count when the
+    /// listing has no sizes.
+    pub fn fraction(&self) -> f32 {
+        if self.listed_bytes > 0 {
+            self.ok_bytes as f32 / self.listed_bytes as f32
+        } else if self.listed > 0 {
+            self.ok as f32 / self.listed as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.listed > 0 && self.ok == self.listed
+    }
+}
+
+/// Listed files matched against the dataset directory.
+#[derive(Debug, Clone, Default)]
+pub struct Reconciliation {
+    pub files: Vec<File>,
+    /// Files on disk that the listing does not mention, relative to the data
+    /// directory.
+    pub untracked: Vec<String>,
+    pub completeness: Completeness,
+}
+
+/// Fills the local fields and status of the listed `files` of a dataset, and of
+/// those extracted from its archives, from what is on disk. Files are hashed only
+/// when their size matches the listing and their size or modification time
+/// changed since they were last hashed.
+pub async fn reconcile(
+    data_directory: &Path,
+    collection: &str,
+    dataset: &str,
+    files: &[File],
+    cache: &mut HashCache,
+) -> Result<Reconciliation> {
+    let usage = DatasetUsage::scan(data_directory, collection, dataset)?;
+    let on_disk: HashMap<&str, &LocalFile> = usage
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f))
+        .collect();
+
+    let dataset_directory = data_directory.join(collection).join(dataset);
+    let mut files = files.to_vec();
+    ExtractedFiles::load(&dataset_directory)?.merge_into(&dataset_directory, &mut files);
+
+    let mut tracked: HashSet<String> = METADATA_FILES
+        .iter()
+        .chain([&EXTRACTED_FILE])
+        .map(|name| format!("{collection}/{dataset}/{name}"))
+        .collect();
+    let mut reconciliation = Reconciliation::default();
+
+    for mut file in files {
+        file.extension = file_extension(&file.remote_path);
+        file.local_size = 0;
+        file.local_md5 = String::new();
+        file.mirror = None;
+
+        let local_path = Path::new(file.local_path.as_str());
+        let relative = relative_path(data_directory, local_path).unwrap_or_default();
+        let part = relative_path(data_directory, &part_path(local_path)).unwrap_or_default();
+
+        file.status = match on_disk.get(relative.as_str()) {
+            None if on_disk.contains_key(part.as_str()) => FileStatus::Partial,
+            None => FileStatus::Missing,
+            Some(local) => {
+                file.local_size = local.size;
+                if file.remote_size > 0 && local.size != file.remote_size {
+                    FileStatus::Mismatch
+                } else {
+                    file.local_md5 = match cache.get(local) {
+                        Some(md5) => md5.to_string(),
+                        None => {
+                            let md5 = md5_file(&local.path).await?;
+                            cache.insert(local, md5.clone());
+                            md5
+                        }
+                    };
+                    hashed_status(&file)
+                }
+            }
+        };
+
+        tracked.insert(relative);
+        tracked.insert(part);
+        reconciliation.files.push(file);
+    }
+
+    cache.retain(&usage);
+    reconciliation.untracked = usage
+        .files
+        .iter()
+        .filter(|f| !tracked.contains(&f.relative_path))
+        .map(|f| f.relative_path.clone())
+        .collect();
+    reconciliation.completeness = completeness(&reconciliation.files);
+
+    Ok(reconciliation)
+}
+
+/// Points the files missing locally at an intact copy in `mirrors`, if any, and
+/// counts them as complete.
+pub async fn apply_mirrors(
+    reconciliation: &mut Reconciliation,
+    data_directory: &Path,
+    mirrors: &Mirrors,
+    cache: &mut HashCache,
+) -> Result<()> {
+    for file in reconciliation.files.iter_mut() {
+        file.mirror = None;
+        if !matches!(file.status, FileStatus::Missing | FileStatus::Partial) {
+            continue;
+        }
+        let Some(relative) = relative_path(data_directory, Path::new(file.local_path.as_str()))
+        else {
+            continue;
+        };
+        if let Some(copy) = mirrors.find_intact(&relative, file, cache).await? {
+            file.mirror = Some(copy.to_string_lossy().to_string());
+            file.status = FileStatus::Ok;
+        }
+    }
+    reconciliation.completeness = completeness(&reconciliation.files);
+    Ok(())
+}
+
+pub fn completeness(files: &[File]) -> Completeness {
+    let mut completeness = Completeness {
+        listed: files.len(),
+        ..Completeness::default()
+    };
+    for file in files {
+        completeness.listed_bytes += file.remote_size;
+        match file.status {
+            FileStatus::Ok => {
+                completeness.ok += 1;
+                completeness.ok_bytes += file.remote_size;
+            }
+            FileStatus::Mismatch => completeness.mismatched += 1,
+            FileStatus::Partial => completeness.partial += 1,
+            FileStatus::Missing => completeness.missing += 1,
+            FileStatus::Unknown => (),
+        }
+    }
+    completeness
+}
+
+/// Kind of a recording file from its name, ignoring the shank number Neuroscope
+/// appends: `ec012ec.188.res.1` is `res`, `ec012ec.11.tar.gz` is `tar.gz`.
+pub fn file_extension(filepath: &str) -> String {
+    let name = filepath.rsplit('/').next().unwrap_or(filepath);
+    let mut parts: Vec<&str> = name.split('.').skip(1).collect();
+    while parts.len() > 1 && parts.last().is_some_and(|p| p.parse::<u32>().is_ok()) {
+        parts.pop();
+    }
+    match parts.as_slice() {
+        [.., "tar", compression] => format!("tar.{compression}"),
+        [.., last] => last.to_lowercase(),
+        [] => String::new(),
+    }
+}
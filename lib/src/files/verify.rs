@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use crate::files::part;
+use crate::net::{md5_file, Result};
+use crate::types::{File, FileStatus};
+
+/// Compares the local copy of `file` with its listed size and md5, returning it
+/// with `local_size`, `local_md5` and `status` filled.
+pub async fn verify_file(file: &File) -> Result<File> {
+    let mut file = file.clone();
+    let local_path = Path::new(file.local_path.as_str());
+
+    let metadata = match tokio::fs::metadata(local_path).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let partial = part::part_len(&part::part_path(local_path)).await;
+            file.local_size = 0;
+            file.local_md5 = String::new();
+            file.status = if partial > 0 {
+                FileStatus::Partial
+            } else {
+                FileStatus::Missing
+            };
+            return Ok(file);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    file.local_size = metadata.len();
+    if file.remote_size > 0 && file.local_size != file.remote_size {
+        // No need to hash what cannot match.
+        file.local_md5 = String::new();
+        file.status = FileStatus::Mismatch;
+        return Ok(file);
+    }
+
+    file.local_md5 = md5_file(local_path).await?;
+    file.status = hashed_status(&file);
+
+    Ok(file)
+}
+
+/// Status of a present file of the listed size once `local_md5` is known.
+pub(crate) fn hashed_status(file: &File) -> FileStatus {
+    if !file.remote_md5.is_empty() {
+        if file
+            .local_md5
+            .eq_ignore_ascii_case(file.remote_md5.as_str())
+        {
+            FileStatus::Ok
+        } else {
+            FileStatus::Mismatch
+        }
+    } else if file.remote_size > 0 {
+        FileStatus::Ok
+    } else {
+        FileStatus::Unknown
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes `contents` to `path` so that readers see either the old file or the
+/// whole new one, never a truncated mix.
+///
+/// The bytes go to a temporary file next to `path` that is synced and then
+/// renamed over it, a crash midway leaves at most a stray `.tmp` file behind.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temporary = temporary_path(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+    result?;
+
+    // The rename itself is only durable once the directory is synced.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(directory) = std::fs::File::open(parent) {
+            let _ = directory.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
@@ -1,19 +1,72 @@
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use crate::gui::app::Lens;
+use crate::recording::{
+    parse_clu, BinaryRecording, Error, Features, PositionSeries, SessionParameters, SpikeTrains,
+    SpikeWaveforms, NOISE_CLUSTERS,
+};
+use crate::search::{Hit, Query, SearchIndex};
+use crate::settings::Workspace;
+use crate::storage::{self, relative_path, DiskUsage, StoragePolicy};
 use crate::types::state::SrPair;
-use crate::types::State;
-use crate::types::{Collection, Dataset, File};
+use crate::types::{Collection, Dataset, File, FileStatus, CRCNS};
+use crate::types::{Report, State};
 
 use once_cell::sync::OnceCell;
-use polars::lazy::frame::LazyFileListReader;
 use tokio::sync::mpsc;
 
 // use std::sync::Arc;
 pub static LENS: OnceCell<Lens> = OnceCell::new();
 
 pub fn get_state() -> State {
-    LENS.get().unwrap().state.clone()
+    LENS.get().unwrap().state.read().unwrap().clone()
+}
+
+/// Replaces the state with a fresh one for `workspace` and loads its catalog.
+///
+/// The previous workspace's downloads stop, their jobs stay queued in its own
+/// queue for the next time it is opened.
+pub fn switch_workspace(workspace: Workspace) -> std::io::Result<()> {
+    std::fs::create_dir_all(workspace.data_directory())?;
+
+    let state = State::for_workspace(workspace);
+    let previous = {
+        let mut current = LENS.get().unwrap().state.write().unwrap();
+        std::mem::replace(&mut *current, state.clone())
+    };
+    previous.downloads.stop();
+    let pending = std::mem::take(&mut *previous.reports.lock().unwrap());
+    state.reports.lock().unwrap().extend(pending);
+
+    state.downloads.start(state.endpoints.clone());
+    load_catalog();
+    refresh_usage();
+    Ok(())
+}
+
+/// Loads the catalog persisted in the workspace into the state, in the background.
+pub fn load_catalog() {
+    let state = get_state();
+    let data_directory = state.workspace.data_directory();
+
+    tokio::spawn(async move {
+        match CRCNS::load(data_directory).await {
+            Ok(collections) if collections.is_empty() => report(Report::Info(format!(
+                "No persisted catalog in {}",
+                state.workspace.name
+            ))),
+            Ok(collections) => {
+                let count = collections.len();
+                *state.collections.lock().unwrap() = collections;
+                reindex();
+                report(Report::Success(format!(
+                    "Loaded {count} persisted collections"
+                )));
+            }
+            Err(e) => report_error(e),
+        }
+    });
 }
 
 pub fn set_state_collection(collection: Collection) {
@@ -32,6 +85,8 @@ pub fn set_state_dataset(dataset: Dataset) {
     let state = get_state();
     let mut state_dataset = state.working_dataset.lock().unwrap();
     *state_dataset = dataset;
+    state.untracked_files.lock().unwrap().clear();
+    *state.working_session.lock().unwrap() = None;
 }
 
 pub fn get_state_dataset() -> Dataset {
@@ -45,6 +100,70 @@ pub fn set_state_dataset_files(files: Vec<File>) {
     let mut state_files = state.working_files.lock().unwrap();
     *state_files = files;
 }
+/// Replaces the working file sharing `file`'s local path, if still listed.
+pub fn set_state_dataset_file(file: File) {
+    let state = get_state();
+    let mut state_files = state.working_files.lock().unwrap();
+    if let Some(f) = state_files
+        .iter_mut()
+        .find(|f| f.local_path == file.local_path)
+    {
+        *f = file;
+    }
+}
+pub fn set_state_untracked_files(untracked: Vec<String>) {
+    let state = get_state();
+    *state.untracked_files.lock().unwrap() = untracked;
+}
+
+pub fn get_state_untracked_files() -> Vec<String> {
+    get_state().untracked_files.lock().unwrap().clone()
+}
+
+/// Adds `files` to the working files, replacing those with the same local path.
+pub fn add_state_dataset_files(files: Vec<File>) {
+    let state = get_state();
+    let mut state_files = state.working_files.lock().unwrap();
+    for file in files {
+        match state_files
+            .iter_mut()
+            .find(|f| f.local_path == file.local_path)
+        {
+            Some(state_file) => *state_file = file,
+            None => state_files.push(file),
+        }
+    }
+}
+
+pub fn open_archive(archive: File) {
+    *get_state().opened_archive.lock().unwrap() = Some(archive);
+}
+
+pub fn take_opened_archive() -> Option<File> {
+    get_state().opened_archive.lock().unwrap().take()
+}
+
+pub fn open_plan(collection: Collection, dataset: Dataset) {
+    *get_state().opened_plan.lock().unwrap() = Some((collection, dataset));
+}
+
+pub fn take_opened_plan() -> Option<(Collection, Dataset)> {
+    get_state().opened_plan.lock().unwrap().take()
+}
+
+pub fn set_progress(key: &str, progress: Option<f32>) {
+    let state = get_state();
+    let mut state_progress = state.progress.lock().unwrap();
+    match progress {
+        Some(progress) => state_progress.insert(key.to_string(), progress),
+        None => state_progress.remove(key),
+    };
+}
+
+pub fn get_progress(key: &str) -> Option<f32> {
+    get_state().progress.lock().unwrap().get(key).copied()
+}
+
 pub fn get_state_dataset_files() -> Vec<File> {
     let state = get_state();
     let state_files_mutex = state.working_files.lock().unwrap();
@@ -63,105 +182,437 @@ pub fn add_or_get_progress(key: String) -> Arc<Mutex<SrPair>> {
     sr_map.get(&key).unwrap().clone()
 }
 
-pub fn set_state_lfp_series() {
-    use memmap2::MmapOptions;
-    use ndarray::{s, Array2};
+/// Queues `report` for the GUI, falls back to the log when no GUI is running.
+pub fn report(report: Report) {
+    match LENS.get() {
+        Some(lens) => lens
+            .state
+            .read()
+            .unwrap()
+            .reports
+            .lock()
+            .unwrap()
+            .push(report),
+        None => match report {
+            Report::Error(m) => log::error!("{m}"),
+            Report::Warning(m) => log::warn!("{m}"),
+            Report::Info(m) | Report::Success(m) => log::info!("{m}"),
+        },
+    }
+}
+
+pub fn report_error(e: impl std::fmt::Display) {
+    report(Report::Error(e.to_string()));
+}
 
+pub fn take_reports() -> Vec<Report> {
     let state = get_state();
-    let filepath = state.working_directory.clone();
-    let filepath = filepath.join("data/hc/hc-3/ec012ec.11/ec012ec.188/ec012ec.188.eeg");
-    let file = std::fs::File::open(filepath).unwrap();
-    let file_size = file.metadata().unwrap().len();
-    let n_channels: usize = 33;
-    let srate: usize = 1250;
-    let n_samples: usize = (file_size as f64 / 2.0 / n_channels as f64) as usize;
+    let mut reports = state.reports.lock().unwrap();
+    std::mem::take(&mut *reports)
+}
 
-    let mmo = MmapOptions::new();
-    let mmap = unsafe { mmo.map(&file).unwrap() };
+/// Rebuilds the search index from the collections currently in the state.
+pub fn reindex() {
+    let state = get_state();
+    let collections = state.collections.lock().unwrap().clone();
+    let index = SearchIndex::build(&collections);
+    *state.search.lock().unwrap() = index;
+}
 
-    // Interpret the memory-mapped file as a slice of i16
-    let data: &[i16] =
-        unsafe { std::slice::from_raw_parts(mmap.as_ptr() as *const i16, n_samples * n_channels) };
+pub fn search(text: &str, limit: usize) -> Vec<Hit> {
+    let state = get_state();
+    let index = state.search.lock().unwrap();
+    index.search(&Query::parse(text), limit)
+}
 
-    // Convert the slice to a 2D array
-    let array = Array2::from_shape_vec((n_samples, n_channels), data.to_vec()).unwrap();
+/// Selects the collection and dataset of a search hit, as if they were opened
+/// in the collections panel.
+pub fn select_hit(hit: &Hit) {
+    let state = get_state();
+    let collections = state.collections.lock().unwrap().clone();
+    let Some(collection) = collections.into_iter().find(|c| c.alias == hit.collection) else {
+        return;
+    };
+    let dataset = hit
+        .dataset
+        .as_ref()
+        .and_then(|alias| {
+            let datasets = collection.datasets.lock().unwrap();
+            datasets.iter().find(|d| &d.alias == alias).cloned()
+        })
+        .unwrap_or_default();
+
+    set_state_collection(collection);
+    set_state_dataset(dataset);
+}
 
-    let s0 = 0;
-    let sf = s0 + srate * 3;
+fn save_storage(state: &State, storage: &StoragePolicy) {
+    let filepath = state.workspace.data_directory().join("storage.bin");
+    if let Err(e) = storage.save(&filepath) {
+        report_error(format!("Unable to save {}: {e}", filepath.display()));
+    }
+}
 
-    let ch: usize = 0;
+/// Records that `path` was just downloaded or read, for the LRU eviction.
+pub fn touch_file(path: &Path) {
+    let Some(lens) = LENS.get() else {
+        return;
+    };
+    let state = lens.state.read().unwrap().clone();
+    let Some(relative) = relative_path(&state.workspace.data_directory(), path) else {
+        return;
+    };
+    let mut storage = state.storage.lock().unwrap();
+    storage.touch(&relative, chrono::Utc::now().timestamp());
+    save_storage(&state, &storage);
+}
 
-    let slice = array.slice(s![s0..sf, ch]);
+pub fn set_pinned(path: &Path, pinned: bool) {
+    let state = get_state();
+    let Some(relative) = relative_path(&state.workspace.data_directory(), path) else {
+        return;
+    };
+    let mut storage = state.storage.lock().unwrap();
+    storage.set_pinned(&relative, pinned);
+    save_storage(&state, &storage);
+}
 
+pub fn is_pinned(path: &Path) -> bool {
     let state = get_state();
-    let mut series = state.lfp_series.lock().unwrap();
-    *series = Vec::new();
+    relative_path(&state.workspace.data_directory(), path)
+        .is_some_and(|relative| state.storage.lock().unwrap().is_pinned(&relative))
+}
 
-    for (s, v) in slice.into_iter().enumerate() {
-        series.push([s as f64, *v as f64]); //cannot index like thys
-    }
+pub fn set_quota(quota: Option<u64>) {
+    let state = get_state();
+    let mut storage = state.storage.lock().unwrap();
+    storage.quota = quota;
+    save_storage(&state, &storage);
 }
 
-pub fn set_state_spk_series() {
-    use memmap2::MmapOptions;
-    use ndarray::{s, Array3, Axis};
+/// Scans the data directory again in the background.
+pub fn refresh_usage() {
+    let state = get_state();
+    tokio::task::spawn_blocking(
+        move || match DiskUsage::scan(&state.workspace.data_directory()) {
+            Ok(usage) => *state.usage.lock().unwrap() = usage,
+            Err(e) => report_error(format!("Unable to measure disk usage: {e}")),
+        },
+    );
+}
+
+/// Evicts the least recently used files while the datasets exceed the quota,
+/// never the files in `keep`.
+pub fn enforce_quota(keep: Vec<PathBuf>) {
+    let Some(lens) = LENS.get() else {
+        return;
+    };
+    let state = lens.state.read().unwrap().clone();
+    tokio::task::spawn_blocking(move || {
+        let data_directory = state.workspace.data_directory();
+        let keep: Vec<String> = keep
+            .iter()
+            .filter_map(|p| relative_path(&data_directory, p))
+            .collect();
+
+        let result = DiskUsage::scan(&data_directory).and_then(|usage| {
+            let mut storage = state.storage.lock().unwrap();
+            let evicted = storage::evict_to_quota(&usage, &mut storage, &keep)?;
+            if !evicted.is_empty() {
+                save_storage(&state, &storage);
+            }
+            Ok(evicted)
+        });
+        match result {
+            Ok(evicted) if evicted.is_empty() => (),
+            Ok(evicted) => {
+                let freed: u64 = evicted.iter().map(|f| f.size).sum();
+                report(Report::Info(format!(
+                    "Quota exceeded, evicted {} files ({} bytes)",
+                    evicted.len(),
+                    freed
+                )));
+            }
+            Err(e) => report_error(format!("Unable to enforce the quota: {e}")),
+        }
+        refresh_usage();
+    });
+}
 
+/// Removes the raw files of a dataset, keeping listings, parameters and pins.
+pub fn free_dataset(collection: String, dataset: String) {
     let state = get_state();
-    let filepath = state.working_directory.clone();
-    let filepath = filepath.join("data/hc/hc-3/ec012ec.11/ec012ec.188/ec012ec.188.spk.1");
-    let file = std::fs::File::open(filepath).unwrap();
-    let file_size = file.metadata().unwrap().len();
+    tokio::task::spawn_blocking(move || {
+        let result = DiskUsage::scan(&state.workspace.data_directory()).and_then(|usage| {
+            let Some(usage) = usage.dataset(&collection, &dataset) else {
+                return Ok(0);
+            };
+            let mut storage = state.storage.lock().unwrap();
+            let freed = storage::free_dataset(usage, &mut storage)?;
+            save_storage(&state, &storage);
+            Ok(freed)
+        });
+        match result {
+            Ok(freed) => report(Report::Success(format!(
+                "Freed {freed} bytes from {collection}/{dataset}"
+            ))),
+            Err(e) => report_error(format!("Unable to free {collection}/{dataset}: {e}")),
+        }
+        refresh_usage();
+    });
+}
 
-    let n_channels: usize = 8;
-    let n_samples: usize = 32;
-    let n_spikes: usize = (file_size as f64 / 2.0 / n_samples as f64 / n_channels as f64) as usize;
+/// Where to read `relative_path` of the data directory from: the local copy, the
+/// mirror copy the last reconciliation verified, or the local path to download it
+/// to when there is neither.
+pub fn resolve_data_path(relative_path: &str) -> PathBuf {
+    let state = get_state();
+    let data_directory = state.workspace.data_directory();
+    let verified = state.verified_copies.lock().unwrap();
+    storage::resolve(&data_directory, &verified, relative_path)
+        .unwrap_or_else(|| data_directory.join(relative_path))
+}
 
-    let mmo = MmapOptions::new();
-    let mmap = unsafe { mmo.map(&file).unwrap() };
+/// Remembers the mirror copies the reconciled `files` of a dataset point at.
+pub fn set_verified_copies(collection: &str, dataset: &str, files: &[File]) {
+    let state = get_state();
+    state.verified_copies.lock().unwrap().update(
+        &state.workspace.data_directory(),
+        collection,
+        dataset,
+        files,
+    );
+}
 
-    let data: &[i16] = unsafe {
-        std::slice::from_raw_parts(
-            mmap.as_ptr() as *const i16,
-            n_spikes * n_samples * n_channels,
-        )
+/// Copies the intact local `file` into the first mirror of the workspace.
+pub fn promote_to_mirror(file: File) {
+    let workspace = get_state().workspace;
+    let Some(root) = workspace.mirrors.first().cloned() else {
+        return;
     };
+    let local_path = PathBuf::from(file.local_path.as_str());
+    let Some(relative) = relative_path(&workspace.data_directory(), &local_path) else {
+        return;
+    };
+    tokio::spawn(async move {
+        match storage::promote(&local_path, &root, &relative).await {
+            Ok(target) => report(Report::Success(format!(
+                "Promoted {} to {}",
+                file.remote_path,
+                target.display()
+            ))),
+            Err(e) => report_error(format!("Unable to promote {}: {e}", file.remote_path)),
+        }
+    });
+}
 
-    let array = Array3::from_shape_vec((n_spikes, n_samples, n_channels), data.to_vec()).unwrap();
-
-    let slice = array.slice(s![0..100, .., ..]);
+/// Picks the session the plots read, `xml` being its parameters file.
+pub fn select_session(xml: &File) {
+    let state = get_state();
+    let Some(relative) = relative_path(
+        &state.workspace.data_directory(),
+        Path::new(xml.local_path.as_str()),
+    ) else {
+        return;
+    };
+    *state.working_session.lock().unwrap() = Some(relative);
+}
 
+/// The `.xml` of the session the plots read, relative to the data directory: the
+/// one picked in the file list, otherwise the first intact one of the working
+/// dataset.
+pub fn working_session() -> Option<String> {
     let state = get_state();
-    let mut series = state.spk_series.lock().unwrap();
-    *series = Vec::new();
+    if let Some(selected) = state.working_session.lock().unwrap().clone() {
+        return Some(selected);
+    }
+    let data_directory = state.workspace.data_directory();
+    let files = state.working_files.lock().unwrap();
+    files
+        .iter()
+        .filter(|f| f.extension == "xml" && f.status == FileStatus::Ok)
+        .filter_map(|f| relative_path(&data_directory, Path::new(f.local_path.as_str())))
+        .min()
+}
 
-    for s in 0..100 {
-        let spk_slice = slice.index_axis(Axis(0), s);
-        let spk_slice = spk_slice.t();
-        let mut spk_series = Vec::new();
-        for (s, v) in spk_slice.into_iter().enumerate() {
-            spk_series.push([s as f64, *v as f64]);
+/// The path the files of the working session share before their extension,
+/// reporting when there is no session.
+fn working_session_base() -> Option<String> {
+    match working_session() {
+        Some(xml) => Some(xml.strip_suffix(".xml").unwrap_or(xml.as_str()).to_string()),
+        None => {
+            report_error("No session to read, download the .xml of one first");
+            None
         }
-        series.push(spk_series);
     }
 }
 
-pub fn set_state_fet_series() {
-    use polars::prelude::LazyCsvReader;
+/// Parameters of the working session with the path its files share before their
+/// extension, reporting why they are unavailable.
+pub fn session_parameters() -> Option<(String, SessionParameters)> {
+    let session = working_session_base()?;
+    SessionParameters::read(&session_file(&session, "xml"))
+        .map(|parameters| (session, parameters))
+        .map_err(report_error)
+        .ok()
+}
+
+/// The `extension` file of the session `base`, see [`resolve_data_path`].
+pub fn session_file(base: &str, extension: &str) -> PathBuf {
+    resolve_data_path(&format!("{base}.{extension}"))
+}
+
+pub fn set_state_lfp_series() {
+    let Some((session, parameters)) = session_parameters() else {
+        return;
+    };
+    let filepath = session_file(&session, "eeg");
+    let recording = match BinaryRecording::open(&filepath, &parameters) {
+        Ok(recording) => recording,
+        Err(e) => return report_error(e),
+    };
+    touch_file(&filepath);
+    if recording.is_truncated() {
+        report(Report::Warning(format!(
+            "{} ends with {} bytes of an incomplete sample, they are ignored",
+            filepath.display(),
+            recording.trailing_bytes()
+        )));
+    }
+
+    let channel = parameters
+        .anatomical_groups
+        .iter()
+        .flat_map(|g| g.channels.iter().copied())
+        .find(|c| !parameters.is_skipped(*c))
+        .unwrap_or(0);
+    let samples = recording.sample_range(0.0, 3.0);
+    let start = samples.start;
+    let window = match recording.format().microvolts_per_bit {
+        Some(_) => recording.read_microvolts(samples, &[channel]),
+        None => recording.read(samples, &[channel]),
+    };
+    let window = match window {
+        Ok(window) => window,
+        Err(e) => return report_error(e),
+    };
+
+    let sampling_rate = recording.format().sampling_rate;
+    let state = get_state();
+    let mut series = state.lfp_series.lock().unwrap();
+    *series = window
+        .column(0)
+        .iter()
+        .enumerate()
+        .map(|(s, v)| [(start + s) as f64 / sampling_rate, *v])
+        .collect();
+}
+
+/// Waveforms of the first sorted unit of shank 1, or of its first spikes when the
+/// shank is not clustered.
+pub fn set_state_spk_series() {
+    let Some((session, parameters)) = session_parameters() else {
+        return;
+    };
+    let filepath = session_file(&session, "spk.1");
+    let Some(group) = parameters.spike_group(1) else {
+        report_error(format!(
+            "Unable to read {}: the session has no spike group 1",
+            filepath.display()
+        ));
+        return;
+    };
+    let waveforms = match SpikeWaveforms::open(&filepath, group, parameters.sampling_rate) {
+        Ok(waveforms) => waveforms,
+        Err(e) => return report_error(e),
+    };
+    touch_file(&filepath);
+
+    let mut trains = SpikeTrains::default();
+    let read = trains.read_shank(&parameters, 1, &NOISE_CLUSTERS, |extension| {
+        session_file(&session, extension)
+    });
+    if !trains.missing_shanks.is_empty() {
+        report(Report::Warning(format!(
+            "{session}: no .res and .clu for shank 1"
+        )));
+    }
+    let spikes: Vec<usize> = match read.map(|()| trains.shank(1).next()) {
+        Ok(Some((_, train))) => train.spike_indices.iter().copied().take(100).collect(),
+        Ok(None) => (0..waveforms.n_spikes().min(100)).collect(),
+        Err(e) => {
+            report(Report::Warning(format!("Showing unsorted spikes: {e}")));
+            (0..waveforms.n_spikes().min(100)).collect()
+        }
+    };
+
+    let mut spk_series = Vec::new();
+    for spike in spikes {
+        let waveform = match waveforms.waveform(spike) {
+            Ok(waveform) => waveform,
+            Err(e) => return report_error(e),
+        };
+        // Channels one after the other.
+        let line = waveform
+            .t()
+            .iter()
+            .enumerate()
+            .map(|(s, v)| [s as f64, *v as f64])
+            .collect();
+        spk_series.push(line);
+    }
 
     let state = get_state();
-    let filepath = state.working_directory.clone();
-    let filepath = filepath.join("data/hc/hc-3/ec012ec.11/ec012ec.188/ec012ec.188.fet.1");
+    *state.spk_series.lock().unwrap() = spk_series;
+}
 
-    let file_df = LazyCsvReader::new(filepath)
-        .with_has_header(false)
-        .with_skip_rows(1)
-        .with_separator(b' ')
-        .finish()
-        .unwrap()
-        .collect()
-        .unwrap();
+pub fn set_state_position_series() {
+    let Some(session) = working_session_base() else {
+        return;
+    };
+    let filepath = session_file(&session, "whl");
+    match PositionSeries::read(&filepath) {
+        Ok(series) => {
+            touch_file(&filepath);
+            *get_state().position_series.lock().unwrap() = Arc::new(series);
+        }
+        Err(e) => report_error(e),
+    }
+}
 
-    // std::iter::zip(file_df[0].iter(), file_df[1].iter()).map(|(a, b)| [a, b.cast(f64)]);
+/// Features of shank 1 labelled with its clusters, when its `.clu` is on disk.
+pub fn set_state_fet_series() {
+    let Some((session, parameters)) = session_parameters() else {
+        return;
+    };
+    let filepath = session_file(&session, "fet.1");
+    let Some(group) = parameters.spike_group(1) else {
+        report_error(format!(
+            "Unable to read {}: the session has no spike group 1",
+            filepath.display()
+        ));
+        return;
+    };
+    let mut features = match Features::read(&filepath, group) {
+        Ok(features) => features,
+        Err(e) => return report_error(e),
+    };
+    touch_file(&filepath);
+
+    let clu = session_file(&session, "clu.1");
+    let clusters = std::fs::read_to_string(&clu)
+        .map_err(|e| Error::io(&clu, e))
+        .and_then(|content| parse_clu(&content, &clu));
+    match clusters {
+        Ok(clusters) => {
+            if let Err(reason) = features.set_clusters(clusters) {
+                report(Report::Warning(format!("{}: {reason}", clu.display())));
+            }
+        }
+        Err(e) => report(Report::Warning(format!(
+            "Showing unclustered features: {e}"
+        ))),
+    }
 
-    println!("{}", file_df);
+    *get_state().fet_series.lock().unwrap() = Arc::new(features);
 }
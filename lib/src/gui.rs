@@ -3,3 +3,4 @@ pub mod misc;
 pub mod panel;
 pub mod traits;
 pub mod types;
+pub mod window;
@@ -2,20 +2,21 @@ use crate::gui::app;
 
 use crate::types::State;
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::runtime::Runtime;
 
 #[derive(Clone)]
 pub struct Lens {
     pub trt: Arc<Mutex<Runtime>>,
     pub gui: Arc<Mutex<app::Main>>,
-    pub state: State,
+    /// Replaced as a whole when another workspace is opened.
+    pub state: Arc<RwLock<State>>,
 }
 
 impl Lens {
-    pub fn new(trt: Arc<Mutex<Runtime>>) -> Self {
+    pub fn new(trt: Arc<Mutex<Runtime>>, state: State) -> Self {
         let gui = Arc::new(Mutex::new(app::Main::default()));
-        let state = State::default();
+        let state = Arc::new(RwLock::new(state));
         Lens { trt, gui, state }
     }
 }
@@ -1,16 +1,78 @@
 use crate::global;
 
 use crate::gui::misc::toasts;
-use crate::gui::panel::CollectionPanel;
+use crate::gui::panel::{CollectionPanel, DownloadPanel};
 use crate::gui::traits::View;
+use crate::gui::window::{ArchiveWindow, LoginWindow, PlanWindow, WorkspaceWindow};
 
-use crate::types::CRCNS;
+use crate::catalog::Snapshot;
+use crate::credentials;
+use crate::recording::{Features, PositionSeries};
+use crate::types::{Report, CRCNS};
 
 use std::sync::Arc;
 
+/// What the trajectory plot colors its points by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrajectoryColor {
+    #[default]
+    Time,
+    Speed,
+}
+
+/// Points of two feature columns by cluster, kept until other features are loaded
+/// or other columns picked.
+#[derive(Clone, Default)]
+struct FeatureScatter {
+    features: Arc<Features>,
+    columns: (usize, usize),
+    clusters: Vec<(u32, Vec<[f64; 2]>)>,
+}
+
+impl FeatureScatter {
+    fn update(&mut self, features: Arc<Features>, columns: (usize, usize)) {
+        if !Arc::ptr_eq(&self.features, &features) || self.columns != columns {
+            self.clusters = features.scatter(columns.0, columns.1);
+            self.features = features;
+            self.columns = columns;
+        }
+    }
+}
+
+/// Colored points of the trajectory, kept until another series is loaded or the
+/// coloring changes.
+#[derive(Clone, Default)]
+struct TrajectoryPoints {
+    series: Arc<PositionSeries>,
+    color: TrajectoryColor,
+    steps: Vec<(egui::Color32, Vec<[f64; 2]>)>,
+}
+
+impl TrajectoryPoints {
+    fn update(&mut self, series: Arc<PositionSeries>, color: TrajectoryColor) {
+        if !Arc::ptr_eq(&self.series, &series) || self.color != color {
+            self.steps = trajectory(&series, color);
+            self.series = series;
+            self.color = color;
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Main {
     pub toasts: toasts::Toasts,
+    pub login: LoginWindow,
+    pub workspaces: WorkspaceWindow,
+    pub archive: ArchiveWindow,
+    pub plan: PlanWindow,
+    pub collections: CollectionPanel,
+    /// Where catalog snapshots are exported to and imported from.
+    pub snapshot_path: String,
+    pub trajectory_color: TrajectoryColor,
+    trajectory: TrajectoryPoints,
+    /// Columns of the features plotted against each other.
+    pub features: (usize, usize),
+    fet_scatter: FeatureScatter,
     pub is_visible: bool,
 }
 
@@ -22,16 +84,158 @@ impl Default for Main {
 
         Main {
             toasts,
+            login: LoginWindow::default(),
+            workspaces: WorkspaceWindow::default(),
+            archive: ArchiveWindow::default(),
+            plan: PlanWindow::default(),
+            collections: CollectionPanel::default(),
+            snapshot_path: "crcns-snapshot.bin".to_string(),
+            trajectory_color: TrajectoryColor::default(),
+            trajectory: TrajectoryPoints::default(),
+            features: (0, 1),
+            fet_scatter: FeatureScatter::default(),
             is_visible: true,
         }
     }
 }
 
 impl Main {
+    pub fn toast(&mut self, report: Report) {
+        let (kind, duration) = match report {
+            Report::Info(_) => (toasts::ToastKind::Info, 5.0),
+            Report::Success(_) => (toasts::ToastKind::Success, 5.0),
+            Report::Warning(_) => (toasts::ToastKind::Warning, 10.0),
+            Report::Error(_) => (toasts::ToastKind::Error, 15.0),
+        };
+        self.toasts.add(toasts::Toast {
+            text: report.message().to_string().into(),
+            kind,
+            options: toasts::ToastOptions::default()
+                .duration_in_seconds(duration)
+                .show_progress(true)
+                .show_icon(true),
+        });
+    }
+
+    fn snapshot(&mut self, ui: &mut egui::Ui) {
+        let state = global::get_state();
+
+        ui.horizontal(|ui| {
+            ui.label("Snapshot");
+            ui.text_edit_singleline(&mut self.snapshot_path);
+
+            let path = state.working_directory.join(self.snapshot_path.as_str());
+            let data_directory = state.workspace.data_directory();
+
+            if ui.button("Export").clicked() {
+                let collections = state.collections.clone();
+                let (path, data_directory) = (path.clone(), data_directory.clone());
+                tokio::task::spawn_blocking(move || {
+                    let collections = collections.lock().unwrap().clone();
+                    let result = Snapshot::capture(&collections, &data_directory)
+                        .and_then(|snapshot| snapshot.export(&path));
+                    match result {
+                        Ok(()) => global::report(Report::Success(format!(
+                            "Catalog exported to {}",
+                            path.display()
+                        ))),
+                        Err(e) => global::report_error(e),
+                    }
+                });
+            }
+
+            if ui.button("Import").clicked() {
+                let collections = state.collections.clone();
+                tokio::spawn(async move {
+                    let directory = data_directory.clone();
+                    let imported = tokio::task::spawn_blocking(move || {
+                        Snapshot::read(&path)?.import(&directory)
+                    })
+                    .await
+                    .expect("snapshot import panicked");
+
+                    let result = match imported {
+                        Ok(report) => CRCNS::load(data_directory).await.map(|c| (report, c)),
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok((report, loaded)) => {
+                            *collections.lock().unwrap() = loaded;
+                            global::reindex();
+                            global::report(Report::Success(format!("Snapshot imported: {report}")));
+                        }
+                        Err(e) => global::report_error(e),
+                    }
+                });
+            }
+        });
+    }
+
+    fn features(&mut self, ui: &mut egui::Ui) {
+        let features = global::get_state().fet_series.lock().unwrap().clone();
+
+        ui.horizontal(|ui| {
+            if ui.button("Features").clicked() {
+                global::set_state_fet_series();
+            }
+            for (id, column) in [
+                ("fet_x", &mut self.features.0),
+                ("fet_y", &mut self.features.1),
+            ] {
+                let selected = features.columns.get(*column).cloned().unwrap_or_default();
+                egui::ComboBox::from_id_source(id)
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (i, name) in features.columns.iter().enumerate() {
+                            ui.selectable_value(column, i, name);
+                        }
+                    });
+            }
+        });
+        self.fet_scatter.update(features, self.features);
+
+        ui.allocate_ui_with_layout(
+            egui::Vec2::new(400.0, 400.0),
+            egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
+            |ui| {
+                egui_plot::Plot::new("fet_plot")
+                    .allow_zoom(true)
+                    .allow_drag(true)
+                    .allow_scroll(true)
+                    .allow_boxed_zoom(true)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |plot_ui| {
+                        for (cluster, points) in &self.fet_scatter.clusters {
+                            plot_ui.points(
+                                egui_plot::Points::new(points.clone())
+                                    .radius(1.0)
+                                    .name(format!("cluster {cluster}")),
+                            );
+                        }
+                    });
+            },
+        );
+    }
+
     pub fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let state = global::get_state();
 
-        CollectionPanel::default().update(ctx, _frame);
+        for report in global::take_reports() {
+            self.toast(report);
+        }
+
+        self.collections.update(ctx, _frame);
+        DownloadPanel::default().update(ctx, _frame);
+        self.login.update(ctx, _frame);
+        self.workspaces.update(ctx, _frame);
+        if let Some(archive) = global::take_opened_archive() {
+            self.archive.open(archive);
+        }
+        self.archive.update(ctx, _frame);
+        if let Some((collection, dataset)) = global::take_opened_plan() {
+            self.plan.open(collection, dataset);
+        }
+        self.plan.update(ctx, _frame);
 
         let layout = egui::Layout::top_down(egui::Align::Center);
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -39,16 +243,38 @@ impl Main {
                 egui::ScrollArea::both().show(ui, |ui| {
                     ui.heading("CRCNS - Lens");
 
-                    ui.label(state.working_directory.to_str().unwrap());
+                    let workspace = format!(
+                        "📁 {} ({})",
+                        state.workspace.name,
+                        state.working_directory.display()
+                    );
+                    if ui.button(workspace).clicked() {
+                        self.workspaces.is_open = true;
+                    }
 
-                    if ui.button("Print dataframe").clicked() {
-                        global::set_state_fet_series();
+                    let account = credentials::current()
+                        .map(|c| format!("🔑 {}", c.username))
+                        .unwrap_or_else(|| "🔑 Log in".to_string());
+                    if ui.button(account).clicked() {
+                        self.login.is_open = true;
                     }
+
                     if ui.button("Get CRCNS").clicked() {
                         let c = state.collections.clone();
+                        let e = state.endpoints.clone();
 
                         tokio::spawn(async move {
-                            CRCNS::get(c).await;
+                            let result = CRCNS::get(e, c).await;
+                            global::reindex();
+                            match result {
+                                Ok(report) if report.has_changes() => global::report(
+                                    Report::Success(format!("Catalog synced: {report}")),
+                                ),
+                                Ok(_) => global::report(Report::Info(
+                                    "Catalog is up to date".to_string(),
+                                )),
+                                Err(e) => global::report_error(e),
+                            }
                         });
                     }
                     ui.label(format!(
@@ -58,12 +284,19 @@ impl Main {
 
                     if ui.button("Persist CRCNS").clicked() {
                         let c = state.collections.clone();
-                        let dd = state.working_directory.clone().join("data");
+                        let dd = state.workspace.data_directory();
                         tokio::spawn(async move {
-                            CRCNS::persist(c, dd).await;
+                            match CRCNS::persist(c, dd).await {
+                                Ok(()) => {
+                                    global::report(Report::Success("Catalog persisted".to_string()))
+                                }
+                                Err(e) => global::report_error(e),
+                            }
                         });
                     };
 
+                    self.snapshot(ui);
+
                     if ui.button("Add toast").clicked() {
                         self.toasts.add(toasts::Toast {
                             text: "Hello, World".into(),
@@ -85,6 +318,9 @@ impl Main {
                     // }
 
                     ui.label(state.working_dataset.lock().unwrap().alias.clone());
+                    if let Some(session) = global::working_session() {
+                        ui.weak(session);
+                    }
 
                     if ui.button("Spike").clicked() {
                         global::set_state_spk_series();
@@ -109,6 +345,8 @@ impl Main {
                         },
                     );
 
+                    self.features(ui);
+
                     if ui.button("LFP").clicked() {
                         global::set_state_lfp_series();
                     }
@@ -129,9 +367,90 @@ impl Main {
                         },
                     );
 
+                    ui.horizontal(|ui| {
+                        if ui.button("Position").clicked() {
+                            global::set_state_position_series();
+                        }
+                        ui.label("Color by");
+                        ui.radio_value(&mut self.trajectory_color, TrajectoryColor::Time, "time");
+                        ui.radio_value(&mut self.trajectory_color, TrajectoryColor::Speed, "speed");
+                    });
+                    ui.allocate_ui_with_layout(
+                        egui::Vec2::new(400.0, 400.0),
+                        egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
+                        |ui| {
+                            let series = state.position_series.lock().unwrap().clone();
+                            self.trajectory.update(series, self.trajectory_color);
+                            egui_plot::Plot::new("trajectory_plot")
+                                .data_aspect(1.0)
+                                .allow_zoom(true)
+                                .allow_drag(true)
+                                .allow_scroll(true)
+                                .allow_boxed_zoom(true)
+                                .show(ui, |plot_ui| {
+                                    for (color, points) in &self.trajectory.steps {
+                                        plot_ui.points(
+                                            egui_plot::Points::new(points.clone())
+                                                .radius(1.5)
+                                                .color(*color),
+                                        );
+                                    }
+                                });
+                        },
+                    );
+
                     self.toasts.show(ctx);
                 });
             })
         });
     }
 }
+
+/// Points of the trajectory in a few color steps, from blue at the start or at rest
+/// to yellow at the end or at the top speed.
+fn trajectory(
+    series: &PositionSeries,
+    color: TrajectoryColor,
+) -> Vec<(egui::Color32, Vec<[f64; 2]>)> {
+    const STEPS: usize = 16;
+    let max = match color {
+        TrajectoryColor::Time => series.duration(),
+        TrajectoryColor::Speed => series.max_speed().unwrap_or(0.0),
+    };
+    let mut steps: Vec<Vec<[f64; 2]>> = vec![Vec::new(); STEPS];
+    for sample in &series.samples {
+        let Some(position) = sample.position else {
+            continue;
+        };
+        let value = match color {
+            TrajectoryColor::Time => sample.time,
+            TrajectoryColor::Speed => sample.speed.unwrap_or(0.0),
+        };
+        let step = if max > 0.0 {
+            ((value / max * STEPS as f64) as usize).min(STEPS - 1)
+        } else {
+            0
+        };
+        steps[step].push(position);
+    }
+
+    let (low, high) = (
+        egui::Color32::from_rgb(40, 60, 200),
+        egui::Color32::from_rgb(250, 220, 40),
+    );
+    steps
+        .into_iter()
+        .enumerate()
+        .filter(|(_, points)| !points.is_empty())
+        .map(|(step, points)| {
+            let t = step as f32 / (STEPS - 1) as f32;
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+            let color = egui::Color32::from_rgb(
+                mix(low.r(), high.r()),
+                mix(low.g(), high.g()),
+                mix(low.b(), high.b()),
+            );
+            (color, points)
+        })
+        .collect()
+}
@@ -310,7 +310,6 @@ impl Toast {
 }
 
 #[doc(hidden)]
-
 pub(crate) const TOAST_WIDTH: f32 = 180.;
 pub(crate) const TOAST_HEIGHT: f32 = 34.;
 
@@ -322,7 +321,7 @@ const SUCCESS_COLOR: Color32 = Color32::from_rgb(140, 230, 140);
 /// Main notifications collector.
 /// # Usage
 /// You need to create [`Toasts`] once and call `.show(ctx)` in every frame.
-/// ```
+/// ```ignore
 /// # use std::time::Duration;
 /// use egui_notify::Toasts;
 ///
@@ -367,11 +366,11 @@ impl Toasts {
     pub fn add(&mut self, toast: Toast) -> &mut Toast {
         if self.reverse {
             self.toasts.insert(0, toast);
-            return self.toasts.get_mut(0).unwrap();
+            self.toasts.get_mut(0).unwrap()
         } else {
             self.toasts.push(toast);
             let l = self.toasts.len() - 1;
-            return self.toasts.get_mut(l).unwrap();
+            self.toasts.get_mut(l).unwrap()
         }
     }
 
@@ -1,4 +1,6 @@
 pub mod collections;
 pub mod datasets;
+pub mod downloads;
 
 pub use collections::CollectionPanel;
+pub use downloads::DownloadPanel;
@@ -5,9 +5,14 @@ use crate::global;
 use crate::gui::traits;
 use crate::types::Collection;
 
+/// Hits listed under the search box.
+const SEARCH_LIMIT: usize = 20;
+
+#[derive(Clone)]
 pub struct CollectionPanel {
     pub is_open: bool,
     pub collection: Collection,
+    pub query: String,
 }
 
 impl Default for CollectionPanel {
@@ -15,6 +20,7 @@ impl Default for CollectionPanel {
         Self {
             is_open: true,
             collection: Collection::default(),
+            query: String::new(),
         }
     }
 }
@@ -34,6 +40,8 @@ impl traits::View for CollectionPanel {
 
                 ui.separator();
 
+                self.search(ui);
+
                 let current_collection = global::get_state_collection();
                 let layout = egui::Layout::top_down(egui::Align::RIGHT);
                 ui.allocate_ui_with_layout(ui.available_size(), layout, |ui| {
@@ -79,6 +87,40 @@ impl traits::View for CollectionPanel {
     }
 }
 
+impl CollectionPanel {
+    fn search(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .hint_text("🔍 Search: words, prefix*, \"a phrase\"")
+                .desired_width(f32::INFINITY),
+        );
+
+        if self.query.trim().is_empty() {
+            return;
+        }
+
+        let hits = global::search(self.query.as_str(), SEARCH_LIMIT);
+        if hits.is_empty() {
+            ui.weak("No matches");
+        }
+        for hit in hits {
+            let label = match hit.dataset.as_ref() {
+                Some(dataset) => format!("{}/{}", hit.collection, dataset),
+                None => hit.collection.clone(),
+            };
+            let response = ui
+                .selectable_label(false, label)
+                .on_hover_text(format!("Score {:.2}", hit.score));
+            if response.clicked() {
+                global::select_hit(&hit);
+                self.query.clear();
+            }
+        }
+
+        ui.separator();
+    }
+}
+
 impl traits::Display for CollectionPanel {
     fn name(&self) -> &'static str {
         ""
@@ -1,5 +1,6 @@
 use crate::global;
 use crate::gui::panel::datasets;
+use crate::gui::panel::downloads::human_bytes;
 use crate::types::collection::Collection;
 use crate::types::Dataset;
 
@@ -13,6 +14,19 @@ pub fn details(ui: &mut egui::Ui, collection: Collection) {
             global::set_state_collection(collection.clone());
         }
 
+        let (local, remote) = global::get_state()
+            .usage
+            .lock()
+            .unwrap()
+            .collection(&collection.alias);
+        if local > 0 {
+            ui.weak(format!(
+                "{} of {} on disk",
+                human_bytes(local),
+                human_bytes(remote)
+            ));
+        }
+
         let state_dataset = global::get_state_dataset();
 
         if state_dataset == Dataset::default() {
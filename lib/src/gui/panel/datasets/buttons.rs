@@ -1,7 +1,13 @@
+pub mod get_file;
 pub mod get_files_info;
+pub mod plan_downloads;
 pub mod set_dataset;
+pub mod verify_dataset;
 pub mod view_filelist;
 
+pub use get_file::get_file;
 pub use get_files_info::get_files_info;
+pub use plan_downloads::plan_downloads;
 pub use set_dataset::set_dataset;
+pub use verify_dataset::verify_dataset;
 pub use view_filelist::view_filelist;
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use crate::downloads::{Job, JobState};
+use crate::files::ArchiveKind;
+use crate::global;
+use crate::types::{Collection, Dataset, File, FileStatus};
+
+pub fn get_file(ui: &mut egui::Ui, collection: Collection, dataset: Dataset, file: File) {
+    let downloads = global::get_state().downloads;
+
+    if file.extension == "xml"
+        && file.status == FileStatus::Ok
+        && ui
+            .small_button("📈")
+            .on_hover_text("Plot this session")
+            .clicked()
+    {
+        global::select_session(&file);
+    }
+
+    if let Some(mirror) = file.mirror.as_ref() {
+        ui.weak("🗄")
+            .on_hover_text(format!("Read from the mirror copy {mirror}"));
+        return;
+    }
+
+    // Downloads only land at `local_path` once complete, a `.part` means one is pending.
+    if Path::new(file.local_path.as_str()).exists() {
+        if file.status == FileStatus::Ok
+            && !global::get_state().workspace.mirrors.is_empty()
+            && ui
+                .small_button("⇪")
+                .on_hover_text("Copy to the team mirror")
+                .clicked()
+        {
+            global::promote_to_mirror(file.clone());
+        }
+        if ArchiveKind::from_path(Path::new(file.local_path.as_str())).is_some()
+            && ui
+                .button("📦")
+                .on_hover_text("List and extract members")
+                .clicked()
+        {
+            global::open_archive(file.clone());
+        }
+        if file.status == FileStatus::Mismatch && ui.button("Re-download").clicked() {
+            if let Err(e) = std::fs::remove_file(file.local_path.as_str()) {
+                global::report_error(format!("{}: {e}", file.local_path));
+                return;
+            }
+            downloads.enqueue(Job::from_file(
+                collection.alias.as_str(),
+                dataset.alias.as_str(),
+                &file,
+            ));
+            global::set_state_dataset_file(File {
+                status: FileStatus::Missing,
+                ..file
+            });
+        }
+        return;
+    }
+
+    match downloads.job_for(file.local_path.as_str()) {
+        Some(job) if job.state == JobState::Running => {
+            ui.add(
+                egui::ProgressBar::new(job.progress().unwrap_or(0.0))
+                    .desired_width(80.0)
+                    .show_percentage(),
+            );
+        }
+        Some(job) if !job.state.is_finished() => {
+            ui.label(format!("{:?}", job.state));
+        }
+        _ => {
+            if ui.button("Get").clicked() {
+                downloads.enqueue(Job::from_file(
+                    collection.alias.as_str(),
+                    dataset.alias.as_str(),
+                    &file,
+                ));
+            }
+        }
+    }
+}
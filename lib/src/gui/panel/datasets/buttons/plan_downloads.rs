@@ -0,0 +1,12 @@
+use crate::global;
+use crate::types::{Collection, Dataset};
+
+pub fn plan_downloads(ui: &mut egui::Ui, collection: Collection, dataset: Dataset) {
+    if ui
+        .button("Plan downloads")
+        .on_hover_text("Pick files by pattern and size before queuing them")
+        .clicked()
+    {
+        global::open_plan(collection, dataset);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::files::handlers::{reconcile_dataset, redownload_mismatches, verify_dataset as verify};
+use crate::global;
+use crate::types::{Collection, Dataset, FileStatus};
+
+pub fn verify_dataset(ui: &mut egui::Ui, collection: Collection, dataset: Dataset) {
+    if ui
+        .button("Rescan")
+        .on_hover_text("Match the directory against the listing, hashing only changed files")
+        .clicked()
+    {
+        let (c, d) = (collection.clone(), dataset.clone());
+        tokio::spawn(async move {
+            reconcile_dataset(c, d).await;
+        });
+    }
+    if ui.button("Verify files").clicked() {
+        let d = dataset.clone();
+        tokio::spawn(async move {
+            verify(d).await;
+        });
+    }
+
+    let mismatches = global::get_state_dataset_files()
+        .iter()
+        .any(|f| f.status == FileStatus::Mismatch);
+    if mismatches && ui.button("Re-download mismatches").clicked() {
+        redownload_mismatches(collection, dataset);
+    }
+}
@@ -1,10 +1,14 @@
-use crate::files::handlers::load_or_download_filelist;
+use crate::files::handlers::{load_or_download_filelist, reconcile_dataset};
+use crate::global;
 use crate::types::{Collection, Dataset};
 
 pub fn view_filelist(ui: &mut egui::Ui, collection: Collection, dataset: Dataset) {
     if ui.button("View files").clicked() {
         tokio::spawn(async move {
-            load_or_download_filelist(collection, dataset).await;
+            match load_or_download_filelist(collection.clone(), dataset.clone()).await {
+                Ok(()) => reconcile_dataset(collection, dataset).await,
+                Err(e) => global::report_error(e),
+            }
         });
     }
 }
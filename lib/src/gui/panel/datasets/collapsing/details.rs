@@ -1,22 +1,42 @@
+use crate::files;
 use crate::global;
 use crate::gui::panel::datasets::buttons;
+use crate::gui::panel::downloads::human_bytes;
 
-use crate::types::{collection::Collection, dataset::Dataset};
+use crate::types::{collection::Collection, dataset::Dataset, DatasetMetadata, File, FileStatus};
 
 pub fn details(ui: &mut egui::Ui, collection: Collection, dataset: Dataset) {
     ui.collapsing(dataset.alias.clone(), |ui| {
         let state_dataset = global::get_state_dataset();
 
-        if state_dataset == dataset && ui.button("x").clicked() {
-            global::set_state_dataset(Dataset::default());
+        if state_dataset == dataset {
+            if ui.button("x").clicked() {
+                global::set_state_dataset(Dataset::default());
+            }
             buttons::get_files_info(ui, collection.clone(), dataset.clone());
             buttons::set_dataset(ui, collection.clone(), dataset.clone());
             buttons::view_filelist(ui, collection.clone(), dataset.clone());
+            buttons::verify_dataset(ui, collection.clone(), dataset.clone());
+            buttons::plan_downloads(ui, collection.clone(), dataset.clone());
+
+            metadata(ui, &dataset.metadata);
+            usage(ui, &collection, &dataset);
 
             let files = global::get_state_dataset_files();
+            completeness(ui, &files);
 
             for file in files {
-                ui.label(file.remote_path);
+                ui.horizontal(|ui| {
+                    status(ui, file.status);
+                    ui.label(file.remote_path.clone());
+                    ui.weak(format!(
+                        "{} / {}",
+                        human_bytes(file.local_size),
+                        human_bytes(file.remote_size)
+                    ));
+                    pin(ui, std::path::Path::new(&file.local_path), "📌");
+                    buttons::get_file(ui, collection.clone(), dataset.clone(), file);
+                });
             }
         } else if ui.button("o").clicked() {
             global::set_state_dataset(dataset.clone());
@@ -28,3 +48,133 @@ pub fn details(ui: &mut egui::Ui, collection: Collection, dataset: Dataset) {
         // ui.label(dataset.url.clone());
     });
 }
+
+fn metadata(ui: &mut egui::Ui, metadata: &DatasetMetadata) {
+    let lists = [
+        ("Species", &metadata.species),
+        ("Regions", &metadata.brain_regions),
+        ("Techniques", &metadata.techniques),
+        ("Formats", &metadata.formats),
+    ];
+    let counts = [
+        ("Subjects", metadata.subjects),
+        ("Sessions", metadata.sessions),
+    ];
+
+    egui::Grid::new("dataset_metadata")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (label, values) in lists.iter().filter(|(_, v)| !v.is_empty()) {
+                ui.label(*label);
+                ui.label(values.join(", "));
+                ui.end_row();
+            }
+            for (label, count) in counts.iter().filter_map(|(l, c)| Some((l, (*c)?))) {
+                ui.label(*label);
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+            if let Some(size) = metadata.total_size {
+                ui.label("Size");
+                ui.label(human_bytes(size));
+                ui.end_row();
+            }
+        });
+
+    for (label, entries) in [
+        ("Publications", &metadata.publications),
+        ("Contributors", &metadata.contributors),
+        ("Funding", &metadata.funding),
+    ] {
+        if !entries.is_empty() {
+            ui.collapsing(label, |ui| {
+                for entry in entries {
+                    ui.label(entry);
+                }
+            });
+        }
+    }
+}
+
+fn usage(ui: &mut egui::Ui, collection: &Collection, dataset: &Dataset) {
+    let state = global::get_state();
+    let directory = state
+        .workspace
+        .data_directory()
+        .join(&collection.alias)
+        .join(&dataset.alias);
+
+    ui.horizontal(|ui| {
+        let usage = state.usage.lock().unwrap();
+        match usage.dataset(&collection.alias, &dataset.alias) {
+            Some(usage) => {
+                let remote = usage.remote_bytes.map_or("?".to_string(), human_bytes);
+                ui.label(format!(
+                    "{} of {remote} on disk",
+                    human_bytes(usage.local_bytes)
+                ));
+            }
+            None => {
+                ui.label("Nothing on disk");
+            }
+        }
+        drop(usage);
+
+        pin(ui, &directory, "Pin dataset");
+        if ui
+            .button("Free space")
+            .on_hover_text("Remove downloaded files, keeping listings, parameters and pins")
+            .clicked()
+        {
+            global::free_dataset(collection.alias.clone(), dataset.alias.clone());
+        }
+    });
+}
+
+fn completeness(ui: &mut egui::Ui, files: &[File]) {
+    if files.is_empty() {
+        return;
+    }
+    let completeness = files::reconcile::completeness(files);
+    ui.add(
+        egui::ProgressBar::new(completeness.fraction()).text(format!(
+            "{} of {} files complete",
+            completeness.ok, completeness.listed
+        )),
+    );
+
+    let untracked = global::get_state_untracked_files();
+    if !untracked.is_empty() {
+        ui.collapsing(format!("{} untracked", untracked.len()), |ui| {
+            for path in untracked {
+                ui.weak(path);
+            }
+        });
+    }
+}
+
+fn pin(ui: &mut egui::Ui, path: &std::path::Path, label: &str) {
+    let mut pinned = global::is_pinned(path);
+    if ui
+        .checkbox(&mut pinned, label)
+        .on_hover_text("Pinned files are never evicted")
+        .changed()
+    {
+        global::set_pinned(path, pinned);
+    }
+}
+
+fn status(ui: &mut egui::Ui, status: FileStatus) {
+    let (icon, color, hover) = match status {
+        FileStatus::Unknown => ("?", ui.visuals().weak_text_color(), "Not verified"),
+        FileStatus::Ok => ("✔", egui::Color32::GREEN, "Matches checksums.md5"),
+        FileStatus::Mismatch => (
+            "✖",
+            ui.visuals().error_fg_color,
+            "Differs from checksums.md5",
+        ),
+        FileStatus::Missing => ("○", ui.visuals().weak_text_color(), "Not downloaded"),
+        FileStatus::Partial => ("◐", ui.visuals().warn_fg_color, "Partially downloaded"),
+    };
+    ui.colored_label(color, icon).on_hover_text(hover);
+}
@@ -0,0 +1,199 @@
+use crate::downloads::{Job, JobState};
+use crate::global;
+use crate::gui::traits;
+
+pub struct DownloadPanel {
+    pub is_open: bool,
+}
+
+impl Default for DownloadPanel {
+    fn default() -> Self {
+        Self { is_open: true }
+    }
+}
+
+impl traits::View for DownloadPanel {
+    fn ui(&mut self, _ui: &mut egui::Ui) {}
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::bottom("download_panel")
+            .resizable(true)
+            .show_animated(ctx, self.is_open, |ui| {
+                let downloads = global::get_state().downloads;
+                let jobs = downloads.jobs();
+
+                ui.horizontal(|ui| {
+                    ui.heading("⬇ Downloads");
+
+                    let count = |state: fn(&JobState) -> bool| {
+                        jobs.iter().filter(|j| state(&j.state)).count()
+                    };
+                    ui.label(format!(
+                        "{} running, {} queued, {} failed",
+                        count(|s| *s == JobState::Running),
+                        count(|s| *s == JobState::Queued),
+                        count(|s| matches!(s, JobState::Failed(_))),
+                    ));
+
+                    ui.separator();
+
+                    let mut config = downloads.config();
+                    ui.label("Concurrent");
+                    ui.add(egui::DragValue::new(&mut config.max_concurrent).clamp_range(1..=16));
+
+                    // Edited in KiB/s, 0 is unlimited.
+                    let mut limit = config.max_bytes_per_second.unwrap_or(0) / 1024;
+                    ui.label("KiB/s");
+                    ui.add(egui::DragValue::new(&mut limit).speed(16));
+                    config.max_bytes_per_second = (limit > 0).then_some(limit * 1024);
+
+                    if config != downloads.config() {
+                        downloads.set_config(config);
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Retry failed").clicked() {
+                        downloads.retry_failed();
+                    }
+                    if ui.button("Clear finished").clicked() {
+                        downloads.clear_finished();
+                    }
+                });
+
+                ui.horizontal(storage);
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for job in jobs.iter().filter(|j| j.state != JobState::Done) {
+                        ui.push_id(format!("download_job_{}", job.id), |ui| {
+                            row(ui, job);
+                        });
+                    }
+                });
+
+                if jobs.iter().any(|j| j.state == JobState::Running) {
+                    ctx.request_repaint_after(std::time::Duration::from_millis(250));
+                }
+            });
+    }
+}
+
+fn storage(ui: &mut egui::Ui) {
+    let state = global::get_state();
+
+    let local = state.usage.lock().unwrap().local_bytes();
+    ui.label(format!("{} on disk", human_bytes(local)));
+
+    // Edited in GiB, 0 is no quota.
+    let quota = state.storage.lock().unwrap().quota;
+    let mut gib = quota.unwrap_or(0) as f64 / GIB;
+    ui.label("Quota GiB");
+    ui.add(
+        egui::DragValue::new(&mut gib)
+            .speed(0.1)
+            .clamp_range(0.0..=f64::MAX),
+    );
+    let edited = (gib > 0.0).then_some((gib * GIB) as u64);
+    if edited != quota {
+        global::set_quota(edited);
+    }
+
+    if ui.button("Refresh usage").clicked() {
+        global::refresh_usage();
+    }
+    if ui
+        .add_enabled(quota.is_some(), egui::Button::new("Enforce quota"))
+        .clicked()
+    {
+        global::enforce_quota(Vec::new());
+    }
+}
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+fn row(ui: &mut egui::Ui, job: &Job) {
+    let downloads = global::get_state().downloads;
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{}/{}/{}",
+            job.collection_alias, job.dataset_alias, job.remote_path
+        ));
+
+        match &job.state {
+            JobState::Failed(reason) => {
+                ui.colored_label(ui.visuals().error_fg_color, "Failed")
+                    .on_hover_text(reason);
+            }
+            state => {
+                let progress = job.progress().unwrap_or(0.0);
+                ui.add(
+                    egui::ProgressBar::new(progress)
+                        .desired_width(120.0)
+                        .text(format!("{state:?} {}", human_bytes(job.downloaded))),
+                );
+            }
+        }
+
+        if ui
+            .small_button("▲")
+            .on_hover_text("Raise priority")
+            .clicked()
+        {
+            downloads.set_priority(job.id, job.priority + 1);
+        }
+        if ui
+            .small_button("▼")
+            .on_hover_text("Lower priority")
+            .clicked()
+        {
+            downloads.set_priority(job.id, job.priority - 1);
+        }
+
+        match job.state {
+            JobState::Queued | JobState::Running => {
+                if ui.small_button("⏸").on_hover_text("Pause").clicked() {
+                    downloads.pause(job.id);
+                }
+            }
+            JobState::Paused => {
+                if ui.small_button("▶").on_hover_text("Resume").clicked() {
+                    downloads.resume(job.id);
+                }
+            }
+            JobState::Failed(_) => {
+                if ui.small_button("↻").on_hover_text("Retry").clicked() {
+                    downloads.retry(job.id);
+                }
+            }
+            JobState::Done => (),
+        }
+
+        if ui.small_button("✖").on_hover_text("Cancel").clicked() {
+            downloads.cancel(job.id);
+        }
+    });
+}
+
+pub(crate) fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+impl traits::Display for DownloadPanel {
+    fn name(&self) -> &'static str {
+        ""
+    }
+    fn show(&mut self, _ctx: &egui::Context, _open: &mut bool) {}
+    fn is_enabled(&self, _ctx: &egui::Context) -> bool {
+        true
+    }
+}
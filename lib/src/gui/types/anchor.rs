@@ -1,6 +1,7 @@
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Anchor {
+    #[default]
     Demo,
     EasyMarkEditor,
     Http,
@@ -21,9 +22,3 @@ impl From<Anchor> for egui::WidgetText {
         Self::RichText(egui::RichText::new(value.to_string()))
     }
 }
-
-impl Default for Anchor {
-    fn default() -> Self {
-        Self::Demo
-    }
-}
@@ -0,0 +1,9 @@
+pub mod archive;
+pub mod login;
+pub mod plan;
+pub mod workspaces;
+
+pub use archive::ArchiveWindow;
+pub use login::LoginWindow;
+pub use plan::PlanWindow;
+pub use workspaces::WorkspaceWindow;
@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::files::{file_extension, handlers::extract_archive, list_members, ArchiveMember};
+use crate::global;
+use crate::gui::panel::downloads::human_bytes;
+use crate::gui::traits;
+use crate::types::File;
+
+type Members = Arc<Mutex<Option<Result<Vec<ArchiveMember>, String>>>>;
+
+/// Lists the members of a downloaded archive and extracts a selection of them.
+#[derive(Clone, Default)]
+pub struct ArchiveWindow {
+    pub is_open: bool,
+    archive: File,
+    /// `None` while the archive is being read.
+    members: Members,
+    selected: BTreeSet<String>,
+    /// Space separated extensions to select at once, e.g. `xml res clu`.
+    extensions: String,
+}
+
+impl ArchiveWindow {
+    pub fn open(&mut self, archive: File) {
+        let members: Members = Arc::new(Mutex::new(None));
+        let path = archive.local_path.clone();
+        let m = members.clone();
+        tokio::task::spawn_blocking(move || {
+            let listed = list_members(Path::new(path.as_str())).map_err(|e| e.to_string());
+            *m.lock().unwrap() = Some(listed);
+        });
+
+        *self = ArchiveWindow {
+            is_open: true,
+            archive,
+            members,
+            selected: BTreeSet::new(),
+            extensions: self.extensions.clone(),
+        };
+    }
+
+    fn select_extensions(&mut self, members: &[ArchiveMember]) {
+        let extensions: Vec<String> = self
+            .extensions
+            .split_whitespace()
+            .map(|e| e.trim_start_matches('.').to_lowercase())
+            .collect();
+        for member in members {
+            if extensions.contains(&file_extension(&member.path)) {
+                self.selected.insert(member.path.clone());
+            }
+        }
+    }
+}
+
+impl traits::View for ArchiveWindow {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(self.archive.remote_path.as_str());
+
+        let members = match self.members.lock().unwrap().clone() {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Reading the archive");
+                });
+                return;
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+                return;
+            }
+            Some(Ok(members)) => members,
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Extensions");
+            ui.text_edit_singleline(&mut self.extensions);
+            if ui.button("Select").clicked() {
+                self.select_extensions(&members);
+            }
+            if ui.button("All").clicked() {
+                self.selected = members.iter().map(|m| m.path.clone()).collect();
+            }
+            if ui.button("None").clicked() {
+                self.selected.clear();
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .max_height(320.0)
+            .show(ui, |ui| {
+                for member in members.iter() {
+                    let mut selected = self.selected.contains(&member.path);
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut selected, member.path.as_str()).changed() {
+                            if selected {
+                                self.selected.insert(member.path.clone());
+                            } else {
+                                self.selected.remove(&member.path);
+                            }
+                        }
+                        ui.weak(human_bytes(member.size));
+                    });
+                }
+            });
+
+        ui.separator();
+
+        let selection: Vec<ArchiveMember> = members
+            .into_iter()
+            .filter(|m| self.selected.contains(&m.path))
+            .collect();
+        match global::get_progress(self.archive.local_path.as_str()) {
+            Some(progress) => {
+                ui.add(egui::ProgressBar::new(progress).show_percentage());
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_millis(250));
+            }
+            None => {
+                let size: u64 = selection.iter().map(|m| m.size).sum();
+                let label = format!("Extract {} ({})", selection.len(), human_bytes(size));
+                if ui
+                    .add_enabled(!selection.is_empty(), egui::Button::new(label))
+                    .clicked()
+                {
+                    tokio::spawn(extract_archive(self.archive.clone(), selection));
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.is_open && self.members.lock().unwrap().is_none() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("📦 Archive")
+            .open(&mut is_open)
+            .collapsible(false)
+            .show(ctx, |ui| self.ui(ui));
+        self.is_open = is_open && self.is_open;
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use crate::credentials::{self, Credentials};
+use crate::global;
+use crate::gui::traits;
+use crate::net::{login, session};
+use crate::types::Report;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Idle,
+    Checking,
+    LoggedIn,
+    Failed(String),
+}
+
+/// Logs in to the portal, remembering the account in the credential store.
+#[derive(Clone)]
+pub struct LoginWindow {
+    pub is_open: bool,
+    username: String,
+    password: String,
+    remember: bool,
+    status: Arc<Mutex<Status>>,
+}
+
+impl Default for LoginWindow {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            username: String::new(),
+            password: String::new(),
+            remember: true,
+            status: Arc::new(Mutex::new(Status::Idle)),
+        }
+    }
+}
+
+impl LoginWindow {
+    fn log_in(&mut self) {
+        let state = global::get_state();
+        let candidate = Credentials::new(self.username.as_str(), self.password.as_str());
+        let remember = self.remember;
+        let status = self.status.clone();
+        *status.lock().unwrap() = Status::Checking;
+
+        tokio::spawn(async move {
+            // Checked once here, downloads then trust the account until it is switched.
+            if let Err(e) = login(&state.endpoints, &candidate).await {
+                *status.lock().unwrap() = Status::Failed(e.to_string());
+                return;
+            }
+
+            let username = candidate.username.clone();
+            credentials::set_current(Some(candidate.clone()));
+            if remember {
+                let store = state.credential_store.clone();
+                match tokio::task::spawn_blocking(move || store.save(&candidate)).await {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => {
+                        global::report_error(format!("Unable to remember {username}: {e}"))
+                    }
+                    Err(e) => global::report_error(e),
+                }
+            }
+
+            global::report(Report::Success(format!("Logged in as {username}")));
+            *status.lock().unwrap() = Status::LoggedIn;
+        });
+    }
+
+    fn log_out(&mut self) {
+        let state = global::get_state();
+        credentials::set_current(None);
+        session().logout();
+
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = state.credential_store.delete() {
+                global::report_error(format!("Unable to forget the stored account: {e}"));
+            }
+        });
+        global::report(Report::Info("Logged out".to_string()));
+    }
+}
+
+impl traits::View for LoginWindow {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let status = self.status.lock().unwrap().clone();
+
+        if let Some(account) = credentials::current() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Logged in as {}", account.username));
+                if ui.button("Log out").clicked() {
+                    self.log_out();
+                }
+            });
+            ui.separator();
+            ui.label("Switch account");
+        }
+
+        egui::Grid::new("login_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Username");
+            ui.text_edit_singleline(&mut self.username);
+            ui.end_row();
+
+            ui.label("Password");
+            ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+            ui.end_row();
+        });
+        ui.checkbox(&mut self.remember, "Remember on this machine");
+
+        ui.horizontal(|ui| {
+            let ready = !self.username.trim().is_empty()
+                && !self.password.is_empty()
+                && status != Status::Checking;
+            if ui.add_enabled(ready, egui::Button::new("Log in")).clicked() {
+                self.log_in();
+            }
+            if status == Status::Checking {
+                ui.spinner();
+            }
+        });
+
+        if let Status::Failed(reason) = status {
+            ui.colored_label(ui.visuals().error_fg_color, reason);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if *self.status.lock().unwrap() == Status::LoggedIn {
+            *self.status.lock().unwrap() = Status::Idle;
+            self.password.clear();
+            self.is_open = false;
+        }
+
+        let mut is_open = self.is_open;
+        egui::Window::new("🔑 CRCNS account")
+            .open(&mut is_open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| self.ui(ui));
+        self.is_open = is_open && self.is_open;
+    }
+}
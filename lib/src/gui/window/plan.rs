@@ -0,0 +1,181 @@
+use crate::downloads::{DownloadPlan, PlanRules};
+use crate::global;
+use crate::gui::panel::downloads::human_bytes;
+use crate::gui::traits;
+use crate::types::{Collection, Dataset, Report};
+
+const MIB: f64 = (1u64 << 20) as f64;
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// Selects the working files to download by pattern and size, with a dry run.
+#[derive(Clone)]
+pub struct PlanWindow {
+    pub is_open: bool,
+    collection: Collection,
+    dataset: Dataset,
+    include: String,
+    exclude: String,
+    /// Size budget in GiB, 0 is none.
+    max_gib: f64,
+    /// Rate the estimate assumes when downloads are not rate limited.
+    assumed_mib_per_second: f64,
+}
+
+impl Default for PlanWindow {
+    fn default() -> Self {
+        PlanWindow {
+            is_open: false,
+            collection: Collection::default(),
+            dataset: Dataset::default(),
+            include: String::new(),
+            exclude: String::new(),
+            max_gib: 0.0,
+            assumed_mib_per_second: 10.0,
+        }
+    }
+}
+
+impl PlanWindow {
+    /// Opens the window for `dataset`, keeping the rules of the previous one.
+    pub fn open(&mut self, collection: Collection, dataset: Dataset) {
+        self.collection = collection;
+        self.dataset = dataset;
+        self.is_open = true;
+    }
+
+    fn rules(&self) -> PlanRules {
+        PlanRules::parse(
+            self.include.as_str(),
+            self.exclude.as_str(),
+            (self.max_gib * GIB) as u64,
+        )
+    }
+}
+
+impl traits::View for PlanWindow {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("{}/{}", self.collection.alias, self.dataset.alias));
+
+        egui::Grid::new("plan_rules_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Include");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.include)
+                        .hint_text("*.xml *.eeg *.res.* *.clu.* *.whl"),
+                );
+                ui.end_row();
+
+                ui.label("Exclude");
+                ui.add(egui::TextEdit::singleline(&mut self.exclude).hint_text("*.dat"));
+                ui.end_row();
+
+                ui.label("Budget GiB");
+                ui.add(
+                    egui::DragValue::new(&mut self.max_gib)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+                ui.end_row();
+            });
+
+        let files = global::get_state_dataset_files();
+        if files.is_empty() || global::get_state_dataset() != self.dataset {
+            ui.label("Open the dataset and view its files first");
+            return;
+        }
+
+        let plan = match DownloadPlan::build(&files, &self.rules()) {
+            Ok(plan) => plan,
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+                return;
+            }
+        };
+
+        let config = global::get_state().downloads.config();
+        let bytes_per_second = match config.max_bytes_per_second {
+            Some(limit) => limit,
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label("Assumed MiB/s");
+                    ui.add(
+                        egui::DragValue::new(&mut self.assumed_mib_per_second)
+                            .speed(0.5)
+                            .clamp_range(0.1..=f64::MAX),
+                    );
+                });
+                (self.assumed_mib_per_second * MIB) as u64
+            }
+        };
+
+        ui.separator();
+        ui.label(format!(
+            "{} files, {}, about {}",
+            plan.selected.len(),
+            human_bytes(plan.bytes()),
+            human_duration(plan.estimated_duration(bytes_per_second).as_secs())
+        ));
+        if plan.present > 0 {
+            ui.weak(format!("{} already on disk", plan.present));
+        }
+        if !plan.over_budget.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("{} do not fit the budget", plan.over_budget.len()),
+            );
+        }
+
+        egui::CollapsingHeader::new("Files")
+            .id_source("plan_files")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for file in plan.selected.iter() {
+                            ui.horizontal(|ui| {
+                                ui.label(file.remote_path.as_str());
+                                ui.weak(human_bytes(file.remote_size));
+                            });
+                        }
+                    });
+            });
+
+        if ui
+            .add_enabled(
+                !plan.selected.is_empty(),
+                egui::Button::new(format!("Queue {} files", plan.selected.len())),
+            )
+            .clicked()
+        {
+            let downloads = global::get_state().downloads;
+            let ids = plan.enqueue(
+                &downloads,
+                self.collection.alias.as_str(),
+                self.dataset.alias.as_str(),
+            );
+            global::report(Report::Info(format!(
+                "Queued {} files of {}",
+                ids.len(),
+                self.dataset.alias
+            )));
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut is_open = self.is_open;
+        egui::Window::new("⬇ Plan downloads")
+            .open(&mut is_open)
+            .collapsible(false)
+            .show(ctx, |ui| self.ui(ui));
+        self.is_open = is_open && self.is_open;
+    }
+}
+
+fn human_duration(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{s} s"),
+        s if s < 3600 => format!("{} min", s.div_ceil(60)),
+        s => format!("{}h{:02}", s / 3600, (s % 3600) / 60),
+    }
+}
@@ -0,0 +1,138 @@
+use crate::global;
+use crate::gui::traits;
+use crate::settings::{Settings, Workspace};
+use crate::types::Report;
+
+/// Lists the workspaces of the settings file, opens one or adds a new one.
+#[derive(Clone, Default)]
+pub struct WorkspaceWindow {
+    pub is_open: bool,
+    name: String,
+    root: String,
+    error: Option<String>,
+}
+
+impl WorkspaceWindow {
+    /// Applies `change` to the settings file and saves it.
+    fn edit(&mut self, change: impl FnOnce(&mut Settings)) -> Option<Settings> {
+        let result = Settings::load().and_then(|mut settings| {
+            change(&mut settings);
+            settings.save().map(|_| settings)
+        });
+        match result {
+            Ok(settings) => {
+                self.error = None;
+                Some(settings)
+            }
+            Err(e) => {
+                self.error = Some(format!(
+                    "Unable to update {}: {e}",
+                    Settings::filepath().display()
+                ));
+                None
+            }
+        }
+    }
+
+    fn open(&mut self, workspace: Workspace) {
+        let name = workspace.name.clone();
+        if self
+            .edit(|settings| settings.current = Some(name.clone()))
+            .is_none()
+        {
+            return;
+        }
+        match global::switch_workspace(workspace) {
+            Ok(()) => global::report(Report::Success(format!("Opened workspace {name}"))),
+            Err(e) => self.error = Some(format!("Unable to open {name}: {e}")),
+        }
+    }
+}
+
+impl traits::View for WorkspaceWindow {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let current = global::get_state().workspace;
+        let settings = match Settings::load() {
+            Ok(settings) => settings,
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+                return;
+            }
+        };
+
+        egui::Grid::new("workspaces_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for workspace in settings.workspaces.iter() {
+                    let is_current = workspace.name == current.name;
+                    if ui
+                        .selectable_label(is_current, workspace.name.as_str())
+                        .clicked()
+                        && !is_current
+                    {
+                        self.open(workspace.clone());
+                    }
+                    let root = ui.label(workspace.root.display().to_string());
+                    if !workspace.mirrors.is_empty() {
+                        let mirrors: Vec<String> = workspace
+                            .mirrors
+                            .iter()
+                            .map(|m| m.display().to_string())
+                            .collect();
+                        root.on_hover_text(format!("Mirrors: {}", mirrors.join(", ")));
+                    }
+                    if ui
+                        .add_enabled(!is_current, egui::Button::new("✖"))
+                        .on_hover_text("Forget, the files are kept")
+                        .clicked()
+                    {
+                        let name = workspace.name.clone();
+                        self.edit(|settings| settings.remove(name.as_str()));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+        egui::Grid::new("new_workspace_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut self.name);
+                ui.end_row();
+
+                ui.label("Root");
+                ui.text_edit_singleline(&mut self.root);
+                ui.end_row();
+            });
+
+        let ready = !self.name.trim().is_empty() && !self.root.trim().is_empty();
+        if ui
+            .add_enabled(ready, egui::Button::new("Add and open"))
+            .clicked()
+        {
+            let workspace = Workspace::new(self.name.as_str(), self.root.trim());
+            let added = workspace.clone();
+            if self.edit(|settings| settings.add(added)).is_some() {
+                self.name.clear();
+                self.root.clear();
+                self.open(workspace);
+            }
+        }
+
+        if let Some(error) = self.error.as_ref() {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut is_open = self.is_open;
+        egui::Window::new("📁 Workspaces")
+            .open(&mut is_open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| self.ui(ui));
+        self.is_open = is_open && self.is_open;
+    }
+}
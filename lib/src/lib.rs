@@ -1,7 +1,14 @@
+pub mod catalog;
+pub mod credentials;
+pub mod downloads;
 pub mod files;
 pub mod global;
 pub mod gui;
 pub mod net;
+pub mod recording;
+pub mod search;
+pub mod settings;
+pub mod storage;
 pub mod types;
 
 pub const LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
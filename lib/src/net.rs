@@ -1,7 +1,23 @@
+pub mod bandwidth;
+pub mod document_modified;
+pub mod download;
+pub mod error;
 pub mod get_crcns_file;
 pub mod get_url_html;
+pub mod login;
+pub mod portal_page;
+pub mod retry;
+pub mod session;
 pub mod write_response;
+pub mod xpath;
 
-pub use get_crcns_file::get_crcns_file;
-pub use get_url_html::get_url_html;
+pub use bandwidth::Bandwidth;
+pub use document_modified::{document_modified, parse_document_modified};
+pub use download::{download, download_with, md5_file, Expected, Transfer};
+pub use error::{Error, Result};
+pub use get_crcns_file::{get_crcns_file, get_crcns_file_range};
+pub use get_url_html::{get_url_html, get_url_html_if_modified, Page, Validators};
+pub use login::login;
+pub use retry::RetryPolicy;
+pub use session::{session, Session};
 pub use write_response::write_response;
@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Bandwidth shared by every transfer it is handed to.
+///
+/// Each chunk reserves the time it takes to move at the limit, the next chunk
+/// waits until that reservation ends, so the combined rate stays under the limit.
+#[derive(Debug, Default)]
+pub struct Bandwidth {
+    limit: Mutex<Option<u64>>,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Bandwidth {
+    pub fn new(bytes_per_second: Option<u64>) -> Self {
+        let bandwidth = Bandwidth::default();
+        bandwidth.set_limit(bytes_per_second);
+        bandwidth
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        *self.limit.lock().unwrap()
+    }
+
+    /// `None` (or zero) lifts the limit.
+    pub fn set_limit(&self, bytes_per_second: Option<u64>) {
+        *self.limit.lock().unwrap() = bytes_per_second.filter(|b| *b > 0);
+        *self.next.lock().unwrap() = None;
+    }
+
+    /// Waits until `bytes` may be moved without exceeding the limit.
+    pub async fn consume(&self, bytes: u64) {
+        let Some(limit) = self.limit() else {
+            return;
+        };
+
+        let start = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let start = next.map_or(now, |n| n.max(now));
+            *next = Some(start + Duration::from_secs_f64(bytes as f64 / limit as f64));
+            start
+        };
+
+        tokio::time::sleep_until(start).await;
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use sxd_document::dom::Document;
+
+use crate::net::error::Result;
+use crate::net::xpath;
+
+/// Layouts of the `documentModified` byline of catalog pages, e.g. `Jul 09, 2019 02:25 PM`.
+const FORMATS: [&str; 3] = ["%b %d, %Y %I:%M %p", "%b %d, %Y %H:%M", "%Y-%m-%d %H:%M"];
+
+/// Unix timestamp of the `documentModified` byline of a catalog page, `0` when the
+/// page has none or it cannot be read.
+pub fn document_modified(document: &Document, url: &str) -> Result<i64> {
+    let text = xpath::optional_string(document, "string(//span[@class='documentModified'])", url)?
+        .unwrap_or_default();
+
+    match parse_document_modified(text.as_str()) {
+        Some(timestamp) => Ok(timestamp),
+        None => {
+            if !text.trim().is_empty() {
+                log::warn!("Unable to read the modification date of {url}: {text:?}");
+            }
+            Ok(0)
+        }
+    }
+}
+
+/// Parses the byline text, with or without its `last modified` label.
+///
+/// The catalog does not say which timezone it uses, dates are taken as UTC.
+pub fn parse_document_modified(text: &str) -> Option<i64> {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let text = text.trim();
+    let text = text
+        .strip_prefix("last modified")
+        .or_else(|| text.strip_prefix("Last modified"))
+        .unwrap_or(text)
+        .trim();
+
+    FORMATS.iter().find_map(|format| {
+        NaiveDateTime::parse_from_str(text, format)
+            .ok()
+            .map(|datetime| datetime.and_utc().timestamp())
+    })
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use tokio::io::AsyncReadExt;
+
+use crate::files::part;
+use crate::net::bandwidth::Bandwidth;
+use crate::net::error::{Error, Result};
+use crate::net::get_crcns_file::get_crcns_file_range;
+use crate::net::session::session;
+use crate::net::write_response::write_response_with;
+use crate::types::Endpoints;
+
+/// What a download should look like once complete, as far as it is known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expected {
+    pub size: Option<u64>,
+    pub md5: Option<String>,
+}
+
+impl Expected {
+    /// Treats `0` and empty strings, the defaults of [`crate::types::File`], as unknown.
+    pub fn new(size: u64, md5: &str) -> Self {
+        Expected {
+            size: (size > 0).then_some(size),
+            md5: (!md5.is_empty()).then(|| md5.to_lowercase()),
+        }
+    }
+}
+
+/// Observes and paces a download while its chunks arrive.
+#[derive(Debug, Clone, Default)]
+pub struct Transfer {
+    /// Bytes in the part so far, resumed bytes included.
+    pub downloaded: Arc<AtomicU64>,
+    /// Shared with other transfers to cap their combined rate.
+    pub bandwidth: Option<Arc<Bandwidth>>,
+}
+
+/// Downloads `remote_filepath` into `local_filepath` through a `.part` sibling.
+///
+/// An existing `.part` is resumed with a range request. The file is only moved into
+/// place once it matches `expected`, so an existing `local_filepath` is always complete.
+pub async fn download(
+    endpoints: &Endpoints,
+    remote_filepath: &str,
+    local_filepath: &Path,
+    expected: &Expected,
+) -> Result<u64> {
+    download_with(
+        endpoints,
+        remote_filepath,
+        local_filepath,
+        expected,
+        &Transfer::default(),
+    )
+    .await
+}
+
+/// [`download`] reporting its progress and pacing itself through `transfer`.
+pub async fn download_with(
+    endpoints: &Endpoints,
+    remote_filepath: &str,
+    local_filepath: &Path,
+    expected: &Expected,
+    transfer: &Transfer,
+) -> Result<u64> {
+    let part_filepath = part::part_path(local_filepath);
+    let mut offset = part::part_len(&part_filepath).await;
+
+    if expected.size.is_some_and(|size| offset > size) {
+        offset = 0;
+    }
+    transfer.downloaded.store(offset, Ordering::Relaxed);
+
+    // md5 of the part as it is written, `None` when nothing was written.
+    let mut md5 = None;
+
+    let complete = offset > 0 && expected.size == Some(offset);
+    if !complete {
+        match get_crcns_file_range(endpoints, remote_filepath, offset).await {
+            Ok(response) => {
+                let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+                if offset > 0 && !resumed {
+                    log::info!("{remote_filepath} does not support ranges, starting over");
+                    transfer.downloaded.store(0, Ordering::Relaxed);
+                }
+                let mut digest = md5::Context::new();
+                if resumed && expected.md5.is_some() {
+                    hash_file(&part_filepath, &mut digest).await?;
+                }
+                let mut part_file = part::open_part(&part_filepath, resumed).await?;
+                let written = write_response_with(
+                    &mut part_file,
+                    response,
+                    Some(&endpoints.portal),
+                    transfer,
+                    &mut digest,
+                )
+                .await;
+                if let Err(e @ (Error::Auth(_) | Error::TermsNotAccepted(_))) = written {
+                    // Whatever is in the part is not the file, never resume from it.
+                    drop(part_file);
+                    session().forget(&endpoints.portal);
+                    let _ = tokio::fs::remove_file(&part_filepath).await;
+                    return Err(e);
+                }
+                written?;
+                if !resumed || expected.md5.is_some() {
+                    md5 = Some(format!("{:x}", digest.compute()));
+                }
+            }
+            // Nothing left past `offset`, the part is as complete as it gets.
+            Err(Error::Status { status, .. })
+                if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let size = verify(&part_filepath, expected, md5).await?;
+    part::commit_part(&part_filepath, local_filepath).await?;
+
+    Ok(size)
+}
+
+/// Checks a finished part against `expected`, discarding it when it cannot be resumed.
+///
+/// `md5` is the digest computed while downloading, the part is only hashed again
+/// when it is unknown.
+async fn verify(part_filepath: &Path, expected: &Expected, md5: Option<String>) -> Result<u64> {
+    let size = part::part_len(part_filepath).await;
+
+    if let Some(expected_size) = expected.size {
+        if size != expected_size {
+            // A short part can still be resumed, anything else is garbage.
+            if size > expected_size {
+                let _ = tokio::fs::remove_file(part_filepath).await;
+            }
+            return Err(Error::integrity(
+                part_filepath.display(),
+                format!("expected {expected_size} bytes, found {size}"),
+            ));
+        }
+    }
+
+    if let Some(expected_md5) = expected.md5.as_ref() {
+        let md5 = match md5 {
+            Some(md5) => md5,
+            None => md5_file(part_filepath).await?,
+        };
+        if &md5 != expected_md5 {
+            let _ = tokio::fs::remove_file(part_filepath).await;
+            return Err(Error::integrity(
+                part_filepath.display(),
+                format!("expected md5 {expected_md5}, found {md5}"),
+            ));
+        }
+    }
+
+    Ok(size)
+}
+
+pub async fn md5_file(filepath: &Path) -> Result<String> {
+    let mut context = md5::Context::new();
+    hash_file(filepath, &mut context).await?;
+    Ok(format!("{:x}", context.compute()))
+}
+
+/// Feeds the whole content of `filepath` to `context`.
+async fn hash_file(filepath: &Path, context: &mut md5::Context) -> Result<()> {
+    let mut file = tokio::fs::File::open(filepath).await?;
+    let mut buffer = vec![0u8; 1 << 20];
+
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        context.consume(&buffer[..n]);
+    }
+
+    Ok(())
+}
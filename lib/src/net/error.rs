@@ -0,0 +1,113 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while talking to the catalog or the download portal.
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or its body could not be read.
+    Transport(reqwest::Error),
+    /// The server answered with a non-success status.
+    Status {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+    /// Credentials are missing or were rejected by the portal.
+    Auth(String),
+    /// The portal wants the data usage agreement accepted before serving files.
+    TermsNotAccepted(String),
+    /// A page did not have the expected structure.
+    Parse { url: String, reason: String },
+    /// Reading or writing a local file failed.
+    Io(std::io::Error),
+    /// A download does not match its expected size or checksum.
+    Integrity { path: String, reason: String },
+    /// A queued download failed or was cancelled.
+    Download(String),
+}
+
+impl Error {
+    pub fn parse(url: impl ToString, reason: impl ToString) -> Self {
+        Error::Parse {
+            url: url.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn integrity(path: impl ToString, reason: impl ToString) -> Self {
+        Error::Integrity {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// What the user can do about it, for errors only they can fix.
+    pub fn advice(&self) -> Option<&'static str> {
+        match self {
+            Error::Auth(_) => Some("check the CRCNS account in the login window"),
+            Error::TermsNotAccepted(_) => {
+                Some("log in on crcns.org and accept the data sharing agreement, then retry")
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether trying again later has a chance of succeeding.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Transport(e) => e.is_timeout() || e.is_connect() || e.is_body(),
+            Error::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    || *status == reqwest::StatusCode::REQUEST_TIMEOUT
+            }
+            Error::Auth(_)
+            | Error::TermsNotAccepted(_)
+            | Error::Parse { .. }
+            | Error::Io(_)
+            | Error::Integrity { .. }
+            | Error::Download(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "Transport error: {e}"),
+            Error::Status { url, status } => write!(f, "{url} answered {status}"),
+            Error::Auth(reason) => write!(f, "Authentication failed: {reason}"),
+            Error::TermsNotAccepted(reason) => write!(f, "Agreement not accepted: {reason}"),
+            Error::Parse { url, reason } => write!(f, "Unable to parse {url}: {reason}"),
+            Error::Io(e) => write!(f, "IO error: {e}"),
+            Error::Integrity { path, reason } => write!(f, "{path} is corrupt: {reason}"),
+            Error::Download(reason) => write!(f, "Download failed: {reason}"),
+        }?;
+        match self.advice() {
+            Some(advice) => write!(f, ", {advice}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -1,22 +1,30 @@
-use std::collections::HashMap;
+use crate::credentials;
+use crate::net::error::{Error, Result};
+use crate::net::session::session;
+use crate::types::Endpoints;
 
-use reqwest::Client;
-use url::Url;
-
-pub async fn get_crcns_file(filepath: &str) -> reqwest::Response {
-    let base_url = "https://portal.nersc.gov/project/crcns/download/index.php";
-    let url = Url::parse(base_url).unwrap();
-    let url = url.join(filepath).unwrap();
-
-    let username = std::env::var("CRCNS_USERNAME").unwrap();
-    let password = std::env::var("CRCNS_PASSWORD").unwrap();
+pub async fn get_crcns_file(endpoints: &Endpoints, filepath: &str) -> Result<reqwest::Response> {
+    get_crcns_file_range(endpoints, filepath, 0).await
+}
 
-    let mut request_data = HashMap::new();
-    request_data.insert("username", username.as_str());
-    request_data.insert("password", password.as_str());
-    request_data.insert("fn", filepath);
-    request_data.insert("submit", "Login");
+/// Requests `filepath` starting at byte `offset`.
+///
+/// The portal may ignore the range, callers must check for `206 Partial Content`
+/// before appending the body to what they already have.
+///
+/// A rejected login still gets a `200` with an HTML page, any page served in place
+/// of a file is turned into an error instead of being returned as its content.
+pub async fn get_crcns_file_range(
+    endpoints: &Endpoints,
+    filepath: &str,
+    offset: u64,
+) -> Result<reqwest::Response> {
+    let credentials = credentials::current().ok_or_else(|| {
+        Error::Auth("not logged in, log in or set CRCNS_USERNAME and CRCNS_PASSWORD".to_string())
+    })?;
 
-    let client = Client::new();
-    client.post(url).form(&request_data).send().await.unwrap()
+    endpoints
+        .retry
+        .run(|| session().post_file(endpoints, &credentials, filepath, offset))
+        .await
 }
@@ -1,23 +1,88 @@
+use bincode::{Decode, Encode};
+use reqwest::header;
 use url as urllib;
 
-pub async fn get_url_html(url: urllib::Url) -> String {
-    let site_future = reqwest::get(url.clone());
-    let site_result = site_future.await;
-    let site_response = match site_result {
-        Ok(response) => response,
-        Err(e) => {
-            println!("Unable to get {url}: {e}");
-            panic!("Failed.")
-        }
-    };
+use crate::net::error::{Error, Result};
+use crate::net::retry::RetryPolicy;
+use crate::net::session::session;
+
+/// Cache validators a server sent along a page, replayed so the page is only
+/// transferred again once it changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
 
-    let site_html_future = site_response.text();
-    let site_html_result = site_html_future.await;
-    match site_html_result {
-        Ok(html) => html,
-        Err(e) => {
-            println!("Unabled to get {url} html: {e}");
-            panic!("Failed");
-        }
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
     }
 }
+
+/// Outcome of a conditional request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Page {
+    Modified {
+        html: String,
+        validators: Validators,
+    },
+    NotModified,
+}
+
+pub async fn get_url_html(url: urllib::Url, retry: &RetryPolicy) -> Result<String> {
+    match get_url_html_if_modified(url, retry, &Validators::default()).await? {
+        Page::Modified { html, .. } => Ok(html),
+        // Only answered to conditional requests, which this was not.
+        Page::NotModified => unreachable!(),
+    }
+}
+
+/// Fetches `url` unless it still matches `validators`, sending `If-None-Match` and
+/// `If-Modified-Since` when they are known.
+pub async fn get_url_html_if_modified(
+    url: urllib::Url,
+    retry: &RetryPolicy,
+    validators: &Validators,
+) -> Result<Page> {
+    retry.run(|| fetch(url.clone(), validators)).await
+}
+
+async fn fetch(url: urllib::Url, validators: &Validators) -> Result<Page> {
+    let mut request = session().client().get(url.clone());
+    if let Some(etag) = validators.etag.as_ref() {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = validators.last_modified.as_ref() {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+    let site_response = request.send().await?;
+
+    let status = site_response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED && !validators.is_empty() {
+        return Ok(Page::NotModified);
+    }
+    if !status.is_success() {
+        return Err(Error::Status {
+            url: url.to_string(),
+            status,
+        });
+    }
+
+    let header_string = |name: header::HeaderName| {
+        site_response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let validators = Validators {
+        etag: header_string(header::ETAG),
+        last_modified: header_string(header::LAST_MODIFIED),
+    };
+
+    Ok(Page::Modified {
+        html: site_response.text().await?,
+        validators,
+    })
+}
@@ -0,0 +1,9 @@
+use crate::credentials::Credentials;
+use crate::net::error::Result;
+use crate::net::session::session;
+use crate::types::Endpoints;
+
+/// Checks `credentials` against the portal, logging the shared session in.
+pub async fn login(endpoints: &Endpoints, credentials: &Credentials) -> Result<()> {
+    session().login(endpoints, credentials).await
+}
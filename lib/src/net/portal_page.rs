@@ -0,0 +1,76 @@
+use url as urllib;
+
+use crate::net::error::Error;
+
+/// Phrases of the portal pages asking to accept the data usage agreement.
+const TERMS_MARKERS: [&str; 4] = [
+    "terms of use",
+    "data sharing agreement",
+    "accept the agreement",
+    "agree to the terms",
+];
+
+/// Phrases the portal uses when it turns a login down.
+const LOGIN_ERROR_MARKERS: [&str; 4] = [
+    "invalid username",
+    "incorrect password",
+    "login failed",
+    "account has expired",
+];
+
+/// Whether `response` is declared as an HTML page.
+pub fn is_html(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.to_lowercase().starts_with("text/html"))
+}
+
+/// Whether `url` is the `portal` script itself, where the portal sends requests it
+/// wants a login for.
+pub fn is_portal(url: &urllib::Url, portal: &urllib::Url) -> bool {
+    url.origin() == portal.origin() && url.path() == portal.path()
+}
+
+/// Whether `body` starts like an HTML document rather than data.
+pub fn looks_like_html(body: &[u8]) -> bool {
+    let head = &body[..body.len().min(512)];
+    let head = String::from_utf8_lossy(head).to_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.starts_with("<head")
+}
+
+/// The error a portal page stands for when it was served instead of `filepath`,
+/// `None` if it does not look like a rejection.
+pub fn rejection(body: &str, filepath: &str) -> Option<Error> {
+    let body = body.to_lowercase();
+
+    if body.contains("type=\"password\"")
+        || body.contains("type='password'")
+        || LOGIN_ERROR_MARKERS.iter().any(|m| body.contains(m))
+    {
+        return Some(Error::Auth(format!(
+            "the portal answered with its login page instead of {filepath}"
+        )));
+    }
+    if TERMS_MARKERS.iter().any(|m| body.contains(m)) {
+        return Some(Error::TermsNotAccepted(format!(
+            "the portal asks to accept its terms before serving {filepath}"
+        )));
+    }
+    None
+}
+
+/// Classifies an HTML page served in place of `filepath`, which is never expected
+/// unless `filepath` is itself a page.
+pub fn classify(body: &str, url: &str, filepath: &str) -> Error {
+    rejection(body, filepath)
+        .unwrap_or_else(|| Error::parse(url, format!("expected {filepath}, got an HTML page")))
+}
+
+/// Whether `filepath` may legitimately be served as HTML.
+pub fn is_page(filepath: &str) -> bool {
+    let filepath = filepath.to_lowercase();
+    filepath.ends_with(".html") || filepath.ends_with(".htm")
+}
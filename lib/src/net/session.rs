@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use url as urllib;
+
+use crate::credentials::Credentials;
+use crate::net::error::{Error, Result};
+use crate::net::portal_page;
+use crate::types::Endpoints;
+
+static SESSION: Lazy<Session> = Lazy::new(Session::default);
+
+/// The session shared by the scraper and the downloader.
+pub fn session() -> &'static Session {
+    &SESSION
+}
+
+/// A long-lived HTTP client keeping connections and portal cookies between requests.
+///
+/// Credentials are posted once per portal, later file requests only carry the session
+/// cookie. When the portal forgets the session it answers with its login page again,
+/// the session then logs in anew and repeats the request.
+pub struct Session {
+    client: RwLock<Client>,
+    /// Account the cookies of each portal were obtained for.
+    authenticated: Mutex<HashMap<String, String>>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            client: RwLock::new(build_client()),
+            authenticated: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Session {
+    pub fn client(&self) -> Client {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Posts `credentials` to the portal without asking for a file, keeping the
+    /// session cookie it answers with.
+    ///
+    /// The portal answers a rejected login with its login form and a `200`, so a
+    /// response still asking for a password (or for the agreement) is a failure.
+    pub async fn login(&self, endpoints: &Endpoints, credentials: &Credentials) -> Result<()> {
+        self.forget(&endpoints.portal);
+
+        let body = endpoints
+            .retry
+            .run(|| async {
+                let response = self
+                    .client()
+                    .post(endpoints.portal.clone())
+                    .form(&login_form(credentials, None))
+                    .send()
+                    .await?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(Error::Status {
+                        url: endpoints.portal.to_string(),
+                        status,
+                    });
+                }
+                Ok(response.text().await?)
+            })
+            .await?;
+
+        match portal_page::rejection(body.as_str(), "files") {
+            Some(Error::Auth(_)) => Err(Error::Auth(format!(
+                "the portal rejected the password of {}",
+                credentials.username
+            ))),
+            Some(e) => Err(e),
+            None => {
+                self.remember(&endpoints.portal, credentials);
+                Ok(())
+            }
+        }
+    }
+
+    /// Drops every cookie and connection, the next request logs in from scratch.
+    pub fn logout(&self) {
+        *self.client.write().unwrap() = build_client();
+        self.authenticated.lock().unwrap().clear();
+    }
+
+    /// Forgets that `portal` accepted the session, without dropping the client.
+    pub fn forget(&self, portal: &urllib::Url) {
+        self.authenticated.lock().unwrap().remove(portal.as_str());
+    }
+
+    /// Requests `filepath` from the portal, from byte `offset` on.
+    ///
+    /// Credentials are only sent when the session is not logged in to the portal as
+    /// `credentials.username`, or after the portal turned the session cookie down.
+    pub(crate) async fn post_file(
+        &self,
+        endpoints: &Endpoints,
+        credentials: &Credentials,
+        filepath: &str,
+        offset: u64,
+    ) -> Result<reqwest::Response> {
+        let cookie_only = self.is_authenticated(&endpoints.portal, credentials);
+
+        match self
+            .send_file_request(endpoints, credentials, filepath, offset, cookie_only)
+            .await
+        {
+            Err(Error::Auth(reason)) if cookie_only => {
+                log::info!("Portal session expired ({reason}), logging in again");
+                self.forget(&endpoints.portal);
+                self.send_file_request(endpoints, credentials, filepath, offset, false)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_file_request(
+        &self,
+        endpoints: &Endpoints,
+        credentials: &Credentials,
+        filepath: &str,
+        offset: u64,
+        cookie_only: bool,
+    ) -> Result<reqwest::Response> {
+        let url = endpoints.portal_url(filepath);
+        let form = match cookie_only {
+            true => file_form(filepath),
+            false => login_form(credentials, Some(filepath)),
+        };
+
+        let mut request = self.client().post(url.clone()).form(&form);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(Error::Auth(format!(
+                "portal answered {status} for {filepath}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(Error::Status {
+                url: url.to_string(),
+                status,
+            });
+        }
+        if portal_page::is_html(&response) && !portal_page::is_page(filepath) {
+            let body = response.text().await?;
+            return Err(portal_page::classify(body.as_str(), url.as_str(), filepath));
+        }
+
+        if !cookie_only {
+            self.remember(&endpoints.portal, credentials);
+        }
+        Ok(response)
+    }
+
+    fn is_authenticated(&self, portal: &urllib::Url, credentials: &Credentials) -> bool {
+        self.authenticated
+            .lock()
+            .unwrap()
+            .get(portal.as_str())
+            .is_some_and(|username| *username == credentials.username)
+    }
+
+    fn remember(&self, portal: &urllib::Url, credentials: &Credentials) {
+        self.authenticated
+            .lock()
+            .unwrap()
+            .insert(portal.to_string(), credentials.username.clone());
+    }
+}
+
+fn build_client() -> Client {
+    Client::builder()
+        .cookie_store(true)
+        .user_agent(concat!("crcns-lens/", env!("CARGO_PKG_VERSION")))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(8)
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .expect("Unable to initialize the HTTP client.")
+}
+
+fn login_form<'a>(
+    credentials: &'a Credentials,
+    filepath: Option<&'a str>,
+) -> Vec<(&'a str, &'a str)> {
+    let mut form = vec![
+        ("username", credentials.username.as_str()),
+        ("password", credentials.password.as_str()),
+    ];
+    if let Some(filepath) = filepath {
+        form.push(("fn", filepath));
+    }
+    form.push(("submit", "Login"));
+    form
+}
+
+fn file_form(filepath: &str) -> Vec<(&str, &str)> {
+    vec![("fn", filepath), ("submit", "Login")]
+}
@@ -1,58 +1,71 @@
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
 
 use futures_util::StreamExt;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use url as urllib;
 
-use crate::types::state::SrPair;
+use crate::net::download::Transfer;
+use crate::net::error::Result;
+use crate::net::portal_page;
 
-pub async fn write_response(file: &mut File, response: reqwest::Response) {
-    let total_size = response
-        .content_length()
-        .ok_or("Failed to get content length")
-        .unwrap();
-
-    let mut downloaded: u64 = 0;
-    let mut stream = response.bytes_stream();
-
-    while let Some(item) = stream.next().await {
-        let chunk = item.unwrap();
-        file.write_all(&chunk).await.unwrap();
-        downloaded += chunk.len() as u64;
-
-        // Log the download progress
-        println!(
-            "Downloaded {} of {} bytes ({:.2}%)",
-            downloaded,
-            total_size,
-            (downloaded as f64 / total_size as f64) * 100.0
-        );
-    }
+/// Streams the response body into `file`, returning the number of bytes written.
+pub async fn write_response(file: &mut File, response: reqwest::Response) -> Result<u64> {
+    let mut digest = md5::Context::new();
+    write_response_with(file, response, None, &Transfer::default(), &mut digest).await
 }
 
-pub async fn write_response_with_sender(
+/// [`write_response`] counting into and paced by `transfer`, every chunk written is
+/// also fed to `digest` so the file never has to be read back to be checked.
+///
+/// A login or agreement page is caught on its first chunk, before anything is
+/// written, when the response is declared as HTML or was redirected to the `portal`
+/// script. Anything else is data, even a file that happens to start like a page.
+pub async fn write_response_with(
     file: &mut File,
     response: reqwest::Response,
-    sr: Arc<Mutex<SrPair>>,
-) {
-    let total_size = response
-        .content_length()
-        .ok_or("Failed to get content length")
-        .unwrap();
+    portal: Option<&urllib::Url>,
+    transfer: &Transfer,
+    digest: &mut md5::Context,
+) -> Result<u64> {
+    let total_size = response.content_length();
+    let may_be_page = portal_page::is_html(&response)
+        || portal.is_some_and(|portal| portal_page::is_portal(response.url(), portal));
 
     let mut downloaded: u64 = 0;
     let mut stream = response.bytes_stream();
 
     while let Some(item) = stream.next().await {
-        let chunk = item.unwrap();
-        file.write_all(&chunk).await.unwrap();
+        let chunk = item?;
+        if may_be_page && downloaded == 0 && portal_page::looks_like_html(&chunk) {
+            let page = String::from_utf8_lossy(&chunk);
+            if let Some(e) = portal_page::rejection(&page, "the requested file") {
+                return Err(e);
+            }
+        }
+        if let Some(bandwidth) = transfer.bandwidth.as_ref() {
+            bandwidth.consume(chunk.len() as u64).await;
+        }
+        file.write_all(&chunk).await?;
+        digest.consume(&chunk);
         downloaded += chunk.len() as u64;
+        transfer
+            .downloaded
+            .fetch_add(chunk.len() as u64, Ordering::Relaxed);
 
         // Log the download progress
-        let _ = sr
-            .lock()
-            .unwrap()
-            .0
-            .send((downloaded as f32 / total_size as f32) * 100.0);
+        match total_size {
+            Some(total_size) => log::debug!(
+                "Downloaded {} of {} bytes ({:.2}%)",
+                downloaded,
+                total_size,
+                (downloaded as f64 / total_size as f64) * 100.0
+            ),
+            None => log::debug!("Downloaded {} bytes", downloaded),
+        }
     }
+
+    // tokio files complete writes in the background, make sure they landed.
+    file.flush().await?;
+    Ok(downloaded)
 }
@@ -0,0 +1,33 @@
+use sxd_document::dom::Document;
+
+use crate::net::error::{Error, Result};
+
+/// String value of the first node matched by `xpath`, a parse error when nothing matches.
+pub fn required_string(document: &Document, xpath: &str, url: &str) -> Result<String> {
+    optional_string(document, xpath, url)?
+        .ok_or_else(|| Error::parse(url, format!("nothing matches {xpath}")))
+}
+
+/// String value of the first node matched by `xpath`, if any.
+pub fn optional_string(document: &Document, xpath: &str, url: &str) -> Result<Option<String>> {
+    match sxd_xpath::evaluate_xpath(document, xpath) {
+        Ok(sxd_xpath::Value::Nodeset(ns)) => {
+            Ok(ns.document_order_first().map(|n| n.string_value()))
+        }
+        Ok(value) => Ok(Some(value.string())),
+        Err(e) => Err(Error::parse(url, format!("{xpath}: {e}"))),
+    }
+}
+
+/// String values of every node matched by `xpath`, in document order.
+pub fn strings(document: &Document, xpath: &str, url: &str) -> Result<Vec<String>> {
+    match sxd_xpath::evaluate_xpath(document, xpath) {
+        Ok(sxd_xpath::Value::Nodeset(ns)) => Ok(ns
+            .document_order()
+            .into_iter()
+            .map(|n| n.string_value())
+            .collect()),
+        Ok(value) => Ok(vec![value.string()]),
+        Err(e) => Err(Error::parse(url, format!("{xpath}: {e}"))),
+    }
+}
@@ -0,0 +1,19 @@
+pub mod binary;
+pub mod error;
+pub mod features;
+pub mod position;
+pub mod session;
+pub mod spikes;
+
+pub use binary::{BinaryFormat, BinaryRecording, Dtype, Endianness, Sample};
+pub use error::{Error, Result};
+pub use features::{parse_fet, Features, TIME_COLUMN};
+pub use position::{
+    interpolate_gaps, parse_whl, Led, PositionSample, PositionSeries, MAX_INTERPOLATED_GAP,
+    WHL_SAMPLING_RATE,
+};
+pub use session::{session_xml_path, AnatomicalGroup, SessionParameters, SpikeGroup};
+pub use spikes::{
+    parse_clu, parse_res, SpikeFiles, SpikeTrain, SpikeTrains, SpikeWaveforms, UnitId,
+    NOISE_CLUSTERS,
+};
@@ -0,0 +1,320 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use ndarray::{s, Array2, ArrayView2};
+
+use crate::files::file_extension;
+use crate::recording::{Error, Result, SessionParameters};
+
+/// Type of the samples of a binary recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dtype {
+    I8,
+    I16,
+    I32,
+    F32,
+}
+
+impl Dtype {
+    /// Integer type of the `nBits` of a session xml.
+    pub fn from_bits(n_bits: u32) -> Option<Self> {
+        match n_bits {
+            8 => Some(Dtype::I8),
+            16 => Some(Dtype::I16),
+            32 => Some(Dtype::I32),
+            _ => None,
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Dtype::I8 => 1,
+            Dtype::I16 => 2,
+            Dtype::I32 | Dtype::F32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], endianness: Endianness) -> f64 {
+        macro_rules! decode {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                match endianness {
+                    Endianness::Little => <$t>::from_le_bytes(bytes) as f64,
+                    Endianness::Big => <$t>::from_be_bytes(bytes) as f64,
+                }
+            }};
+        }
+        match self {
+            Dtype::I8 => bytes[0] as i8 as f64,
+            Dtype::I16 => decode!(i16),
+            Dtype::I32 => decode!(i32),
+            Dtype::F32 => decode!(f32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// What the acquisition systems of the CRCNS datasets write.
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub fn is_native(self) -> bool {
+        match self {
+            Endianness::Little => cfg!(target_endian = "little"),
+            Endianness::Big => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+/// Rust type of a [`Dtype`], for zero-copy views.
+pub trait Sample: bytemuck::Pod {
+    const DTYPE: Dtype;
+}
+
+impl Sample for i8 {
+    const DTYPE: Dtype = Dtype::I8;
+}
+
+impl Sample for i16 {
+    const DTYPE: Dtype = Dtype::I16;
+}
+
+impl Sample for i32 {
+    const DTYPE: Dtype = Dtype::I32;
+}
+
+impl Sample for f32 {
+    const DTYPE: Dtype = Dtype::F32;
+}
+
+/// Layout of a binary recording with channels interleaved sample by sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryFormat {
+    pub n_channels: usize,
+    /// In Hz.
+    pub sampling_rate: f64,
+    pub dtype: Dtype,
+    pub endianness: Endianness,
+    pub microvolts_per_bit: Option<f64>,
+}
+
+impl BinaryFormat {
+    /// Format of the wideband `.dat` or the downsampled `.eeg`/`.lfp` file of a
+    /// session.
+    pub fn for_file(filepath: &Path, parameters: &SessionParameters) -> Result<Self> {
+        let sampling_rate = match file_extension(&filepath.to_string_lossy()).as_str() {
+            "dat" => parameters.sampling_rate,
+            "eeg" | "lfp" => parameters.lfp_sampling_rate,
+            extension => {
+                return Err(Error::unsupported(
+                    filepath,
+                    format!("{extension:?} is not a .dat, .eeg or .lfp file"),
+                ))
+            }
+        };
+        let dtype = Dtype::from_bits(parameters.n_bits).ok_or_else(|| {
+            Error::unsupported(
+                filepath,
+                format!("{} bit samples are not supported", parameters.n_bits),
+            )
+        })?;
+        Ok(BinaryFormat {
+            n_channels: parameters.n_channels,
+            sampling_rate,
+            dtype,
+            endianness: Endianness::Little,
+            microvolts_per_bit: parameters.microvolts_per_bit(),
+        })
+    }
+
+    /// Bytes of one sample of every channel.
+    pub fn frame_size(&self) -> usize {
+        self.n_channels * self.dtype.size()
+    }
+}
+
+/// A memory-mapped `.dat`, `.eeg` or `.lfp` file. Nothing is read until asked
+/// for, and a partial frame at the end of the file is ignored.
+#[derive(Debug)]
+pub struct BinaryRecording {
+    filepath: PathBuf,
+    mmap: Mmap,
+    format: BinaryFormat,
+    n_samples: usize,
+}
+
+impl BinaryRecording {
+    /// Opens a recording of a session with the layout of its xml.
+    pub fn open(filepath: &Path, parameters: &SessionParameters) -> Result<Self> {
+        Self::open_with(filepath, BinaryFormat::for_file(filepath, parameters)?)
+    }
+
+    pub fn open_with(filepath: &Path, format: BinaryFormat) -> Result<Self> {
+        if format.n_channels == 0 || format.sampling_rate <= 0.0 {
+            return Err(Error::unsupported(
+                filepath,
+                format!(
+                    "{} channels at {} Hz",
+                    format.n_channels, format.sampling_rate
+                ),
+            ));
+        }
+        let file = std::fs::File::open(filepath).map_err(|e| Error::io(filepath, e))?;
+        // The mapping is only read, a file truncated while mapped is the usual
+        // risk of reading recordings this way.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| Error::io(filepath, e))?;
+        let n_samples = mmap.len() / format.frame_size();
+        Ok(BinaryRecording {
+            filepath: filepath.to_path_buf(),
+            mmap,
+            format,
+            n_samples,
+        })
+    }
+
+    pub fn format(&self) -> &BinaryFormat {
+        &self.format
+    }
+
+    pub fn n_channels(&self) -> usize {
+        self.format.n_channels
+    }
+
+    /// Complete samples of every channel.
+    pub fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+
+    /// In seconds.
+    pub fn duration(&self) -> f64 {
+        self.n_samples as f64 / self.format.sampling_rate
+    }
+
+    /// Bytes after the last complete sample, left by an interrupted copy or
+    /// recording.
+    pub fn trailing_bytes(&self) -> usize {
+        self.mmap.len() - self.n_samples * self.format.frame_size()
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.trailing_bytes() > 0
+    }
+
+    /// The whole recording as a samples × channels array borrowing the mapping.
+    /// Only possible when `T` is the recording's type in native byte order.
+    pub fn view<T: Sample>(&self) -> Result<ArrayView2<'_, T>> {
+        if T::DTYPE != self.format.dtype || !self.format.endianness.is_native() {
+            return Err(Error::unsupported(
+                &self.filepath,
+                format!(
+                    "a {:?} {:?} endian recording cannot be viewed as {:?}",
+                    self.format.dtype,
+                    self.format.endianness,
+                    T::DTYPE
+                ),
+            ));
+        }
+        let bytes = &self.mmap[..self.n_samples * self.format.frame_size()];
+        let samples: &[T] =
+            bytemuck::try_cast_slice(bytes).map_err(|e| Error::unsupported(&self.filepath, e))?;
+        Ok(ArrayView2::from_shape((self.n_samples, self.format.n_channels), samples).unwrap())
+    }
+
+    /// Samples from `start` seconds lasting `duration` seconds, clamped to the
+    /// recording.
+    pub fn sample_range(&self, start: f64, duration: f64) -> Range<usize> {
+        let sample = |seconds: f64| {
+            ((seconds * self.format.sampling_rate).round().max(0.0) as usize).min(self.n_samples)
+        };
+        let first = sample(start);
+        first..sample(start + duration).max(first)
+    }
+
+    /// `samples` of the contiguous `channels` as a samples × channels slice of
+    /// [`BinaryRecording::view`], nothing is copied.
+    pub fn window<T: Sample>(
+        &self,
+        samples: Range<usize>,
+        channels: Range<usize>,
+    ) -> Result<ArrayView2<'_, T>> {
+        self.check_samples(&samples)?;
+        if channels.end > self.format.n_channels || channels.start > channels.end {
+            return Err(Error::unsupported(
+                &self.filepath,
+                format!(
+                    "channels {}..{} are out of the {} recorded",
+                    channels.start, channels.end, self.format.n_channels
+                ),
+            ));
+        }
+        Ok(self.view()?.slice_move(s![samples, channels]))
+    }
+
+    /// Values of `channels` (every channel when empty) over `samples` converted to
+    /// `f64`, as a samples × channels array. Unlike [`BinaryRecording::window`] it
+    /// copies, but reads any type, byte order and channel order.
+    pub fn read(&self, samples: Range<usize>, channels: &[usize]) -> Result<Array2<f64>> {
+        self.check_samples(&samples)?;
+        let all: Vec<usize>;
+        let channels = if channels.is_empty() {
+            all = (0..self.format.n_channels).collect();
+            &all
+        } else {
+            channels
+        };
+        if let Some(channel) = channels.iter().find(|c| **c >= self.format.n_channels) {
+            return Err(Error::unsupported(
+                &self.filepath,
+                format!(
+                    "channel {channel} is out of the {} recorded",
+                    self.format.n_channels
+                ),
+            ));
+        }
+
+        let size = self.format.dtype.size();
+        let frame_size = self.format.frame_size();
+        let mut window = Array2::zeros((samples.len(), channels.len()));
+        for (row, sample) in samples.enumerate() {
+            let frame = &self.mmap[sample * frame_size..(sample + 1) * frame_size];
+            for (column, channel) in channels.iter().enumerate() {
+                let bytes = &frame[channel * size..(channel + 1) * size];
+                window[[row, column]] = self.format.dtype.decode(bytes, self.format.endianness);
+            }
+        }
+        Ok(window)
+    }
+
+    /// [`BinaryRecording::read`] scaled to microvolts with the session's gain.
+    pub fn read_microvolts(
+        &self,
+        samples: Range<usize>,
+        channels: &[usize],
+    ) -> Result<Array2<f64>> {
+        let scale = self.format.microvolts_per_bit.ok_or_else(|| {
+            Error::unsupported(
+                &self.filepath,
+                "the session gives no voltage range or amplification",
+            )
+        })?;
+        Ok(self.read(samples, channels)? * scale)
+    }
+
+    fn check_samples(&self, samples: &Range<usize>) -> Result<()> {
+        if samples.end > self.n_samples || samples.start > samples.end {
+            return Err(Error::unsupported(
+                &self.filepath,
+                format!(
+                    "samples {}..{} are out of the {} recorded",
+                    samples.start, samples.end, self.n_samples
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why a recording or its session parameters could not be read.
+#[derive(Debug)]
+pub enum Error {
+    /// The session `.xml` the readers need is not on disk.
+    MissingParameters(PathBuf),
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file does not have the expected structure.
+    Parse { path: PathBuf, reason: String },
+    /// The file can be read, but not the way it was asked for.
+    Unsupported { path: PathBuf, reason: String },
+}
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn parse(path: impl Into<PathBuf>, reason: impl ToString) -> Self {
+        Error::Parse {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn unsupported(path: impl Into<PathBuf>, reason: impl ToString) -> Self {
+        Error::Unsupported {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingParameters(path) => write!(
+                f,
+                "Session parameters {} are missing, download the session's .xml first",
+                path.display()
+            ),
+            Error::Io { path, source } => {
+                write!(f, "Unable to access {}: {source}", path.display())
+            }
+            Error::Parse { path, reason } => {
+                write!(f, "Unable to parse {}: {reason}", path.display())
+            }
+            Error::Unsupported { path, reason } => {
+                write!(f, "Unable to read {}: {reason}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
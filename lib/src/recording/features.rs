@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use ndarray::{Array2, ArrayView1};
+use polars::prelude::{DataFrame, NamedFrom, PolarsResult, Series};
+
+use crate::recording::{Error, Result, SpikeGroup};
+
+/// Name of the last column of a `.fet` file, the spike time in samples.
+pub const TIME_COLUMN: &str = "time";
+
+/// Features of the spikes of one shank, from its `.fet.N` file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Features {
+    /// `ch<channel>_pc<n>` for the principal components of each channel of the
+    /// spike group, `extra<n>` for what Klusters appends after them, then
+    /// [`TIME_COLUMN`].
+    pub columns: Vec<String>,
+    /// Spikes × columns.
+    pub values: Array2<i64>,
+    /// Cluster of each spike from the `.clu.N` file, once joined.
+    pub clusters: Option<Vec<u32>>,
+}
+
+impl Features {
+    pub fn read(filepath: &Path, group: &SpikeGroup) -> Result<Self> {
+        let content = std::fs::read_to_string(filepath).map_err(|e| Error::io(filepath, e))?;
+        parse_fet(&content, filepath, group)
+    }
+
+    /// Labels each spike with its cluster, `clusters` being in `.res` order as
+    /// the spikes are.
+    pub fn with_clusters(mut self, clusters: Vec<u32>) -> std::result::Result<Self, String> {
+        self.set_clusters(clusters)?;
+        Ok(self)
+    }
+
+    /// [`Features::with_clusters`] in place, the features are left as they were
+    /// when the counts differ.
+    pub fn set_clusters(&mut self, clusters: Vec<u32>) -> std::result::Result<(), String> {
+        if clusters.len() != self.n_spikes() {
+            return Err(format!(
+                "{} cluster ids for {} spikes",
+                clusters.len(),
+                self.n_spikes()
+            ));
+        }
+        self.clusters = Some(clusters);
+        Ok(())
+    }
+
+    pub fn n_spikes(&self) -> usize {
+        self.values.nrows()
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == name)
+    }
+
+    pub fn column(&self, name: &str) -> Option<ArrayView1<'_, i64>> {
+        self.column_index(name).map(|i| self.values.column(i))
+    }
+
+    /// Points of features `x` and `y` by cluster, everything under cluster 0 when
+    /// no clusters were joined.
+    pub fn scatter(&self, x: usize, y: usize) -> Vec<(u32, Vec<[f64; 2]>)> {
+        let mut clusters: Vec<(u32, Vec<[f64; 2]>)> = Vec::new();
+        if x >= self.columns.len() || y >= self.columns.len() {
+            return clusters;
+        }
+        for (spike, row) in self.values.rows().into_iter().enumerate() {
+            let cluster = self.clusters.as_ref().map_or(0, |c| c[spike]);
+            let point = [row[x] as f64, row[y] as f64];
+            match clusters.iter_mut().find(|(c, _)| *c == cluster) {
+                Some((_, points)) => points.push(point),
+                None => clusters.push((cluster, vec![point])),
+            }
+        }
+        clusters.sort_by_key(|(c, _)| *c);
+        clusters
+    }
+
+    /// The features as a frame with the column names, plus a `cluster` column
+    /// once joined.
+    pub fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        let mut columns: Vec<Series> = self
+            .columns
+            .iter()
+            .zip(self.values.columns())
+            .map(|(name, values)| Series::new(name, values.to_vec()))
+            .collect();
+        if let Some(clusters) = &self.clusters {
+            columns.push(Series::new("cluster", clusters.as_slice()));
+        }
+        DataFrame::new(columns)
+    }
+}
+
+/// Parses a `.fet` file, a first line with the number of columns then one line of
+/// space separated integers per spike.
+pub fn parse_fet(content: &str, filepath: &Path, group: &SpikeGroup) -> Result<Features> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let n_columns: usize = match lines.next() {
+        Some((_, line)) => line
+            .parse()
+            .map_err(|_| Error::parse(filepath, format!("{line:?} is not a number of features")))?,
+        None => return Err(Error::parse(filepath, "no number of features")),
+    };
+    let n_components = group.channels.len() * group.n_features;
+    if n_columns < n_components + 1 {
+        return Err(Error::parse(
+            filepath,
+            format!(
+                "{n_columns} columns, the spike group needs {} principal components and a time",
+                n_components
+            ),
+        ));
+    }
+
+    let mut values = Vec::new();
+    let mut n_spikes = 0;
+    for (n, line) in lines {
+        let before = values.len();
+        for value in line.split_whitespace() {
+            values.push(value.parse::<i64>().map_err(|_| {
+                Error::parse(filepath, format!("line {n}: {value:?} is not an integer"))
+            })?);
+        }
+        if values.len() - before != n_columns {
+            return Err(Error::parse(
+                filepath,
+                format!(
+                    "line {n}: {} values, expected {n_columns}",
+                    values.len() - before
+                ),
+            ));
+        }
+        n_spikes += 1;
+    }
+
+    let mut columns = Vec::with_capacity(n_columns);
+    for channel in &group.channels {
+        for pc in 1..=group.n_features {
+            columns.push(format!("ch{channel}_pc{pc}"));
+        }
+    }
+    for extra in 1..n_columns - n_components {
+        columns.push(format!("extra{extra}"));
+    }
+    columns.push(TIME_COLUMN.to_string());
+
+    Ok(Features {
+        columns,
+        values: Array2::from_shape_vec((n_spikes, n_columns), values).unwrap(),
+        clusters: None,
+    })
+}
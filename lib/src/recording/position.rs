@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use crate::recording::{Error, Result};
+
+/// Rate of the `.whl` files, the 1250 Hz lfp rate divided by 32.
+pub const WHL_SAMPLING_RATE: f64 = 39.0625;
+/// Longest run of missing samples filled by linear interpolation, in seconds.
+pub const MAX_INTERPOLATED_GAP: f64 = 0.5;
+
+/// Coordinates of one LED, `None` where the tracker lost it.
+pub type Led = Option<[f64; 2]>;
+
+/// One position sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PositionSample {
+    /// In seconds from the start of the recording.
+    pub time: f64,
+    /// Midpoint of the LEDs, or the one that was seen.
+    pub position: Option<[f64; 2]>,
+    /// In radians, from the second LED of each line to the first one. Needs both.
+    pub head_direction: Option<f64>,
+    /// In position units per second.
+    pub speed: Option<f64>,
+}
+
+/// Position of the animal during a session, from its `.whl` file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionSeries {
+    pub sampling_rate: f64,
+    pub samples: Vec<PositionSample>,
+}
+
+impl PositionSeries {
+    /// Reads a `.whl` file, filling gaps up to [`MAX_INTERPOLATED_GAP`].
+    pub fn read(filepath: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(filepath).map_err(|e| Error::io(filepath, e))?;
+        let leds = parse_whl(&content, filepath)?;
+        let max_gap = (MAX_INTERPOLATED_GAP * WHL_SAMPLING_RATE).round() as usize;
+        Ok(Self::from_leds(leds, WHL_SAMPLING_RATE, max_gap))
+    }
+
+    /// Positions from one or two LEDs per sample, interpolating runs of up to
+    /// `max_gap` missing samples of each LED.
+    pub fn from_leds(leds: Vec<Vec<Led>>, sampling_rate: f64, max_gap: usize) -> Self {
+        let n_leds = leds.first().map_or(0, Vec::len);
+        let mut tracks: Vec<Vec<Led>> = (0..n_leds)
+            .map(|led| leds.iter().map(|sample| sample[led]).collect())
+            .collect();
+        for track in tracks.iter_mut() {
+            interpolate_gaps(track, max_gap);
+        }
+
+        let mut samples: Vec<PositionSample> = (0..leds.len())
+            .map(|i| {
+                let seen: Vec<[f64; 2]> = tracks.iter().filter_map(|t| t[i]).collect();
+                let position = (!seen.is_empty()).then(|| {
+                    let n = seen.len() as f64;
+                    [
+                        seen.iter().map(|p| p[0]).sum::<f64>() / n,
+                        seen.iter().map(|p| p[1]).sum::<f64>() / n,
+                    ]
+                });
+                let head_direction = match tracks.as_slice() {
+                    [front, back, ..] => match (front[i], back[i]) {
+                        (Some(f), Some(b)) => Some((f[1] - b[1]).atan2(f[0] - b[0])),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                PositionSample {
+                    time: i as f64 / sampling_rate,
+                    position,
+                    head_direction,
+                    speed: None,
+                }
+            })
+            .collect();
+
+        // Centered differences, one-sided at the ends and next to gaps.
+        for i in 0..samples.len() {
+            let before = i.checked_sub(1).filter(|j| samples[*j].position.is_some());
+            let after =
+                Some(i + 1).filter(|j| samples.get(*j).is_some_and(|s| s.position.is_some()));
+            let (from, to) = match (before, after) {
+                (Some(b), Some(a)) => (b, a),
+                (Some(b), None) => (b, i),
+                (None, Some(a)) => (i, a),
+                (None, None) => continue,
+            };
+            if let (Some(p), Some(q)) = (samples[from].position, samples[to].position) {
+                let distance = ((q[0] - p[0]).powi(2) + (q[1] - p[1]).powi(2)).sqrt();
+                samples[i].speed = Some(distance * sampling_rate / (to - from) as f64);
+            }
+        }
+
+        PositionSeries {
+            sampling_rate,
+            samples,
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sampling_rate
+    }
+
+    pub fn max_speed(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .filter_map(|s| s.speed)
+            .max_by(|a, b| a.total_cmp(b))
+    }
+}
+
+/// LEDs of each line of a `.whl` file, `x y` of one LED or `x1 y1 x2 y2` of two,
+/// negative coordinates meaning the LED was not seen.
+pub fn parse_whl(content: &str, filepath: &Path) -> Result<Vec<Vec<Led>>> {
+    let mut samples = Vec::new();
+    let mut n_columns = None;
+
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let values = line
+            .split_whitespace()
+            .map(|v| v.parse::<f64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::parse(filepath, format!("line {}: {line:?}", n + 1)))?;
+        if values.len() % 2 != 0 || *n_columns.get_or_insert(values.len()) != values.len() {
+            return Err(Error::parse(
+                filepath,
+                format!("line {}: {} columns", n + 1, values.len()),
+            ));
+        }
+        samples.push(
+            values
+                .chunks(2)
+                .map(|xy| (xy[0] >= 0.0 && xy[1] >= 0.0).then_some([xy[0], xy[1]]))
+                .collect(),
+        );
+    }
+
+    Ok(samples)
+}
+
+/// Fills runs of up to `max_gap` missing samples between two seen ones linearly.
+pub fn interpolate_gaps(track: &mut [Led], max_gap: usize) {
+    let mut last_seen: Option<usize> = None;
+    for i in 0..track.len() {
+        let Some(to) = track[i] else {
+            continue;
+        };
+        if let Some(start) = last_seen {
+            let gap = i - start - 1;
+            if gap > 0 && gap <= max_gap {
+                let from = track[start].unwrap();
+                for (j, led) in track[start + 1..i].iter_mut().enumerate() {
+                    let t = (j + 1) as f64 / (i - start) as f64;
+                    *led = Some([
+                        from[0] + (to[0] - from[0]) * t,
+                        from[1] + (to[1] - from[1]) * t,
+                    ]);
+                }
+            }
+        }
+        last_seen = Some(i);
+    }
+}
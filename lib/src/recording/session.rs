@@ -0,0 +1,268 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory, Value};
+
+use crate::recording::{Error, Result};
+
+/// Lfp sampling rate Neuroscope assumes when the xml does not give one.
+const DEFAULT_LFP_SAMPLING_RATE: f64 = 1250.0;
+/// Principal components per channel Klusters assumes when the xml does not give one.
+const DEFAULT_FEATURES_PER_CHANNEL: usize = 3;
+
+/// Channels of one anatomical group, usually a shank, in display order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnatomicalGroup {
+    pub channels: Vec<usize>,
+}
+
+/// Channels of one spike group and the layout of its `.spk` and `.fet` files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpikeGroup {
+    pub channels: Vec<usize>,
+    /// Samples per channel of each waveform in the `.spk` file.
+    pub n_samples: usize,
+    pub peak_sample_index: usize,
+    /// Principal components per channel in the `.fet` file.
+    pub n_features: usize,
+}
+
+/// Recording parameters of a session, from the Neuroscope/Klusters `.xml` next to
+/// its data files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionParameters {
+    pub n_bits: u32,
+    pub n_channels: usize,
+    /// Wideband (`.dat`) sampling rate in Hz.
+    pub sampling_rate: f64,
+    /// Sampling rate of the downsampled `.eeg`/`.lfp` file in Hz.
+    pub lfp_sampling_rate: f64,
+    /// Peak to peak input range of the acquisition system in volts.
+    pub voltage_range: Option<f64>,
+    pub amplification: Option<f64>,
+    pub offset: f64,
+    pub anatomical_groups: Vec<AnatomicalGroup>,
+    /// The spike groups in file order, group `n` is the shank of `.spk.n`.
+    pub spike_groups: Vec<SpikeGroup>,
+    /// Channels marked `skip="1"` in the anatomical description, sorted.
+    pub skipped_channels: Vec<usize>,
+}
+
+impl SessionParameters {
+    /// Reads the parameters of the session `recording` belongs to, see
+    /// [`session_xml_path`].
+    pub fn for_recording(recording: &Path) -> Result<Self> {
+        Self::read(&session_xml_path(recording))
+    }
+
+    pub fn read(filepath: &Path) -> Result<Self> {
+        match std::fs::read_to_string(filepath) {
+            Ok(content) => Self::parse(&content, filepath),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(Error::MissingParameters(filepath.to_path_buf()))
+            }
+            Err(e) => Err(Error::io(filepath, e)),
+        }
+    }
+
+    /// Parses the content of a session `.xml`, `filepath` is only used in errors.
+    pub fn parse(content: &str, filepath: &Path) -> Result<Self> {
+        let package =
+            sxd_document::parser::parse(content).map_err(|e| Error::parse(filepath, e))?;
+        let document = package.as_document();
+        let root: Node = document.root().into();
+        let xml = Xml { filepath };
+
+        let n_bits: u32 = xml.required(root, "/parameters/acquisitionSystem/nBits")?;
+        if n_bits == 0 || !n_bits.is_multiple_of(8) || n_bits > 32 {
+            return Err(Error::parse(
+                filepath,
+                format!("nBits is {n_bits}, expected 8, 16, 24 or 32"),
+            ));
+        }
+        let n_channels: usize = xml.required(root, "/parameters/acquisitionSystem/nChannels")?;
+        if n_channels == 0 {
+            return Err(Error::parse(filepath, "nChannels is 0"));
+        }
+
+        let mut anatomical_groups = Vec::new();
+        let mut skipped_channels = Vec::new();
+        for group in xml.nodes(
+            root,
+            "/parameters/anatomicalDescription/channelGroups/group",
+        ) {
+            let mut channels = Vec::new();
+            for channel in xml.nodes(group, "channel") {
+                let index = xml.channel(channel, n_channels)?;
+                if xml.optional::<u8>(channel, "@skip")?.unwrap_or(0) != 0 {
+                    skipped_channels.push(index);
+                }
+                channels.push(index);
+            }
+            anatomical_groups.push(AnatomicalGroup { channels });
+        }
+        skipped_channels.sort_unstable();
+        skipped_channels.dedup();
+
+        // Older files give the waveform layout once for every group.
+        let shared_n_samples = xml.optional(root, "/parameters/spikes/nSamples")?;
+        let shared_peak = xml.optional(root, "/parameters/spikes/peakSampleIndex")?;
+        let shared_n_features = xml.optional(root, "/parameters/spikes/nFeatures")?;
+        let mut spike_groups = Vec::new();
+        for (index, group) in xml
+            .nodes(root, "/parameters/spikeDetection/channelGroups/group")
+            .into_iter()
+            .enumerate()
+        {
+            let channels = xml
+                .nodes(group, "channels/channel")
+                .into_iter()
+                .map(|channel| xml.channel(channel, n_channels))
+                .collect::<Result<Vec<_>>>()?;
+            let n_samples: usize = xml
+                .optional(group, "nSamples")?
+                .or(shared_n_samples)
+                .ok_or_else(|| {
+                    Error::parse(
+                        filepath,
+                        format!("spike group {} has no nSamples", index + 1),
+                    )
+                })?;
+            let peak_sample_index = xml
+                .optional(group, "peakSampleIndex")?
+                .or(shared_peak)
+                .unwrap_or(n_samples / 2);
+            if peak_sample_index >= n_samples {
+                return Err(Error::parse(
+                    filepath,
+                    format!(
+                        "spike group {} has its peak at sample {peak_sample_index} of {n_samples}",
+                        index + 1
+                    ),
+                ));
+            }
+            let n_features = xml
+                .optional(group, "nFeatures")?
+                .or(shared_n_features)
+                .unwrap_or(DEFAULT_FEATURES_PER_CHANNEL);
+            spike_groups.push(SpikeGroup {
+                channels,
+                n_samples,
+                peak_sample_index,
+                n_features,
+            });
+        }
+
+        Ok(SessionParameters {
+            n_bits,
+            n_channels,
+            sampling_rate: xml.required(root, "/parameters/acquisitionSystem/samplingRate")?,
+            lfp_sampling_rate: xml
+                .optional(root, "/parameters/fieldPotentials/lfpSamplingRate")?
+                .unwrap_or(DEFAULT_LFP_SAMPLING_RATE),
+            voltage_range: xml.optional(root, "/parameters/acquisitionSystem/voltageRange")?,
+            amplification: xml.optional(root, "/parameters/acquisitionSystem/amplification")?,
+            offset: xml
+                .optional(root, "/parameters/acquisitionSystem/offset")?
+                .unwrap_or(0.0),
+            anatomical_groups,
+            spike_groups,
+            skipped_channels,
+        })
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        self.n_bits as usize / 8
+    }
+
+    /// Spike group of the `.spk.n`, `.res.n`, `.clu.n` and `.fet.n` files, `shank`
+    /// counting from 1.
+    pub fn spike_group(&self, shank: usize) -> Option<&SpikeGroup> {
+        self.spike_groups.get(shank.checked_sub(1)?)
+    }
+
+    pub fn is_skipped(&self, channel: usize) -> bool {
+        self.skipped_channels.binary_search(&channel).is_ok()
+    }
+
+    /// Microvolts of one step of the recorded integers, when the xml gives the
+    /// voltage range and amplification.
+    pub fn microvolts_per_bit(&self) -> Option<f64> {
+        let (range, amplification) = (self.voltage_range?, self.amplification?);
+        (amplification != 0.0).then(|| range / 2f64.powi(self.n_bits as i32) / amplification * 1e6)
+    }
+}
+
+/// The `.xml` of the session a recording file belongs to, named after the file
+/// without its extension and shank number: `ec012ec.188.res.1` is described by
+/// `ec012ec.188.xml`.
+pub fn session_xml_path(recording: &Path) -> PathBuf {
+    let name = recording
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut parts: Vec<&str> = name.split('.').collect();
+    if parts.len() > 2 && parts.last().is_some_and(|p| p.parse::<u32>().is_ok()) {
+        parts.pop();
+    }
+    if parts.len() > 1 {
+        parts.pop();
+    }
+    recording.with_file_name(format!("{}.xml", parts.join(".")))
+}
+
+struct Xml<'a> {
+    filepath: &'a Path,
+}
+
+impl Xml<'_> {
+    fn evaluate<'d>(&self, node: Node<'d>, xpath: &str) -> Value<'d> {
+        let xpath = Factory::new()
+            .build(xpath)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| panic!("invalid xpath {xpath}"));
+        xpath
+            .evaluate(&Context::new(), node)
+            .unwrap_or_else(|e| panic!("unable to evaluate an xpath: {e}"))
+    }
+
+    fn nodes<'d>(&self, node: Node<'d>, xpath: &str) -> Vec<Node<'d>> {
+        match self.evaluate(node, xpath) {
+            Value::Nodeset(nodes) => nodes.document_order(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn optional<T: FromStr>(&self, node: Node, xpath: &str) -> Result<Option<T>> {
+        let Some(text) = self
+            .nodes(node, xpath)
+            .first()
+            .map(|n| n.string_value().trim().to_string())
+            .filter(|t| !t.is_empty())
+        else {
+            return Ok(None);
+        };
+        text.parse()
+            .map(Some)
+            .map_err(|_| Error::parse(self.filepath, format!("{xpath} is {text:?}")))
+    }
+
+    fn required<T: FromStr>(&self, node: Node, xpath: &str) -> Result<T> {
+        self.optional(node, xpath)?
+            .ok_or_else(|| Error::parse(self.filepath, format!("{xpath} is missing")))
+    }
+
+    fn channel(&self, node: Node, n_channels: usize) -> Result<usize> {
+        let channel: usize = self.required(node, ".")?;
+        if channel >= n_channels {
+            return Err(Error::parse(
+                self.filepath,
+                format!("channel {channel} is out of the {n_channels} recorded"),
+            ));
+        }
+        Ok(channel)
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use ndarray::ArrayView2;
+
+use crate::recording::{
+    BinaryFormat, BinaryRecording, Dtype, Endianness, Error, Result, SessionParameters, SpikeGroup,
+};
+
+/// Clusters Klusters reserves for artifacts (0) and unsorted noise (1).
+pub const NOISE_CLUSTERS: [u32; 2] = [0, 1];
+
+/// A sorted unit, clusters are numbered per shank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnitId {
+    /// Counting from 1, as in the file names.
+    pub shank: usize,
+    pub cluster: u32,
+}
+
+/// Spikes of one unit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpikeTrain {
+    /// In seconds from the start of the recording.
+    pub times: Vec<f64>,
+    /// Position of each spike in its shank's `.res`, `.spk` and `.fet` files.
+    pub spike_indices: Vec<usize>,
+}
+
+impl SpikeTrain {
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+}
+
+/// Files of one shank of a session, `<session>.<kind>.<shank>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpikeFiles {
+    pub shank: usize,
+    pub res: PathBuf,
+    pub clu: PathBuf,
+    pub spk: PathBuf,
+    pub fet: PathBuf,
+}
+
+impl SpikeFiles {
+    pub fn new(directory: &Path, session: &str, shank: usize) -> Self {
+        Self::locate(shank, |extension| {
+            directory.join(format!("{session}.{extension}"))
+        })
+    }
+
+    /// Files of `shank` wherever `locate` finds each of them from its extension,
+    /// `res.1` for the `.res` of shank 1.
+    pub fn locate(shank: usize, locate: impl Fn(&str) -> PathBuf) -> Self {
+        let path = |kind: &str| locate(&format!("{kind}.{shank}"));
+        SpikeFiles {
+            shank,
+            res: path("res"),
+            clu: path("clu"),
+            spk: path("spk"),
+            fet: path("fet"),
+        }
+    }
+}
+
+/// Sorted units of a session from its `.res.N` and `.clu.N` files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpikeTrains {
+    pub units: BTreeMap<UnitId, SpikeTrain>,
+    /// Spikes of each shank read, excluded clusters included, which is the number
+    /// of waveforms in its `.spk` and of rows in its `.fet`.
+    pub shank_spikes: BTreeMap<usize, usize>,
+    pub files: Vec<SpikeFiles>,
+    /// Shanks of the session with neither a `.res` nor a `.clu` file, left out.
+    pub missing_shanks: Vec<usize>,
+}
+
+impl SpikeTrains {
+    /// Reads the shanks of the session described by `xml` from the files next to
+    /// it, see [`SpikeTrains::read_with`].
+    pub fn read(xml: &Path, parameters: &SessionParameters, excluded: &[u32]) -> Result<Self> {
+        let directory = xml.parent().unwrap_or(Path::new(""));
+        let session = xml
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::read_with(parameters, excluded, |extension| {
+            directory.join(format!("{session}.{extension}"))
+        })
+    }
+
+    /// Reads the shanks that have both a `.res` and a `.clu` file, each found by
+    /// `locate` as in [`SpikeFiles::locate`], leaving out the `excluded` clusters,
+    /// usually [`NOISE_CLUSTERS`]. Shanks with neither are listed in
+    /// `missing_shanks`.
+    pub fn read_with(
+        parameters: &SessionParameters,
+        excluded: &[u32],
+        locate: impl Fn(&str) -> PathBuf,
+    ) -> Result<Self> {
+        let mut trains = SpikeTrains::default();
+        for shank in 1..=parameters.spike_groups.len() {
+            trains.read_shank(parameters, shank, excluded, &locate)?;
+        }
+        Ok(trains)
+    }
+
+    /// Reads `shank` alone as [`SpikeTrains::read_with`] does, the files of the
+    /// other shanks are not opened.
+    pub fn read_shank(
+        &mut self,
+        parameters: &SessionParameters,
+        shank: usize,
+        excluded: &[u32],
+        locate: impl Fn(&str) -> PathBuf,
+    ) -> Result<()> {
+        let files = SpikeFiles::locate(shank, locate);
+        let (res, clu) = match (read_optional(&files.res)?, read_optional(&files.clu)?) {
+            (Some(res), Some(clu)) => (res, clu),
+            (None, None) => {
+                self.missing_shanks.push(shank);
+                return Ok(());
+            }
+            (Some(_), None) => return Err(Error::io(&files.clu, ErrorKind::NotFound.into())),
+            (None, Some(_)) => return Err(Error::io(&files.res, ErrorKind::NotFound.into())),
+        };
+        let samples = parse_res(&res, &files.res)?;
+        let clusters = parse_clu(&clu, &files.clu)?;
+        self.add_shank(
+            shank,
+            &samples,
+            &clusters,
+            parameters.sampling_rate,
+            excluded,
+        )
+        .map_err(|reason| Error::parse(&files.clu, reason))?;
+        self.files.push(files);
+        Ok(())
+    }
+
+    /// Adds the spikes of a shank from its `.res` sample indices and `.clu`
+    /// cluster ids, which must be as many.
+    pub fn add_shank(
+        &mut self,
+        shank: usize,
+        samples: &[u64],
+        clusters: &[u32],
+        sampling_rate: f64,
+        excluded: &[u32],
+    ) -> std::result::Result<(), String> {
+        if samples.len() != clusters.len() {
+            return Err(format!(
+                "{} cluster ids for {} spike times",
+                clusters.len(),
+                samples.len()
+            ));
+        }
+        for (index, (sample, cluster)) in samples.iter().zip(clusters).enumerate() {
+            if excluded.contains(cluster) {
+                continue;
+            }
+            let id = UnitId {
+                shank,
+                cluster: *cluster,
+            };
+            let train = self.units.entry(id).or_default();
+            train.times.push(*sample as f64 / sampling_rate);
+            train.spike_indices.push(index);
+        }
+        self.shank_spikes.insert(shank, samples.len());
+        Ok(())
+    }
+
+    pub fn unit(&self, shank: usize, cluster: u32) -> Option<&SpikeTrain> {
+        self.units.get(&UnitId { shank, cluster })
+    }
+
+    pub fn shank(&self, shank: usize) -> impl Iterator<Item = (&UnitId, &SpikeTrain)> {
+        self.units.iter().filter(move |(id, _)| id.shank == shank)
+    }
+
+    pub fn files(&self, shank: usize) -> Option<&SpikeFiles> {
+        self.files.iter().find(|f| f.shank == shank)
+    }
+
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+}
+
+/// Spike sample indices, one per line.
+pub fn parse_res(content: &str, filepath: &Path) -> Result<Vec<u64>> {
+    lines(content)
+        .map(|(n, line)| {
+            line.parse()
+                .map_err(|_| Error::parse(filepath, format!("line {n}: {line:?} is not a sample")))
+        })
+        .collect()
+}
+
+/// Cluster ids, after a first line with the number of clusters.
+pub fn parse_clu(content: &str, filepath: &Path) -> Result<Vec<u32>> {
+    let mut lines = lines(content);
+    match lines.next() {
+        Some((_, line)) if line.parse::<u32>().is_ok() => (),
+        Some((n, line)) => {
+            return Err(Error::parse(
+                filepath,
+                format!("line {n}: {line:?} is not a number of clusters"),
+            ))
+        }
+        None => return Err(Error::parse(filepath, "no number of clusters")),
+    }
+    lines
+        .map(|(n, line)| {
+            line.parse()
+                .map_err(|_| Error::parse(filepath, format!("line {n}: {line:?} is not a cluster")))
+        })
+        .collect()
+}
+
+/// Waveforms of a `.spk.N` file, spikes × samples × channels of 16 bit samples.
+#[derive(Debug)]
+pub struct SpikeWaveforms {
+    recording: BinaryRecording,
+    n_samples: usize,
+}
+
+impl SpikeWaveforms {
+    pub fn open(filepath: &Path, group: &SpikeGroup, sampling_rate: f64) -> Result<Self> {
+        let format = BinaryFormat {
+            n_channels: group.channels.len(),
+            sampling_rate,
+            dtype: Dtype::I16,
+            endianness: Endianness::Little,
+            microvolts_per_bit: None,
+        };
+        if group.n_samples == 0 {
+            return Err(Error::unsupported(
+                filepath,
+                "the spike group has no samples",
+            ));
+        }
+        Ok(SpikeWaveforms {
+            recording: BinaryRecording::open_with(filepath, format)?,
+            n_samples: group.n_samples,
+        })
+    }
+
+    /// Complete waveforms in the file.
+    pub fn n_spikes(&self) -> usize {
+        self.recording.n_samples() / self.n_samples
+    }
+
+    /// Samples × channels of the spike at `index` of the shank's `.res` file,
+    /// borrowing the mapping.
+    pub fn waveform(&self, index: usize) -> Result<ArrayView2<'_, i16>> {
+        self.recording.window(
+            index * self.n_samples..(index + 1) * self.n_samples,
+            0..self.recording.n_channels(),
+        )
+    }
+}
+
+fn read_optional(filepath: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(filepath) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io(filepath, e)),
+    }
+}
+
+fn lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
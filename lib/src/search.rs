@@ -0,0 +1,7 @@
+pub mod index;
+pub mod query;
+pub mod tokenize;
+
+pub use index::{Hit, SearchIndex};
+pub use query::{Clause, Query};
+pub use tokenize::tokenize;
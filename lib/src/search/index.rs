@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::search::query::{Clause, Query};
+use crate::search::tokenize::tokenize;
+use crate::types::{Collection, Dataset};
+
+/// Part of a document a token was found in, matches in aliases count the most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Alias,
+    Title,
+    Keywords,
+    Content,
+}
+
+impl Field {
+    fn weight(self) -> f64 {
+        match self {
+            Field::Alias => 5.0,
+            Field::Title => 2.0,
+            Field::Keywords => 2.0,
+            Field::Content => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Document {
+    collection: String,
+    dataset: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Posting {
+    document: usize,
+    field: Field,
+    positions: Vec<usize>,
+}
+
+/// A collection or dataset matching a query, best first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub collection: String,
+    /// `None` when the collection itself matched.
+    pub dataset: Option<String>,
+    pub score: f64,
+}
+
+/// In-memory inverted index over the catalog.
+///
+/// Collections are indexed by alias and descriptor, datasets by alias,
+/// description, extracted metadata and page content. Tokens are kept sorted so
+/// prefix queries are a range scan.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    postings: BTreeMap<String, Vec<Posting>>,
+}
+
+impl SearchIndex {
+    pub fn build(collections: &[Collection]) -> Self {
+        let mut index = SearchIndex::default();
+        for collection in collections {
+            index.add_collection(collection);
+            for dataset in collection.datasets.lock().unwrap().iter() {
+                index.add_dataset(&collection.alias, dataset);
+            }
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    fn add_collection(&mut self, collection: &Collection) {
+        let document = self.add_document(collection.alias.clone(), None);
+        self.add_field(document, Field::Alias, &collection.alias);
+        self.add_field(document, Field::Title, &collection.descriptor);
+    }
+
+    fn add_dataset(&mut self, collection: &str, dataset: &Dataset) {
+        let document = self.add_document(collection.to_string(), Some(dataset.alias.clone()));
+        self.add_field(document, Field::Alias, &dataset.alias);
+        self.add_field(document, Field::Title, &dataset.description);
+
+        let metadata = &dataset.metadata;
+        let keywords = [
+            &metadata.species,
+            &metadata.brain_regions,
+            &metadata.techniques,
+            &metadata.formats,
+        ]
+        .iter()
+        .flat_map(|values| values.iter().map(|v| v.as_str()))
+        .collect::<Vec<&str>>()
+        .join(" ");
+        self.add_field(document, Field::Keywords, &keywords);
+        self.add_field(document, Field::Content, &dataset.content);
+    }
+
+    fn add_document(&mut self, collection: String, dataset: Option<String>) -> usize {
+        self.documents.push(Document {
+            collection,
+            dataset,
+        });
+        self.documents.len() - 1
+    }
+
+    fn add_field(&mut self, document: usize, field: Field, text: &str) {
+        let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, token) in tokenize(text).into_iter().enumerate() {
+            positions.entry(token).or_default().push(position);
+        }
+        for (token, positions) in positions {
+            self.postings.entry(token).or_default().push(Posting {
+                document,
+                field,
+                positions,
+            });
+        }
+    }
+
+    /// Documents matching every clause of `query`, ranked by a field weighted
+    /// tf-idf, at most `limit` of them.
+    pub fn search(&self, query: &Query, limit: usize) -> Vec<Hit> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<usize, f64>> = None;
+        for clause in query.clauses.iter() {
+            let clause_scores = self.score(clause);
+            scores = Some(match scores {
+                None => clause_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(d, s)| clause_scores.get(&d).map(|c| (d, s + c)))
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(document, score)| Hit {
+                collection: self.documents[document].collection.clone(),
+                dataset: self.documents[document].dataset.clone(),
+                score,
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.collection.cmp(&b.collection))
+                .then_with(|| a.dataset.cmp(&b.dataset))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Score of every document matching `clause`.
+    fn score(&self, clause: &Clause) -> HashMap<usize, f64> {
+        // Occurrences per document and field.
+        let mut matches: HashMap<(usize, Field), usize> = HashMap::new();
+
+        match clause {
+            Clause::Term(term) => {
+                for posting in self.postings.get(term).into_iter().flatten() {
+                    *matches
+                        .entry((posting.document, posting.field))
+                        .or_default() += posting.positions.len();
+                }
+            }
+            Clause::Prefix(prefix) => {
+                for (_, postings) in self
+                    .postings
+                    .range(prefix.clone()..)
+                    .take_while(|(token, _)| token.starts_with(prefix.as_str()))
+                {
+                    for posting in postings {
+                        *matches
+                            .entry((posting.document, posting.field))
+                            .or_default() += posting.positions.len();
+                    }
+                }
+            }
+            Clause::Phrase(tokens) => matches = self.phrase(tokens),
+        }
+
+        let documents: HashSet<usize> = matches.keys().map(|(d, _)| *d).collect();
+        let idf = (1.0 + self.documents.len() as f64 / documents.len().max(1) as f64).ln();
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for ((document, field), count) in matches {
+            *scores.entry(document).or_default() +=
+                field.weight() * (1.0 + (count as f64).ln()) * idf;
+        }
+        scores
+    }
+
+    fn phrase(&self, tokens: &[String]) -> HashMap<(usize, Field), usize> {
+        let mut matches = HashMap::new();
+
+        let mut positions: Vec<HashMap<(usize, Field), &Vec<usize>>> = Vec::new();
+        for token in tokens {
+            let Some(postings) = self.postings.get(token) else {
+                return matches;
+            };
+            positions.push(
+                postings
+                    .iter()
+                    .map(|p| ((p.document, p.field), &p.positions))
+                    .collect(),
+            );
+        }
+
+        for (key, starts) in positions[0].iter() {
+            let count = starts
+                .iter()
+                .filter(|start| {
+                    positions[1..].iter().enumerate().all(|(i, next)| {
+                        next.get(key)
+                            .is_some_and(|p| p.binary_search(&(**start + i + 1)).is_ok())
+                    })
+                })
+                .count();
+            if count > 0 {
+                matches.insert(*key, count);
+            }
+        }
+
+        matches
+    }
+}
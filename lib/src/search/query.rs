@@ -0,0 +1,52 @@
+use crate::search::tokenize::tokenize;
+
+/// One part of a [`Query`], every clause must match for a document to be a hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clause {
+    Term(String),
+    /// Any token starting with the given text, written `hippo*`.
+    Prefix(String),
+    /// Consecutive tokens within the same field, written `"visual cortex"`.
+    Phrase(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+impl Query {
+    /// Parses what is typed in the search box.
+    ///
+    /// Words are terms, a trailing `*` makes a prefix and double quotes make a
+    /// phrase. A word the tokenizer splits (`hc-3`, `ec013.res`) is a phrase too.
+    /// An unterminated quote runs to the end of the text.
+    pub fn parse(text: &str) -> Self {
+        let mut clauses = Vec::new();
+
+        for (i, part) in text.split('"').enumerate() {
+            if i % 2 == 1 {
+                clauses.extend(clause(tokenize(part), false));
+                continue;
+            }
+            for word in part.split_whitespace() {
+                clauses.extend(clause(tokenize(word), word.ends_with('*')));
+            }
+        }
+
+        Query { clauses }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+}
+
+fn clause(mut tokens: Vec<String>, prefix: bool) -> Option<Clause> {
+    match tokens.len() {
+        0 => None,
+        1 if prefix => Some(Clause::Prefix(tokens.remove(0))),
+        1 => Some(Clause::Term(tokens.remove(0))),
+        _ => Some(Clause::Phrase(tokens)),
+    }
+}
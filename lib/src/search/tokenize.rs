@@ -0,0 +1,10 @@
+/// Lowercase alphanumeric runs of `text`, everything else separates tokens.
+///
+/// `hc-3` gives `["hc", "3"]`, which the query side matches as a phrase so
+/// aliases are still found whole.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
@@ -0,0 +1,5 @@
+pub mod config_directory;
+pub mod workspace;
+
+pub use config_directory::config_directory;
+pub use workspace::{Settings, Workspace};
@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+/// Name of the application's directories.
+pub const APPLICATION: &str = "crcns-lens";
+
+/// `$XDG_CONFIG_HOME/crcns-lens`, `~/.config/crcns-lens` when unset.
+pub fn config_directory() -> PathBuf {
+    let config_directory = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .unwrap_or_else(|| std::env::current_dir().unwrap());
+    config_directory.join(APPLICATION)
+}
@@ -0,0 +1,143 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::files::write_atomic;
+use crate::settings::config_directory;
+use crate::storage::Mirrors;
+
+/// A data root with its own catalog, downloads and queue under `<root>/data`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    pub root: PathBuf,
+    /// Read-only data directories shared with others, laid out like `data/` and
+    /// consulted before downloading.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<PathBuf>,
+}
+
+impl Workspace {
+    pub fn new(name: impl ToString, root: impl Into<PathBuf>) -> Self {
+        Workspace {
+            name: name.to_string().trim().to_string(),
+            root: root.into(),
+            mirrors: Vec::new(),
+        }
+    }
+
+    pub fn with_mirrors(mut self, mirrors: Vec<PathBuf>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    pub fn data_directory(&self) -> PathBuf {
+        self.root.join("data")
+    }
+
+    pub fn mirrors(&self) -> Mirrors {
+        Mirrors::new(self.mirrors.clone())
+    }
+}
+
+/// What is remembered between runs, stored as TOML in `settings.toml` of the
+/// configuration directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Settings {
+    /// Name of the workspace opened last.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    #[serde(default, rename = "workspace")]
+    pub workspaces: Vec<Workspace>,
+}
+
+impl Settings {
+    pub const DEFAULT_WORKSPACE: &'static str = "default";
+
+    pub fn filepath() -> PathBuf {
+        config_directory().join("settings.toml")
+    }
+
+    /// Reads the settings, the defaults when there is no file yet. A file that
+    /// does not parse is an error rather than silently replaced.
+    pub fn load_from(filepath: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(filepath) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(e) => return Err(e),
+        };
+        toml::from_str(content.as_str()).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {e}", filepath.display()),
+            )
+        })
+    }
+
+    pub fn save_to(&self, filepath: &Path) -> Result<()> {
+        let content =
+            toml::to_string_pretty(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        write_atomic(filepath, content.as_bytes())
+    }
+
+    pub fn load() -> Result<Self> {
+        Settings::load_from(&Settings::filepath())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Settings::filepath())
+    }
+
+    pub fn workspace(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+
+    /// Adds `workspace`, replacing the one with the same name.
+    pub fn add(&mut self, workspace: Workspace) {
+        match self
+            .workspaces
+            .iter_mut()
+            .find(|w| w.name == workspace.name)
+        {
+            Some(existing) => *existing = workspace,
+            None => self.workspaces.push(workspace),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.workspaces.retain(|w| w.name != name);
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+    }
+
+    /// The workspace to open: `requested` by name, otherwise the one opened
+    /// last, otherwise the first one.
+    ///
+    /// Without any workspace, `fallback_root` becomes the `default` workspace,
+    /// which is where data went before workspaces existed. The choice becomes
+    /// the current workspace.
+    pub fn resolve(&mut self, requested: Option<&str>, fallback_root: &Path) -> Result<Workspace> {
+        if self.workspaces.is_empty() {
+            self.add(Workspace::new(Settings::DEFAULT_WORKSPACE, fallback_root));
+        }
+
+        let workspace = match requested {
+            Some(name) => self.workspace(name).cloned().ok_or_else(|| {
+                let known: Vec<&str> = self.workspaces.iter().map(|w| w.name.as_str()).collect();
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("no workspace named {name}, known: {}", known.join(", ")),
+                )
+            })?,
+            None => self
+                .current
+                .as_deref()
+                .and_then(|name| self.workspace(name))
+                .unwrap_or(&self.workspaces[0])
+                .clone(),
+        };
+
+        self.current = Some(workspace.name.clone());
+        Ok(workspace)
+    }
+}
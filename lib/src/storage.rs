@@ -0,0 +1,9 @@
+pub mod evict;
+pub mod mirror;
+pub mod policy;
+pub mod usage;
+
+pub use evict::{evict_to_quota, eviction_plan, free_dataset, is_evictable, is_metadata};
+pub use mirror::{promote, resolve, Mirrors, VerifiedCopies};
+pub use policy::StoragePolicy;
+pub use usage::{relative_path, DatasetUsage, DiskUsage, LocalFile};
@@ -0,0 +1,102 @@
+use std::io::{ErrorKind, Result};
+
+use crate::catalog::snapshot::METADATA_FILES;
+use crate::files::EXTRACTED_FILE;
+use crate::storage::policy::StoragePolicy;
+use crate::storage::usage::{DatasetUsage, DiskUsage, LocalFile};
+
+/// Listings, the record of extracted files and recording parameters, small and
+/// needed to make sense of the rest.
+pub fn is_metadata(relative_path: &str) -> bool {
+    let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    METADATA_FILES.contains(&name) || name == EXTRACTED_FILE || name.ends_with(".xml")
+}
+
+/// Whether `file` may be removed to make room: raw data, not pinned and not a
+/// download in progress.
+pub fn is_evictable(file: &LocalFile, policy: &StoragePolicy) -> bool {
+    !is_metadata(&file.relative_path)
+        && !file.relative_path.ends_with(".part")
+        && !policy.is_pinned(&file.relative_path)
+}
+
+/// The least recently used evictable files to remove to get under the quota,
+/// never those in `keep`. Empty without a quota or when under it.
+pub fn eviction_plan<'a>(
+    usage: &'a DiskUsage,
+    policy: &StoragePolicy,
+    keep: &[String],
+) -> Vec<&'a LocalFile> {
+    let Some(quota) = policy.quota else {
+        return Vec::new();
+    };
+    let mut excess = usage.local_bytes().saturating_sub(quota);
+    if excess == 0 {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<&LocalFile> = usage
+        .datasets
+        .iter()
+        .flat_map(|d| d.files.iter())
+        .filter(|f| is_evictable(f, policy) && !keep.contains(&f.relative_path))
+        .collect();
+    candidates.sort_by(|a, b| {
+        policy
+            .last_used(a)
+            .cmp(&policy.last_used(b))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+
+    let mut plan = Vec::new();
+    for file in candidates {
+        if excess == 0 {
+            break;
+        }
+        excess = excess.saturating_sub(file.size);
+        plan.push(file);
+    }
+    plan
+}
+
+/// Removes the files of [`eviction_plan`], returning them.
+pub fn evict_to_quota(
+    usage: &DiskUsage,
+    policy: &mut StoragePolicy,
+    keep: &[String],
+) -> Result<Vec<LocalFile>> {
+    let plan: Vec<LocalFile> = eviction_plan(usage, policy, keep)
+        .into_iter()
+        .cloned()
+        .collect();
+    for file in plan.iter() {
+        remove(file, policy)?;
+    }
+    Ok(plan)
+}
+
+/// Removes the raw files of a dataset, keeping its listings, parameters and
+/// pinned files. Returns the bytes freed.
+pub fn free_dataset(usage: &DatasetUsage, policy: &mut StoragePolicy) -> Result<u64> {
+    let evictable: Vec<&LocalFile> = usage
+        .files
+        .iter()
+        .filter(|f| is_evictable(f, policy))
+        .collect();
+    let mut freed = 0;
+    for file in evictable {
+        remove(file, policy)?;
+        freed += file.size;
+    }
+    Ok(freed)
+}
+
+fn remove(file: &LocalFile, policy: &mut StoragePolicy) -> Result<()> {
+    match std::fs::remove_file(&file.path) {
+        Ok(()) => (),
+        Err(e) if e.kind() == ErrorKind::NotFound => (),
+        Err(e) => return Err(e),
+    }
+    policy.forget(&file.relative_path);
+    Ok(())
+}
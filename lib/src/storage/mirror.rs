@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::files::{part_path, verify::hashed_status, HashCache};
+use crate::net::md5_file;
+use crate::storage::{relative_path, usage::modified, LocalFile};
+use crate::types::{File, FileStatus};
+
+/// Read-only data directories shared by a team, each laid out like a workspace's
+/// `data/`: `<root>/<collection>/<dataset>/…`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mirrors {
+    pub roots: Vec<PathBuf>,
+}
+
+impl Mirrors {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Mirrors { roots }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// First copy of `relative_path`, in the order of the roots, matching the listed
+    /// size and md5 of `file`. Hashes are cached under the absolute path of the copy.
+    pub async fn find_intact(
+        &self,
+        relative_path: &str,
+        file: &File,
+        cache: &mut HashCache,
+    ) -> crate::net::Result<Option<PathBuf>> {
+        for root in &self.roots {
+            let path = root.join(relative_path);
+            let metadata = match tokio::fs::metadata(&path).await {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+            if file.remote_size > 0 && metadata.len() != file.remote_size {
+                continue;
+            }
+            let copy = LocalFile {
+                relative_path: path.to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified: modified(&metadata),
+                path: path.clone(),
+            };
+            let local_md5 = match cache.get(&copy) {
+                Some(md5) => md5.to_string(),
+                None => {
+                    let md5 = md5_file(&path).await?;
+                    cache.insert(&copy, md5.clone());
+                    md5
+                }
+            };
+            let checked = File {
+                local_md5,
+                ..file.clone()
+            };
+            if hashed_status(&checked) == FileStatus::Ok {
+                return Ok(Some(path));
+            }
+            log::warn!("Ignoring {}, it does not match the listing", path.display());
+        }
+        Ok(None)
+    }
+}
+
+/// Mirror copies that [`Mirrors::find_intact`] picked during the last
+/// reconciliation of each dataset, keyed by their path relative to the data
+/// directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifiedCopies {
+    copies: HashMap<String, PathBuf>,
+}
+
+impl VerifiedCopies {
+    /// Replaces the copies of `collection/dataset` by those its reconciled `files`
+    /// point at.
+    pub fn update(
+        &mut self,
+        data_directory: &Path,
+        collection: &str,
+        dataset: &str,
+        files: &[File],
+    ) {
+        let prefix = format!("{collection}/{dataset}/");
+        self.copies.retain(|path, _| !path.starts_with(&prefix));
+        for file in files {
+            let Some(mirror) = &file.mirror else {
+                continue;
+            };
+            if let Some(relative) =
+                relative_path(data_directory, Path::new(file.local_path.as_str()))
+            {
+                self.copies.insert(relative, PathBuf::from(mirror));
+            }
+        }
+    }
+
+    pub fn get(&self, relative_path: &str) -> Option<&Path> {
+        self.copies.get(relative_path).map(PathBuf::as_path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.copies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.copies.is_empty()
+    }
+}
+
+/// `relative_path` in the data directory when it is there, otherwise its mirror
+/// copy in `verified`. Readers call this on every open, so nothing is hashed here:
+/// a copy is only used once a reconciliation found it intact.
+pub fn resolve(
+    data_directory: &Path,
+    verified: &VerifiedCopies,
+    relative_path: &str,
+) -> Option<PathBuf> {
+    let local = data_directory.join(relative_path);
+    if local.is_file() {
+        return Some(local);
+    }
+    verified
+        .get(relative_path)
+        .filter(|copy| copy.is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Copies the local file `local` into the mirror `root` as `relative_path`, through
+/// a `.part` so readers of the mirror never see half a file. An existing copy is
+/// only left alone when its md5 is the local file's, any other is replaced.
+pub async fn promote(
+    local: &Path,
+    root: &Path,
+    relative_path: &str,
+) -> crate::net::Result<PathBuf> {
+    let target = root.join(relative_path);
+    let size = tokio::fs::metadata(local).await?.len();
+    if let Ok(existing) = tokio::fs::metadata(&target).await {
+        if existing.len() == size && md5_file(&target).await? == md5_file(local).await? {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} is already mirrored", target.display()),
+            )
+            .into());
+        }
+        log::info!(
+            "Replacing {}, it differs from the local copy",
+            target.display()
+        );
+    }
+
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let part = part_path(&target);
+    tokio::fs::copy(local, &part).await?;
+    tokio::fs::File::open(&part).await?.sync_all().await?;
+    tokio::fs::rename(&part, &target).await?;
+    Ok(target)
+}
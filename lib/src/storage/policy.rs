@@ -0,0 +1,99 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use bincode::{Decode, Encode};
+
+use crate::files::write_atomic;
+use crate::storage::usage::LocalFile;
+
+/// How much the data directory may hold and what must stay, persisted in
+/// `data/storage.bin`. Paths are relative to the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct StoragePolicy {
+    /// Bytes the datasets may take before the least recently used files are evicted.
+    pub quota: Option<u64>,
+    /// Files, or directories with everything below them, never evicted.
+    pub pinned: BTreeSet<String>,
+    /// Unix timestamp of the last download or read of each file.
+    pub last_used: BTreeMap<String, i64>,
+}
+
+impl StoragePolicy {
+    pub fn load(filepath: &Path) -> Result<Self> {
+        match std::fs::read(filepath) {
+            Ok(content) => bincode::decode_from_slice(&content, bincode::config::standard())
+                .map(|(policy, _)| policy)
+                .map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: {e}", filepath.display()),
+                    )
+                }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(StoragePolicy::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// [`StoragePolicy::load`], or an empty policy when the file cannot be read. The
+    /// file is then renamed to `<file>.bak`, so that saving the empty policy does
+    /// not overwrite the pins it holds, and the message says so.
+    pub fn load_or_set_aside(filepath: &Path) -> (Self, Option<String>) {
+        let e = match StoragePolicy::load(filepath) {
+            Ok(policy) => return (policy, None),
+            Err(e) => e,
+        };
+        let mut backup = filepath.as_os_str().to_owned();
+        backup.push(".bak");
+        let message = match std::fs::rename(filepath, &backup) {
+            Ok(()) => format!(
+                "Starting with an empty storage policy, {e}. The previous one is kept as {}",
+                Path::new(&backup).display()
+            ),
+            Err(rename) => format!(
+                "Starting with an empty storage policy, {e}. Unable to keep the previous one: {rename}"
+            ),
+        };
+        (StoragePolicy::default(), Some(message))
+    }
+
+    pub fn save(&self, filepath: &Path) -> Result<()> {
+        let encoded = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        write_atomic(filepath, &encoded)
+    }
+
+    pub fn touch(&mut self, relative_path: &str, now: i64) {
+        self.last_used.insert(relative_path.to_string(), now);
+    }
+
+    pub fn forget(&mut self, relative_path: &str) {
+        self.last_used.remove(relative_path);
+    }
+
+    pub fn set_pinned(&mut self, relative_path: &str, pinned: bool) {
+        if pinned {
+            self.pinned.insert(relative_path.to_string());
+        } else {
+            self.pinned.remove(relative_path);
+        }
+    }
+
+    /// Whether `relative_path` or a directory above it is pinned.
+    pub fn is_pinned(&self, relative_path: &str) -> bool {
+        self.pinned.iter().any(|pin| {
+            relative_path == pin
+                || relative_path
+                    .strip_prefix(pin.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// When `file` was last used, its modification time if never recorded.
+    pub fn last_used(&self, file: &LocalFile) -> i64 {
+        self.last_used
+            .get(&file.relative_path)
+            .copied()
+            .unwrap_or(file.modified)
+    }
+}
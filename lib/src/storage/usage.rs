@@ -0,0 +1,176 @@
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::files::parse_filelist;
+
+/// A file under a dataset directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalFile {
+    /// Path relative to the data directory, `/` separated, e.g.
+    /// `hc/hc-3/ec013.527/ec013.527.res.1`.
+    pub relative_path: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Unix timestamp of the last modification.
+    pub modified: i64,
+}
+
+/// What one dataset takes on disk against what the portal lists for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetUsage {
+    pub collection: String,
+    pub dataset: String,
+    pub local_bytes: u64,
+    /// Sum of the sizes in the dataset's `filelist.txt`, `None` until it is fetched.
+    pub remote_bytes: Option<u64>,
+    pub files: Vec<LocalFile>,
+}
+
+impl DatasetUsage {
+    /// Walks `data_directory/collection/dataset`, empty when it does not exist.
+    pub fn scan(data_directory: &Path, collection: &str, dataset: &str) -> Result<Self> {
+        let directory = data_directory.join(collection).join(dataset);
+        let mut usage = DatasetUsage {
+            collection: collection.to_string(),
+            dataset: dataset.to_string(),
+            remote_bytes: remote_bytes(&directory.join("filelist.txt"))?,
+            ..DatasetUsage::default()
+        };
+        if directory.is_dir() {
+            walk(data_directory, &directory, &mut usage.files)?;
+        }
+        usage
+            .files
+            .sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        usage.local_bytes = usage.files.iter().map(|f| f.size).sum();
+        Ok(usage)
+    }
+}
+
+/// Bytes on disk for every dataset of a data directory, `data/<collection>/<dataset>/…`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub datasets: Vec<DatasetUsage>,
+}
+
+impl DiskUsage {
+    /// Walks `data_directory`. Files directly in it or in a collection directory
+    /// (the catalog, the download queue) are not part of any dataset.
+    pub fn scan(data_directory: &Path) -> Result<Self> {
+        let mut datasets = Vec::new();
+
+        for collection in directories(data_directory)? {
+            for dataset in directories(&collection)? {
+                datasets.push(DatasetUsage::scan(
+                    data_directory,
+                    &file_name(&collection),
+                    &file_name(&dataset),
+                )?);
+            }
+        }
+
+        Ok(DiskUsage { datasets })
+    }
+
+    pub fn local_bytes(&self) -> u64 {
+        self.datasets.iter().map(|d| d.local_bytes).sum()
+    }
+
+    pub fn dataset(&self, collection: &str, dataset: &str) -> Option<&DatasetUsage> {
+        self.datasets
+            .iter()
+            .find(|d| d.collection == collection && d.dataset == dataset)
+    }
+
+    /// Local and listed bytes of a collection, the latter only counting datasets
+    /// whose filelist was fetched.
+    pub fn collection(&self, collection: &str) -> (u64, u64) {
+        self.datasets
+            .iter()
+            .filter(|d| d.collection == collection)
+            .fold((0, 0), |(local, remote), d| {
+                (local + d.local_bytes, remote + d.remote_bytes.unwrap_or(0))
+            })
+    }
+
+    pub fn file(&self, relative_path: &str) -> Option<&LocalFile> {
+        self.datasets
+            .iter()
+            .flat_map(|d| d.files.iter())
+            .find(|f| f.relative_path == relative_path)
+    }
+}
+
+/// `path` relative to `data_directory` in the form used as key by the usage and
+/// the storage policy, `None` when it is not inside.
+pub fn relative_path(data_directory: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(data_directory).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn directories(path: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut directories: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    directories.sort();
+    Ok(directories)
+}
+
+fn walk(data_directory: &Path, directory: &Path, files: &mut Vec<LocalFile>) -> Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let path = entry.path();
+        if metadata.is_dir() {
+            walk(data_directory, &path, files)?;
+        } else if metadata.is_file() {
+            files.push(LocalFile {
+                relative_path: relative_path(data_directory, &path).unwrap_or_default(),
+                path,
+                size: metadata.len(),
+                modified: modified(&metadata),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Unix timestamp of the last modification, 0 when unknown.
+pub(crate) fn modified(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Total of the size column of a `filelist.txt`.
+/// `None` until the listing is fetched, or when it cannot be parsed.
+fn remote_bytes(filelist: &Path) -> Result<Option<u64>> {
+    let content = match std::fs::read_to_string(filelist) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(parse_filelist(&content, filelist.display())
+        .ok()
+        .map(|filelist| filelist.sizes.values().sum()))
+}
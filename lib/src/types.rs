@@ -1,11 +1,19 @@
 pub mod collection;
 pub mod crcns;
 pub mod dataset;
+pub mod dataset_metadata;
+pub mod endpoints;
 pub mod file;
+pub mod report;
 pub mod state;
+pub mod sync_report;
 
 pub use collection::Collection;
 pub use crcns::CRCNS;
 pub use dataset::Dataset;
-pub use file::File;
+pub use dataset_metadata::DatasetMetadata;
+pub use endpoints::Endpoints;
+pub use file::{File, FileStatus};
+pub use report::Report;
 pub use state::State;
+pub use sync_report::SyncReport;
@@ -1,161 +1,227 @@
+use crate::catalog::{self, Manifest};
+use crate::global;
 use crate::net::get_url_html::get_url_html;
+use crate::net::xpath;
+use crate::net::Result;
 use crate::types::collection::Collection;
 use crate::types::dataset::Dataset;
+use crate::types::endpoints::Endpoints;
+use crate::types::sync_report::SyncReport;
 
 use std::path::PathBuf;
-use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use url as urllib;
 
 pub struct CRCNS;
 
 impl CRCNS {
-    pub async fn get(collections: Arc<Mutex<Vec<Collection>>>) {
-        let crcns_sitemap_url = urllib::Url::from_str("https://crcns.org/sitemap").unwrap();
-        let crcns_sitemap_html = get_url_html(crcns_sitemap_url).await;
-        let crcns_sitemap_package = sxd_html::parse_html(crcns_sitemap_html.as_str());
-        let crcns_sitemap_document = crcns_sitemap_package.as_document();
-
-        let links = sxd_xpath::evaluate_xpath(&crcns_sitemap_document, "//a/@href").expect("Panic");
-
-        let mut urls: Vec<urllib::Url> = match links {
-            sxd_xpath::Value::Nodeset(d) => d
-                .into_iter()
-                .map(|n| n.string_value())
-                .filter(|s| s.contains("/data-sets/"))
-                .map(|u| urllib::Url::parse(u.as_str()))
-                .filter_map(|r| r.ok())
-                .collect(),
-            _ => panic!("Cannot find anchor tags in CRCNS sitemap."),
+    /// Syncs `collections` with the catalog.
+    ///
+    /// Pages already in `collections` are requested conditionally and only parsed
+    /// again when the server says they changed. Collections and datasets gone from
+    /// the sitemap are dropped.
+    ///
+    /// Only a failure to read the sitemap is returned, pages that fail to load or
+    /// parse are reported to the GUI and kept as they were so the rest of the
+    /// catalog still loads.
+    pub async fn get(
+        endpoints: Endpoints,
+        collections: Arc<Mutex<Vec<Collection>>>,
+    ) -> Result<SyncReport> {
+        let crcns_sitemap_url = endpoints.sitemap_url();
+        let crcns_sitemap_html = get_url_html(crcns_sitemap_url.clone(), &endpoints.retry).await?;
+
+        // The parsed document is not `Send`, so it must be dropped before awaiting.
+        let links: Vec<String> = {
+            let crcns_sitemap_package = sxd_html::parse_html(crcns_sitemap_html.as_str());
+            let crcns_sitemap_document = crcns_sitemap_package.as_document();
+
+            xpath::strings(
+                &crcns_sitemap_document,
+                "//a/@href",
+                crcns_sitemap_url.as_str(),
+            )?
         };
 
+        // Links may be absolute or relative to the sitemap, only those under the
+        // catalog's `data-sets/` tree are kept.
+        let mut urls: Vec<(urllib::Url, Vec<String>)> = links
+            .into_iter()
+            .filter_map(|u| crcns_sitemap_url.join(u.as_str()).ok())
+            .filter_map(|u| endpoints.catalog_segments(&u).map(|s| (u, s)))
+            .filter(|(_, s)| s.first().map(|s| s.as_str()) == Some("data-sets"))
+            .collect();
+
         urls.sort();
-        let nof_segments = |a: &urllib::Url| a.path_segments().map(|c| c.collect::<Vec<_>>().len());
+        urls.dedup();
 
-        let collections_url: Vec<urllib::Url> = urls
+        let collections_url: Vec<(urllib::Url, Vec<String>)> = urls
             .clone()
             .into_iter()
-            .filter(|u| nof_segments(u).unwrap() == 2)
+            .filter(|(_, s)| s.len() == 2)
             .collect();
 
-        for (count_c, url) in collections_url.into_iter().enumerate() {
-            println!("{}", url.as_str());
+        let report = Arc::new(Mutex::new(SyncReport::default()));
+        let previous_collections = collections.lock().unwrap().clone();
+
+        // Collections that left the sitemap take their datasets with them.
+        let listed: Vec<&String> = collections_url.iter().map(|(_, s)| &s[1]).collect();
+        for collection in previous_collections.iter() {
+            if !listed.contains(&&collection.alias) {
+                let mut report = report.lock().unwrap();
+                for dataset in collection.datasets.lock().unwrap().iter() {
+                    report
+                        .removed
+                        .push(format!("{}/{}", collection.alias, dataset.alias));
+                }
+            }
+        }
+        collections
+            .lock()
+            .unwrap()
+            .retain(|c| listed.contains(&&c.alias));
+
+        let mut handles = Vec::new();
+
+        for (url, segments) in collections_url.into_iter() {
             let c = collections.clone();
+            let e = endpoints.clone();
+            let r = report.clone();
+            let previous = previous_collections
+                .iter()
+                .find(|c| c.alias == segments[1])
+                .cloned();
             let collection_urls: Vec<urllib::Url> = urls
                 .clone()
                 .into_iter()
-                .filter(|u| {
-                    u.as_str().contains(url.clone().as_str()) && nof_segments(u).unwrap() == 3
-                })
+                .filter(|(_, s)| s.len() == 3 && s[1] == segments[1])
+                .map(|(u, _)| u)
                 .collect();
 
-            tokio::spawn(async move {
-                let collection = Collection::from_url(url).await;
-                for (count_d, ds_url) in collection_urls.into_iter().enumerate() {
-                    let d = collection.datasets.clone();
-                    tokio::spawn(async move {
-                        let dataset = Dataset::from_url(ds_url).await;
-                        d.lock().unwrap().push(dataset);
-                    });
-                    if count_d > 1 {
-                        // break;
+            handles.push(tokio::spawn(async move {
+                let collection = match Collection::refresh(&e, url, previous.as_ref()).await {
+                    Ok(Some(collection)) => collection,
+                    Ok(None) => previous.clone().unwrap(),
+                    Err(err) => {
+                        global::report_error(err);
+                        match previous {
+                            Some(previous) => previous,
+                            None => return,
+                        }
+                    }
+                };
+
+                {
+                    let mut c = c.lock().unwrap();
+                    match c.iter_mut().find(|c| c.alias == collection.alias) {
+                        Some(existing) => *existing = collection.clone(),
+                        None => c.push(collection.clone()),
                     }
                 }
-                c.lock().unwrap().push(collection);
-            });
-            if count_c > 1 {
-                // break;
-            }
+
+                sync_datasets(&e, &collection, collection_urls, &r).await;
+            }));
+        }
+
+        for handle in handles {
+            let _ = handle.await;
         }
-        println!("utcny4792d87my3u984ymu2,09m8u59g82yumj9f8tm39 yrmd9uh");
+
+        let mut report = report.lock().unwrap().clone();
+        report.sort();
+        Ok(report)
     }
 
+    /// Writes `collections` to the manifest in `data_directory`, replacing what
+    /// was persisted before.
     pub async fn persist(
-        collections_arc: Arc<Mutex<Vec<Collection>>>,
-        data_directory_path: PathBuf,
-    ) {
-        'cloop: loop {
-            let collections_lock_result = collections_arc.lock();
-            match collections_lock_result {
-                Ok(collections_mutex) => {
-                    for collection in collections_mutex.clone().iter_mut() {
-                        let collection_directory_path = data_directory_path.clone();
-                        let collection_directory_path =
-                            collection_directory_path.join(collection.alias.clone());
-
-                        if !collection_directory_path.exists() {
-                            let _ = std::fs::create_dir(collection_directory_path.clone());
-                        }
+        collections: Arc<Mutex<Vec<Collection>>>,
+        data_directory: PathBuf,
+    ) -> catalog::Result<()> {
+        let manifest = Manifest::from_collections(&collections.lock().unwrap());
+        tokio::task::spawn_blocking(move || manifest.save(&data_directory))
+            .await
+            .expect("catalog writer panicked")
+    }
 
-                        let collection_file_path = collection_directory_path.join("c.bin");
-
-                        if !collection_file_path.exists() {
-                            collection.persist(collection_directory_path);
-                        } else {
-                            let persisted_collection =
-                                Collection::from_filepath(collection_directory_path.clone());
-                            if persisted_collection.last_modified < collection.last_modified {
-                                collection.persist(collection_directory_path);
-                            } else {
-                                *collection = persisted_collection;
-                            }
-                        }
-                        'dloop: loop {
-                            let datasets_lock_result = collection.datasets.lock();
-                            match datasets_lock_result {
-                                Ok(mut datasets_mutex) => {
-                                    for dataset in datasets_mutex.iter_mut() {
-                                        let mut dataset_directory_path =
-                                            data_directory_path.clone();
-                                        for f in urllib::Url::parse(dataset.url.as_str())
-                                            .unwrap()
-                                            .clone()
-                                            .path_segments()
-                                            .unwrap()
-                                            .skip(1)
-                                        {
-                                            dataset_directory_path.push(f);
-                                        }
-
-                                        if !dataset_directory_path.exists() {
-                                            let _ =
-                                                std::fs::create_dir(dataset_directory_path.clone());
-                                        }
-
-                                        let dataset_file_path =
-                                            dataset_directory_path.join("ds.bin");
-
-                                        if !dataset_file_path.exists() {
-                                            dataset.persist(dataset_directory_path)
-                                        } else {
-                                            let persisted_dataset = Dataset::from_filepath(
-                                                dataset_directory_path.clone(),
-                                            );
-                                            if persisted_dataset.last_modified
-                                                < dataset.last_modified
-                                            {
-                                                dataset.persist(dataset_directory_path)
-                                            } else {
-                                                *dataset = persisted_dataset;
-                                            }
-                                        }
-                                    }
-                                    break 'dloop;
-                                }
-                                Err(e) => {
-                                    println!("Could not persist CRCNS data: {}", e);
-                                    continue 'dloop;
-                                }
-                            };
-                        }
-                    }
-                    break 'cloop;
+    /// Reads the collections persisted in `data_directory`, migrating files
+    /// written by older versions. Empty when nothing was persisted.
+    pub async fn load(data_directory: PathBuf) -> catalog::Result<Vec<Collection>> {
+        let manifest = tokio::task::spawn_blocking(move || Manifest::load(&data_directory))
+            .await
+            .expect("catalog reader panicked")?;
+        Ok(manifest.map(Manifest::into_collections).unwrap_or_default())
+    }
+}
+
+/// Refreshes the datasets of `collection` from `urls`, the ones listed in its sitemap.
+async fn sync_datasets(
+    endpoints: &Endpoints,
+    collection: &Collection,
+    urls: Vec<urllib::Url>,
+    report: &Mutex<SyncReport>,
+) {
+    let previous_datasets = collection.datasets.lock().unwrap().clone();
+
+    let mut handles = Vec::new();
+    for url in urls.into_iter() {
+        let e = endpoints.clone();
+        let alias = e
+            .catalog_segments(&url)
+            .and_then(|s| s.last().cloned())
+            .unwrap_or_default();
+        let previous = previous_datasets.iter().find(|d| d.alias == alias).cloned();
+        handles.push(tokio::spawn(async move {
+            let refreshed = Dataset::refresh(&e, url, previous.as_ref()).await;
+            (previous, refreshed)
+        }));
+    }
+
+    let mut datasets = Vec::new();
+    for handle in handles {
+        let Ok((previous, refreshed)) = handle.await else {
+            continue;
+        };
+        let path = |d: &Dataset| format!("{}/{}", collection.alias, d.alias);
+
+        let dataset = match (previous, refreshed) {
+            (Some(previous), Ok(None)) => {
+                report.lock().unwrap().unchanged += 1;
+                previous
+            }
+            (Some(previous), Ok(Some(dataset))) => {
+                let mut report = report.lock().unwrap();
+                match dataset.is_update_of(&previous) {
+                    true => report.updated.push(path(&dataset)),
+                    false => report.unchanged += 1,
                 }
-                Err(e) => {
-                    println!("Could not persist CRCNS data: {}", e);
-                    continue 'cloop;
+                dataset
+            }
+            (None, Ok(Some(dataset))) => {
+                report.lock().unwrap().added.push(path(&dataset));
+                dataset
+            }
+            (previous, Err(e)) => {
+                global::report_error(e);
+                match previous {
+                    Some(previous) => previous,
+                    None => continue,
                 }
-            };
+            }
+            // Without validators the page is always sent.
+            (None, Ok(None)) => continue,
+        };
+        datasets.push(dataset);
+    }
+
+    let mut report = report.lock().unwrap();
+    for previous in previous_datasets.iter() {
+        if !datasets.iter().any(|d| d.alias == previous.alias) {
+            report
+                .removed
+                .push(format!("{}/{}", collection.alias, previous.alias));
         }
     }
+
+    *collection.datasets.lock().unwrap() = datasets;
 }
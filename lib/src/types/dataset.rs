@@ -1,24 +1,21 @@
-use std::borrow::BorrowMut;
-use std::sync::{Arc, Mutex};
-
-use crate::files::get_file;
-use crate::global;
-use crate::net::get_crcns_file;
-use crate::net::get_url_html::get_url_html;
-use crate::net::write_response::write_response;
-use crate::types::state::SrPair;
-// use crate::net::write_response::write_response_with_sender;
-use egui::TextBuffer;
+use crate::net::get_url_html::{get_url_html_if_modified, Page, Validators};
+use crate::net::Result;
+use crate::net::{document_modified, xpath};
+use crate::types::dataset_metadata::DatasetMetadata;
+use crate::types::endpoints::Endpoints;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone)]
 pub struct Dataset {
     pub url: String,
     pub html: String,
     pub alias: String,
     pub content: String,
     pub description: String,
-    pub last_modified: String,
+    /// Unix timestamp of the page's `documentModified`, `0` when unknown.
+    pub last_modified: i64,
+    pub validators: Validators,
+    pub metadata: DatasetMetadata,
 }
 
 impl PartialEq for Dataset {
@@ -36,124 +33,77 @@ impl Default for Dataset {
             alias: String::from_str("Default").unwrap(),
             content: String::from_str("").unwrap(),
             description: String::from_str("Default Dataset").unwrap(),
-            last_modified: chrono::Utc::now().to_string(),
+            last_modified: 0,
+            validators: Validators::default(),
+            metadata: DatasetMetadata::default(),
         }
     }
 }
 
 impl Dataset {
-    pub async fn from_url(url: url::Url) -> Dataset {
-        println!("{url}");
-        let html = get_url_html(url.clone()).await;
-        let package = sxd_html::parse_html(html.as_str());
-        let document = package.as_document();
-
-        let alias = url.path_segments().unwrap().last().unwrap().to_string();
-
-        let description =
-            sxd_xpath::evaluate_xpath(&document, "//div[@class='documentDescription']//text()")
-                .expect("Unable to find h1 descriptor")
-                .string();
+    pub async fn from_url(endpoints: &Endpoints, url: url::Url) -> Result<Dataset> {
+        match Dataset::refresh(endpoints, url, None).await? {
+            Some(dataset) => Ok(dataset),
+            None => unreachable!("unconditional requests are always answered"),
+        }
+    }
 
-        // let content = String::new();
-        let content = match sxd_xpath::evaluate_xpath(&document, "//div[@id='content']").unwrap() {
-            sxd_xpath::Value::Nodeset(ns) => {
-                let node = ns.document_order_first().unwrap();
-                node.string_value()
-            }
-            _ => panic!("Unable to get content"),
+    /// Fetches the dataset page again unless it did not change since `previous` was
+    /// scraped, `None` when it did not.
+    pub async fn refresh(
+        endpoints: &Endpoints,
+        url: url::Url,
+        previous: Option<&Dataset>,
+    ) -> Result<Option<Dataset>> {
+        let validators = previous.map(|p| p.validators.clone()).unwrap_or_default();
+        let (html, validators) =
+            match get_url_html_if_modified(url.clone(), &endpoints.retry, &validators).await? {
+                Page::Modified { html, validators } => (html, validators),
+                Page::NotModified => return Ok(None),
+            };
+
+        let alias = endpoints
+            .catalog_segments(&url)
+            .and_then(|s| s.last().cloned())
+            .unwrap_or_default();
+
+        let (description, content, last_modified) = {
+            let package = sxd_html::parse_html(html.as_str());
+            let document = package.as_document();
+
+            let description = xpath::optional_string(
+                &document,
+                "//div[@class='documentDescription']//text()",
+                url.as_str(),
+            )?
+            .unwrap_or_default();
+
+            let content = xpath::required_string(&document, "//div[@id='content']", url.as_str())?;
+
+            let last_modified = document_modified(&document, url.as_str())?;
+
+            (description, content, last_modified)
         };
 
-        // let content = content.nodes.iter.next();
+        let metadata = DatasetMetadata::from_html(html.as_str());
 
-        let modified = chrono::Utc::now();
-
-        let url = url.to_string();
-        let modified = modified.to_string();
-
-        Dataset {
-            url,
+        Ok(Some(Dataset {
+            url: url.to_string(),
+            metadata,
             html,
             alias,
             content,
             description,
-            last_modified: modified,
-        }
+            last_modified,
+            validators,
+        }))
     }
 
-    pub fn persist(&self, fp: std::path::PathBuf) {
-        let dataset_file_path = fp.join("ds.bin");
-        let dataset_encode_result = bincode::encode_to_vec(self, bincode::config::standard());
-        match dataset_encode_result {
-            Ok(dataset_encoded) => {
-                let dataset_write_result =
-                    std::fs::write(dataset_file_path.clone(), dataset_encoded);
-                match dataset_write_result {
-                    Ok(()) => {
-                        println!("{} was written.", dataset_file_path.to_str().unwrap())
-                    }
-                    Err(e) => {
-                        println!(
-                            "Unable to write {}: {}.",
-                            dataset_file_path.to_str().unwrap(),
-                            e
-                        )
-                    }
-                }
-            }
-            Err(e) => {
-                println!(
-                    "Unable to persist {}: {}.",
-                    dataset_file_path.to_str().unwrap(),
-                    e
-                );
-            }
+    /// Whether `self` is a newer scrape of `previous` with actual changes.
+    pub fn is_update_of(&self, previous: &Dataset) -> bool {
+        match (self.last_modified, previous.last_modified) {
+            (0, _) | (_, 0) => self.content != previous.content,
+            (modified, previous_modified) => modified > previous_modified,
         }
     }
-
-    pub fn from_filepath(fp: std::path::PathBuf) -> Self {
-        let dataset_fp = fp.join("ds.bin");
-        let dataset_fc_result = std::fs::read(dataset_fp.clone());
-        match dataset_fc_result {
-            Ok(dataset_fc) => {
-                let dataset_decode_result =
-                    bincode::decode_from_slice(&dataset_fc[..], bincode::config::standard());
-                match dataset_decode_result {
-                    Ok((dataset, _len)) => dataset,
-                    Err(e) => {
-                        println!("Unable to decode {}: {}", dataset_fp.to_str().unwrap(), e);
-                        Dataset::default()
-                    }
-                }
-            }
-            Err(e) => {
-                println!("Unable to read {}: {}", dataset_fp.to_str().unwrap(), e);
-                Dataset::default()
-            }
-        }
-    }
-    pub async fn get_crcns_file(
-        &self,
-        collection_alias: &str,
-        filepath: &str,
-        _sr: Arc<Mutex<SrPair>>,
-    ) {
-        let remote_filepath = format!("{}/{}", self.alias, filepath);
-        println!("{remote_filepath}");
-        let remote_response = get_crcns_file(remote_filepath.as_str()).await;
-
-        // Construct the file path
-        let local_filepath = global::get_state()
-            .working_directory
-            .join("data")
-            .join(collection_alias.as_str())
-            .join(remote_filepath);
-
-        let mut local_file = get_file(local_filepath).await.unwrap();
-
-        write_response(local_file.borrow_mut(), remote_response).await;
-        // write_response_with_sender(local_file.borrow_mut(), remote_response, sr).await;
-
-        // Create directories if they don't exist
-    }
 }
@@ -0,0 +1,430 @@
+use bincode::{Decode, Encode};
+use sxd_xpath::nodeset::Node;
+
+/// What a dataset page says about the data, in a form that can be filtered on.
+///
+/// Extracted from the stored page by [`DatasetMetadata::from_html`]: labelled
+/// sections (headings, table headers, definition terms) are read first, the
+/// free text of the page fills in whatever they do not cover.
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode)]
+pub struct DatasetMetadata {
+    pub species: Vec<String>,
+    pub brain_regions: Vec<String>,
+    pub techniques: Vec<String>,
+    pub subjects: Option<u32>,
+    pub sessions: Option<u32>,
+    pub formats: Vec<String>,
+    /// Total size of the files in bytes.
+    pub total_size: Option<u64>,
+    pub publications: Vec<String>,
+    pub funding: Vec<String>,
+    pub contributors: Vec<String>,
+}
+
+/// Spelling on the page, name it is recorded under.
+const SPECIES: [(&str, &str); 20] = [
+    ("rat", "Rat"),
+    ("rats", "Rat"),
+    ("mouse", "Mouse"),
+    ("mice", "Mouse"),
+    ("macaque", "Macaque"),
+    ("macaques", "Macaque"),
+    ("monkey", "Monkey"),
+    ("monkeys", "Monkey"),
+    ("cat", "Cat"),
+    ("cats", "Cat"),
+    ("human", "Human"),
+    ("humans", "Human"),
+    ("ferret", "Ferret"),
+    ("ferrets", "Ferret"),
+    ("zebra finch", "Zebra finch"),
+    ("zebra finches", "Zebra finch"),
+    ("drosophila", "Drosophila"),
+    ("locust", "Locust"),
+    ("salamander", "Salamander"),
+    ("turtle", "Turtle"),
+];
+
+const BRAIN_REGIONS: [(&str, &str); 24] = [
+    ("ca1", "CA1"),
+    ("ca3", "CA3"),
+    ("dentate gyrus", "Dentate gyrus"),
+    ("dg", "Dentate gyrus"),
+    ("entorhinal cortex", "Entorhinal cortex"),
+    ("hippocampus", "Hippocampus"),
+    ("hippocampal", "Hippocampus"),
+    ("primary visual cortex", "V1"),
+    ("v1", "V1"),
+    ("v2", "V2"),
+    ("v4", "V4"),
+    ("mt", "MT"),
+    ("lgn", "LGN"),
+    ("retina", "Retina"),
+    ("barrel cortex", "Barrel cortex"),
+    ("somatosensory cortex", "Somatosensory cortex"),
+    ("motor cortex", "Motor cortex"),
+    ("prefrontal cortex", "Prefrontal cortex"),
+    ("auditory cortex", "Auditory cortex"),
+    ("thalamus", "Thalamus"),
+    ("olfactory bulb", "Olfactory bulb"),
+    ("cerebellum", "Cerebellum"),
+    ("striatum", "Striatum"),
+    ("amygdala", "Amygdala"),
+];
+
+const TECHNIQUES: [(&str, &str); 17] = [
+    ("tetrode", "Tetrodes"),
+    ("tetrodes", "Tetrodes"),
+    ("silicon probe", "Silicon probes"),
+    ("silicon probes", "Silicon probes"),
+    ("utah array", "Utah array"),
+    ("multi-electrode array", "Multi-electrode array"),
+    ("extracellular", "Extracellular recording"),
+    ("intracellular", "Intracellular recording"),
+    ("juxtacellular", "Juxtacellular recording"),
+    ("whole-cell", "Patch clamp"),
+    ("patch clamp", "Patch clamp"),
+    ("calcium imaging", "Calcium imaging"),
+    ("two-photon", "Two-photon imaging"),
+    ("eeg", "EEG"),
+    ("ecog", "ECoG"),
+    ("lfp", "LFP"),
+    ("fmri", "fMRI"),
+];
+
+const FORMATS: [(&str, &str); 17] = [
+    (".res", "RES"),
+    (".clu", "CLU"),
+    (".spk", "SPK"),
+    (".fet", "FET"),
+    (".eeg", "EEG"),
+    (".dat", "DAT"),
+    (".whl", "WHL"),
+    (".xml", "XML"),
+    (".mat", "MAT"),
+    ("matlab", "MAT"),
+    ("hdf5", "HDF5"),
+    ("nwb", "NWB"),
+    (".nev", "NEV"),
+    (".ns5", "NSx"),
+    (".csv", "CSV"),
+    ("ascii", "Text"),
+    (".txt", "Text"),
+];
+
+/// Nouns that count the animals or people recorded from.
+const SUBJECT_NOUNS: [&str; 16] = [
+    "rats",
+    "mice",
+    "animals",
+    "subjects",
+    "monkeys",
+    "macaques",
+    "cats",
+    "ferrets",
+    "patients",
+    "participants",
+    "rat",
+    "mouse",
+    "animal",
+    "subject",
+    "monkey",
+    "cat",
+];
+
+const NUMBER_WORDS: [&str; 13] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve",
+];
+
+/// Elements holding a label for the content that follows them.
+const LABELS: [&str; 6] = ["h2", "h3", "h4", "h5", "th", "dt"];
+/// Elements holding content, only the innermost ones are read.
+const BLOCKS: [&str; 4] = ["p", "li", "td", "dd"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Species,
+    Regions,
+    Techniques,
+    Subjects,
+    Sessions,
+    Formats,
+    Size,
+    Publications,
+    Funding,
+    Contributors,
+}
+
+impl Field {
+    /// The field a section label announces, if any.
+    fn from_label(label: &str) -> Option<Field> {
+        let label = label.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| label.contains(w));
+
+        if has(&["species", "organism", "animal model"]) {
+            Some(Field::Species)
+        } else if has(&["region", "brain area", "areas recorded"]) {
+            Some(Field::Regions)
+        } else if has(&["technique", "method", "preparation"]) {
+            Some(Field::Techniques)
+        } else if has(&["subject", "animals"]) {
+            Some(Field::Subjects)
+        } else if has(&["session"]) {
+            Some(Field::Sessions)
+        } else if has(&["format"]) {
+            Some(Field::Formats)
+        } else if has(&["size"]) {
+            Some(Field::Size)
+        } else if has(&["publication", "reference", "citation", "papers"]) {
+            Some(Field::Publications)
+        } else if has(&["funding", "grant", "acknowledg", "support"]) {
+            Some(Field::Funding)
+        } else if has(&["contributor", "author", "contributed by", "investigator"]) {
+            Some(Field::Contributors)
+        } else {
+            None
+        }
+    }
+}
+
+impl DatasetMetadata {
+    pub fn from_html(html: &str) -> Self {
+        let sections = sections(html);
+        let mut metadata = DatasetMetadata::default();
+
+        for (field, paragraphs) in sections.iter().filter_map(|(l, p)| Some((l.as_ref()?, p))) {
+            let text = paragraphs.join(" ");
+            match field {
+                Field::Species => extend(&mut metadata.species, vocabulary(&text, &SPECIES)),
+                Field::Regions => extend(
+                    &mut metadata.brain_regions,
+                    vocabulary(&text, &BRAIN_REGIONS),
+                ),
+                Field::Techniques => {
+                    extend(&mut metadata.techniques, vocabulary(&text, &TECHNIQUES))
+                }
+                Field::Subjects => metadata.subjects = metadata.subjects.or(first_number(&text)),
+                Field::Sessions => metadata.sessions = metadata.sessions.or(first_number(&text)),
+                Field::Formats => extend(&mut metadata.formats, vocabulary(&text, &FORMATS)),
+                Field::Size => metadata.total_size = metadata.total_size.or(largest_size(&text)),
+                Field::Publications => extend(&mut metadata.publications, entries(paragraphs)),
+                Field::Funding => extend(&mut metadata.funding, entries(paragraphs)),
+                Field::Contributors => extend(&mut metadata.contributors, names(paragraphs)),
+            }
+        }
+
+        // Free text fills in what no section covered.
+        let text: String = sections
+            .iter()
+            .filter(|(field, _)| {
+                !matches!(
+                    field,
+                    Some(Field::Publications | Field::Funding | Field::Contributors)
+                )
+            })
+            .flat_map(|(_, p)| p.iter().map(|p| p.as_str()))
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        if metadata.species.is_empty() {
+            metadata.species = vocabulary(&text, &SPECIES);
+        }
+        if metadata.brain_regions.is_empty() {
+            metadata.brain_regions = vocabulary(&text, &BRAIN_REGIONS);
+        }
+        if metadata.techniques.is_empty() {
+            metadata.techniques = vocabulary(&text, &TECHNIQUES);
+        }
+        if metadata.formats.is_empty() {
+            metadata.formats = vocabulary(&text, &FORMATS);
+        }
+        if metadata.subjects.is_none() {
+            metadata.subjects = counted(&text, &SUBJECT_NOUNS);
+        }
+        if metadata.sessions.is_none() {
+            metadata.sessions = counted(&text, &["sessions", "session"]);
+        }
+        if metadata.total_size.is_none() {
+            metadata.total_size = largest_size(&text);
+        }
+
+        // "Macaque" already says "Monkey".
+        if metadata.species.iter().any(|s| s == "Macaque") {
+            metadata.species.retain(|s| s != "Monkey");
+        }
+
+        metadata
+    }
+}
+
+/// Content blocks of the page grouped under the field of the label before them.
+fn sections(html: &str) -> Vec<(Option<Field>, Vec<String>)> {
+    let package = sxd_html::parse_html(html);
+    let document = package.as_document();
+
+    let any = |tags: &[&str]| {
+        tags.iter()
+            .map(|t| format!("self::{t}"))
+            .collect::<Vec<String>>()
+            .join(" or ")
+    };
+    let nested = BLOCKS
+        .iter()
+        .map(|t| format!(".//{t}"))
+        .collect::<Vec<String>>()
+        .join(" or ");
+    let xpath = format!(
+        "//div[@id='content']//*[{} or @class='documentDescription' or (({}) and not({}))]",
+        any(&LABELS),
+        any(&BLOCKS),
+        nested
+    );
+
+    let mut sections: Vec<(Option<Field>, Vec<String>)> = vec![(None, Vec::new())];
+    let Ok(sxd_xpath::Value::Nodeset(nodes)) = sxd_xpath::evaluate_xpath(&document, &xpath) else {
+        return sections;
+    };
+
+    for node in nodes.document_order() {
+        let Node::Element(element) = node else {
+            continue;
+        };
+        let text = clean(node.string_value().as_str());
+        if text.is_empty() {
+            continue;
+        }
+
+        let tag = element.name().local_part().to_lowercase();
+        if LABELS.contains(&tag.as_str()) {
+            sections.push((Field::from_label(&text), Vec::new()));
+        } else if let Some((_, paragraphs)) = sections.last_mut() {
+            paragraphs.push(text);
+        }
+    }
+
+    sections
+}
+
+fn clean(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn extend(values: &mut Vec<String>, more: Vec<String>) {
+    for value in more {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+}
+
+/// Lowercase words of `text`, punctuation other than `.` and `-` dropped.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || ",;:()[]/\"'".contains(c))
+        .map(|w| w.trim_end_matches('.').to_string())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Names of the `vocabulary` terms mentioned in `text`, as whole words.
+fn vocabulary(text: &str, vocabulary: &[(&str, &str)]) -> Vec<String> {
+    let words = words(text);
+    let mut found = Vec::new();
+
+    for (term, name) in vocabulary {
+        let term: Vec<&str> = term.split(' ').collect();
+        // File extensions are also matched inside names such as `ec013.res.1`.
+        let mentioned = if term.len() == 1 && term[0].starts_with('.') {
+            words
+                .iter()
+                .any(|w| w.ends_with(term[0]) || w.contains(format!("{}.", term[0]).as_str()))
+        } else {
+            words.windows(term.len()).any(|w| w.iter().eq(term.iter()))
+        };
+        if mentioned && !found.contains(&name.to_string()) {
+            found.push(name.to_string());
+        }
+    }
+
+    found
+}
+
+fn number(word: &str) -> Option<u32> {
+    word.replace(',', "").parse().ok().or_else(|| {
+        NUMBER_WORDS
+            .iter()
+            .position(|n| *n == word)
+            .map(|n| n as u32)
+    })
+}
+
+fn first_number(text: &str) -> Option<u32> {
+    words(text).iter().find_map(|w| number(w))
+}
+
+/// The number right before one of `nouns`, allowing one word in between as in
+/// `442 recording sessions`.
+fn counted(text: &str, nouns: &[&str]) -> Option<u32> {
+    let words = words(text);
+    for (i, word) in words.iter().enumerate() {
+        if !nouns.contains(&word.as_str()) {
+            continue;
+        }
+        let before = |n: usize| i.checked_sub(n).and_then(|j| number(&words[j]));
+        if let Some(count) = before(1).or_else(|| before(2)) {
+            return Some(count);
+        }
+    }
+    None
+}
+
+/// The largest size mentioned in `text`, e.g. `433 GB` or `1.2TB`, in bytes.
+fn largest_size(text: &str) -> Option<u64> {
+    let words = words(text);
+    let mut largest: Option<u64> = None;
+
+    for (i, word) in words.iter().enumerate() {
+        // Either `433 gb` over two words or `433gb` in one.
+        let split = word.find(|c: char| c.is_alphabetic()).unwrap_or(word.len());
+        let (value, unit) = match split {
+            0 => continue,
+            s if s == word.len() => match words.get(i + 1) {
+                Some(unit) => (word.as_str(), unit.as_str()),
+                None => continue,
+            },
+            s => (&word[..s], &word[s..]),
+        };
+
+        let multiplier: u64 = match unit {
+            "kb" | "kib" => 1 << 10,
+            "mb" | "mib" => 1 << 20,
+            "gb" | "gib" | "gbytes" | "gigabytes" => 1 << 30,
+            "tb" | "tib" | "tbytes" | "terabytes" => 1 << 40,
+            _ => continue,
+        };
+        let Ok(value) = value.replace(',', "").parse::<f64>() else {
+            continue;
+        };
+        let bytes = (value * multiplier as f64) as u64;
+        largest = Some(largest.map_or(bytes, |l| l.max(bytes)));
+    }
+
+    largest
+}
+
+/// One entry per paragraph or list item.
+fn entries(paragraphs: &[String]) -> Vec<String> {
+    paragraphs.iter().map(|p| p.to_string()).collect()
+}
+
+/// People listed in `paragraphs`, separated by commas, semicolons or `and`.
+fn names(paragraphs: &[String]) -> Vec<String> {
+    paragraphs
+        .iter()
+        .flat_map(|p| p.split([',', ';']))
+        .flat_map(|p| p.split(" and "))
+        .map(|n| n.trim().trim_end_matches('.').to_string())
+        .filter(|n| !n.is_empty())
+        .collect()
+}
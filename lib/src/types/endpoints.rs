@@ -0,0 +1,101 @@
+use std::str::FromStr;
+use url as urllib;
+
+use crate::net::RetryPolicy;
+
+pub const CRCNS_CATALOG_URL: &str = "https://crcns.org/";
+pub const CRCNS_PORTAL_URL: &str = "https://portal.nersc.gov/project/crcns/download/index.php";
+
+/// Where the catalog pages are scraped from, where files are downloaded from and
+/// how patiently both are retried.
+///
+/// Both default to the public CRCNS services and can be pointed at a local mirror
+/// (or a fixture server) with the `CRCNS_CATALOG_URL` and `CRCNS_PORTAL_URL`
+/// environment variables, `CRCNS_MAX_RETRIES` bounds the retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// Base of the catalog site, the sitemap lives at `<catalog>/sitemap`.
+    pub catalog: urllib::Url,
+    /// Download portal script, files are requested relative to it.
+    pub portal: urllib::Url,
+    /// Applied to every request sent to either endpoint.
+    pub retry: RetryPolicy,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints::new(
+            urllib::Url::from_str(CRCNS_CATALOG_URL).unwrap(),
+            urllib::Url::from_str(CRCNS_PORTAL_URL).unwrap(),
+        )
+    }
+}
+
+impl Endpoints {
+    pub fn new(catalog: urllib::Url, portal: urllib::Url) -> Self {
+        // Without a trailing slash `Url::join` would replace the last segment of the base.
+        let mut catalog = catalog;
+        if !catalog.path().ends_with('/') {
+            let path = format!("{}/", catalog.path());
+            catalog.set_path(path.as_str());
+        }
+        Endpoints {
+            catalog,
+            portal,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let default = Endpoints::default();
+        let catalog = env_url("CRCNS_CATALOG_URL").unwrap_or(default.catalog);
+        let portal = env_url("CRCNS_PORTAL_URL").unwrap_or(default.portal);
+        let mut endpoints = Endpoints::new(catalog, portal);
+        if let Some(max_retries) = std::env::var("CRCNS_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            endpoints.retry.max_retries = max_retries;
+        }
+        endpoints
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn sitemap_url(&self) -> urllib::Url {
+        self.catalog.join("sitemap").unwrap()
+    }
+
+    /// Path segments of `url` relative to the catalog base, `None` if `url` is not
+    /// served by the catalog.
+    pub fn catalog_segments(&self, url: &urllib::Url) -> Option<Vec<String>> {
+        if url.origin() != self.catalog.origin() {
+            return None;
+        }
+        let relative = url.path().strip_prefix(self.catalog.path())?;
+        let segments = relative
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+        Some(segments)
+    }
+
+    pub fn portal_url(&self, filepath: &str) -> urllib::Url {
+        self.portal.join(filepath).unwrap()
+    }
+}
+
+fn env_url(key: &str) -> Option<urllib::Url> {
+    let value = std::env::var(key).ok()?;
+    match urllib::Url::parse(value.as_str()) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            log::warn!("Ignoring {key}={value}: {e}");
+            None
+        }
+    }
+}
@@ -1,4 +1,20 @@
-#[derive(Clone)]
+/// How a local file compares to what the portal lists for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Not verified yet.
+    #[default]
+    Unknown,
+    /// Matches the listed size and md5.
+    Ok,
+    /// Present but differs from the listing, it should be downloaded again.
+    Mismatch,
+    /// Neither downloaded nor being downloaded.
+    Missing,
+    /// Only a `.part` of it is on disk.
+    Partial,
+}
+
+#[derive(Debug, Clone)]
 pub struct File {
     pub remote_md5: String,
     pub remote_path: String,
@@ -7,6 +23,11 @@ pub struct File {
     pub local_path: String,
     pub local_size: u64,
     pub extension: String,
+    pub status: FileStatus,
+    /// Dataset path of the archive the file was extracted from, `None` when listed.
+    pub archive: Option<String>,
+    /// Intact copy in a team mirror, read in place when there is none locally.
+    pub mirror: Option<String>,
 }
 
 impl Default for File {
@@ -18,6 +39,9 @@ impl Default for File {
         let local_path = String::new();
         let local_size = 0;
         let extension = String::new();
+        let status = FileStatus::default();
+        let archive = None;
+        let mirror = None;
 
         Self {
             remote_md5,
@@ -27,6 +51,9 @@ impl Default for File {
             local_path,
             local_size,
             extension,
+            status,
+            archive,
+            mirror,
         }
     }
 }
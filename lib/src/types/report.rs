@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// Outcome of a background task that the user should hear about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Report {
+    Info(String),
+    Success(String),
+    Warning(String),
+    Error(String),
+}
+
+impl Report {
+    pub fn message(&self) -> &str {
+        match self {
+            Report::Info(m) | Report::Success(m) | Report::Warning(m) | Report::Error(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
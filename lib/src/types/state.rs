@@ -1,6 +1,14 @@
+use crate::credentials::CredentialStore;
+use crate::downloads::DownloadManager;
+use crate::recording::{Features, PositionSeries};
+use crate::search::SearchIndex;
+use crate::settings::{Settings, Workspace};
+use crate::storage::{DiskUsage, StoragePolicy, VerifiedCopies};
 use crate::types::Collection;
 use crate::types::Dataset;
+use crate::types::Endpoints;
 use crate::types::File;
+use crate::types::Report;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -16,38 +24,95 @@ pub type SrMap = HashMap<String, Arc<Mutex<SrPair>>>;
 #[derive(Clone)]
 pub struct State {
     pub working_files: Arc<Mutex<Vec<File>>>,
+    /// Files in the working dataset's directory that its listing does not mention.
+    pub untracked_files: Arc<Mutex<Vec<String>>>,
+    /// Archive whose members were asked for, picked up by the archive window.
+    pub opened_archive: Arc<Mutex<Option<File>>>,
+    /// Dataset whose downloads are being planned, picked up by the plan window.
+    pub opened_plan: Arc<Mutex<Option<(Collection, Dataset)>>>,
     pub working_dataset: Arc<Mutex<Dataset>>,
     pub working_collection: Arc<Mutex<Collection>>,
+    /// `.xml` of the session picked for the plots, relative to the data directory.
+    pub working_session: Arc<Mutex<Option<String>>>,
+    /// Root of `workspace`, downloads and the catalog live in its `data/`.
     pub working_directory: PathBuf,
+    pub workspace: Workspace,
+    pub endpoints: Endpoints,
+    pub credential_store: CredentialStore,
 
     pub collections: Arc<Mutex<Vec<Collection>>>,
+    /// Rebuilt from `collections` by `global::reindex` whenever they change.
+    pub search: Arc<Mutex<SearchIndex>>,
 
     pub progress_sr: Arc<Mutex<SrMap>>,
     pub progress: Arc<Mutex<HashMap<String, f32>>>,
     pub progress_done: Arc<Mutex<HashSet<String>>>,
 
+    pub reports: Arc<Mutex<Vec<Report>>>,
+    pub downloads: DownloadManager,
+    pub storage: Arc<Mutex<StoragePolicy>>,
+    /// Last scan of the data directory, refreshed by `global::refresh_usage`.
+    pub usage: Arc<Mutex<DiskUsage>>,
+    /// Mirror copies read in place of missing local files, see `global::resolve_data_path`.
+    pub verified_copies: Arc<Mutex<VerifiedCopies>>,
+
     pub lfp_series: Arc<Mutex<Vec<[f64; 2]>>>,
     pub spk_series: Arc<Mutex<Vec<Vec<[f64; 2]>>>>,
-    pub fet_series: Arc<Mutex<Vec<[f64; 2]>>>,
+    /// Shared with the plot, which keeps its points until another file is loaded.
+    pub fet_series: Arc<Mutex<Arc<Features>>>,
+    pub position_series: Arc<Mutex<Arc<PositionSeries>>>,
 }
 
 impl Default for State {
     fn default() -> Self {
+        State::for_workspace(Workspace::new(
+            Settings::DEFAULT_WORKSPACE,
+            current_dir().unwrap(),
+        ))
+    }
+}
+
+impl State {
+    /// A fresh state working in `workspace`, with its download queue restored.
+    pub fn for_workspace(workspace: Workspace) -> Self {
+        let working_directory = workspace.root.clone();
+        let storage_filepath = workspace.data_directory().join("storage.bin");
+        let (storage, storage_error) = StoragePolicy::load_or_set_aside(&storage_filepath);
+        let reports = storage_error
+            .inspect(|message| log::error!("{message}"))
+            .map(Report::Warning)
+            .into_iter()
+            .collect();
         Self {
             lfp_series: Arc::new(Mutex::new(Vec::new())),
             spk_series: Arc::new(Mutex::new(Vec::new())),
-            fet_series: Arc::new(Mutex::new(Vec::new())),
+            fet_series: Arc::new(Mutex::new(Arc::new(Features::default()))),
+            position_series: Arc::new(Mutex::new(Arc::new(PositionSeries::default()))),
 
             working_files: Arc::new(Mutex::new(Vec::new())),
+            untracked_files: Arc::new(Mutex::new(Vec::new())),
+            opened_archive: Arc::new(Mutex::new(None)),
+            opened_plan: Arc::new(Mutex::new(None)),
             working_dataset: Arc::new(Mutex::new(Dataset::default())),
             working_collection: Arc::new(Mutex::new(Collection::default())),
-            working_directory: current_dir().unwrap(),
+            working_session: Arc::new(Mutex::new(None)),
+            downloads: DownloadManager::load(workspace.data_directory().join("downloads.bin")),
+            storage: Arc::new(Mutex::new(storage)),
+            usage: Arc::new(Mutex::new(DiskUsage::default())),
+            verified_copies: Arc::new(Mutex::new(VerifiedCopies::default())),
+            working_directory,
+            workspace,
+            endpoints: Endpoints::from_env(),
+            credential_store: CredentialStore::detect(),
 
             collections: Arc::new(Mutex::new(Vec::new())),
+            search: Arc::new(Mutex::new(SearchIndex::default())),
 
             progress: Arc::new(Mutex::new(HashMap::new())),
             progress_sr: Arc::new(Mutex::new(HashMap::new())),
             progress_done: Arc::new(Mutex::new(HashSet::new())),
+
+            reports: Arc::new(Mutex::new(reports)),
         }
     }
 }
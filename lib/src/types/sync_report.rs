@@ -0,0 +1,36 @@
+use std::fmt;
+
+/// What a catalog sync changed, datasets named `<collection>/<dataset>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Datasets found as they were, not modified or not even transferred.
+    pub unchanged: usize,
+}
+
+impl SyncReport {
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty())
+    }
+
+    pub(crate) fn sort(&mut self) {
+        self.added.sort();
+        self.updated.sort();
+        self.removed.sort();
+    }
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed, {} unchanged",
+            self.added.len(),
+            self.updated.len(),
+            self.removed.len(),
+            self.unchanged
+        )
+    }
+}
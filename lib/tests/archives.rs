@@ -0,0 +1,235 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use lib::files::{
+    dataset_directory, extract_members, extracted_file, list_members, reconcile, ArchiveKind,
+    ArchiveMember, ExtractedFiles, HashCache,
+};
+use lib::types::{File, FileStatus};
+
+const MEMBERS: [(&str, &[u8]); 4] = [
+    ("ec012ec.188/ec012ec.188.xml", b"<parameters/>"),
+    ("ec012ec.188/ec012ec.188.eeg", b"0123456789abcdef"),
+    ("ec012ec.188/ec012ec.188.res.1", b"10\n20\n"),
+    ("ec012ec.188/ec012ec.188.clu.1", b"2\n3\n3\n"),
+];
+
+fn tar_bytes() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "ec012ec.188/", std::io::empty())
+        .unwrap();
+    for (path, body) in MEMBERS {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, body).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+fn write_tar(directory: &Path) -> PathBuf {
+    let path = directory.join("ec012ec.188.tar");
+    std::fs::write(&path, tar_bytes()).unwrap();
+    path
+}
+
+fn write_tar_gz(directory: &Path) -> PathBuf {
+    let path = directory.join("ec012ec.188.tar.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        std::fs::File::create(&path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(&tar_bytes()).unwrap();
+    encoder.finish().unwrap();
+    path
+}
+
+fn write_zip(directory: &Path) -> PathBuf {
+    let path = directory.join("ec012ec.188.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let options = zip::write::FileOptions::default();
+    zip.add_directory("ec012ec.188/", options).unwrap();
+    for (member, body) in MEMBERS {
+        zip.start_file(member, options).unwrap();
+        zip.write_all(body).unwrap();
+    }
+    zip.finish().unwrap();
+    path
+}
+
+fn expected_members() -> Vec<ArchiveMember> {
+    MEMBERS
+        .iter()
+        .map(|(path, body)| ArchiveMember {
+            path: path.to_string(),
+            size: body.len() as u64,
+        })
+        .collect()
+}
+
+#[test]
+fn kinds_are_recognized_from_the_name() {
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("a/ec012ec.11.tar.gz")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("b.TGZ")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("b.tar")),
+        Some(ArchiveKind::Tar)
+    );
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("b.zip")),
+        Some(ArchiveKind::Zip)
+    );
+    assert_eq!(ArchiveKind::from_path(Path::new("b.eeg")), None);
+}
+
+#[test]
+fn members_are_listed_without_directories() {
+    let directory = tempfile::tempdir().unwrap();
+    for archive in [
+        write_tar(directory.path()),
+        write_tar_gz(directory.path()),
+        write_zip(directory.path()),
+    ] {
+        assert_eq!(
+            list_members(&archive).unwrap(),
+            expected_members(),
+            "{}",
+            archive.display()
+        );
+    }
+    // Nothing was extracted by listing.
+    assert!(!directory.path().join("ec012ec.188").exists());
+}
+
+#[test]
+fn selected_members_are_extracted_with_progress() {
+    let directory = tempfile::tempdir().unwrap();
+    for archive in [
+        write_tar(directory.path()),
+        write_tar_gz(directory.path()),
+        write_zip(directory.path()),
+    ] {
+        let mut name = archive.file_name().unwrap().to_os_string();
+        name.push(".out");
+        let destination = directory.path().join(name);
+        let selected: Vec<ArchiveMember> = expected_members()
+            .into_iter()
+            .filter(|m| !m.path.ends_with(".eeg"))
+            .collect();
+
+        let mut calls = Vec::new();
+        let extracted = extract_members(&archive, &destination, &selected, |written, total| {
+            calls.push((written, total))
+        })
+        .unwrap();
+
+        assert_eq!(extracted.len(), 3);
+        assert_eq!(calls.last(), Some(&(25, 25)));
+        for member in extracted {
+            let (_, body) = MEMBERS
+                .iter()
+                .find(|(p, _)| *p == member.member.path)
+                .unwrap();
+            assert_eq!(member.path, destination.join(&member.member.path));
+            assert_eq!(std::fs::read(&member.path).unwrap(), *body);
+        }
+        assert!(!destination.join("ec012ec.188/ec012ec.188.eeg").exists());
+        assert!(!destination
+            .join("ec012ec.188/ec012ec.188.xml.part")
+            .exists());
+    }
+}
+
+#[test]
+fn unknown_members_and_unsafe_paths_are_refused() {
+    let directory = tempfile::tempdir().unwrap();
+    let archive = write_tar(directory.path());
+    let missing = ArchiveMember {
+        path: "ec012ec.188/ec012ec.188.fet.1".to_string(),
+        size: 3,
+    };
+    let error = extract_members(&archive, directory.path(), &[missing], |_, _| ()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+    // A member escaping the destination is not even listed.
+    let path = directory.path().join("evil.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    zip.start_file("../escaped.txt", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(b"x").unwrap();
+    zip.finish().unwrap();
+    assert!(list_members(&path).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn extracted_members_stay_tracked_after_a_reload() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path();
+    let dataset = data.join("hc/hc-3");
+    std::fs::create_dir_all(dataset.join("ec012ec.11")).unwrap();
+    let archive_path = write_tar(&dataset.join("ec012ec.11"));
+    let archive = File {
+        remote_path: "ec012ec.11/ec012ec.188.tar".to_string(),
+        remote_size: std::fs::metadata(&archive_path).unwrap().len(),
+        local_path: archive_path.to_str().unwrap().to_string(),
+        ..File::default()
+    };
+    assert_eq!(dataset_directory(&archive), Some(dataset.as_path()));
+
+    let extracted = extract_members(
+        &archive_path,
+        archive_path.parent().unwrap(),
+        &expected_members(),
+        |_, _| (),
+    )
+    .unwrap();
+    let mut record = ExtractedFiles::load(&dataset).unwrap();
+    for member in &extracted {
+        record.add(&extracted_file(&archive, member));
+    }
+    // Listed files are not recorded.
+    record.add(&archive);
+    record.save(&dataset).unwrap();
+
+    // A later run only has the listing to start from.
+    let record = ExtractedFiles::load(&dataset).unwrap();
+    assert_eq!(record.len(), MEMBERS.len());
+    let mut cache = HashCache::default();
+    let reconciliation = reconcile(data, "hc", "hc-3", &[archive], &mut cache)
+        .await
+        .unwrap();
+
+    assert!(
+        reconciliation.untracked.is_empty(),
+        "{:?}",
+        reconciliation.untracked
+    );
+    assert_eq!(reconciliation.files.len(), MEMBERS.len() + 1);
+    let eeg = reconciliation
+        .files
+        .iter()
+        .find(|f| f.remote_path == "ec012ec.11/ec012ec.188/ec012ec.188.eeg")
+        .unwrap();
+    assert_eq!(eeg.archive.as_deref(), Some("ec012ec.11/ec012ec.188.tar"));
+    assert_eq!(eeg.status, FileStatus::Ok);
+    assert_eq!(
+        eeg.local_path,
+        dataset
+            .join("ec012ec.11/ec012ec.188/ec012ec.188.eeg")
+            .to_str()
+            .unwrap()
+    );
+}
@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+
+use lib::recording::{BinaryFormat, BinaryRecording, Dtype, Endianness, Error, SessionParameters};
+
+const SESSION: &str = "<parameters>\
+    <acquisitionSystem><nBits>16</nBits><nChannels>3</nChannels>\
+    <samplingRate>20000</samplingRate><voltageRange>20</voltageRange>\
+    <amplification>1000</amplification></acquisitionSystem>\
+    <fieldPotentials><lfpSamplingRate>10</lfpSamplingRate></fieldPotentials>\
+    </parameters>";
+
+fn parameters() -> SessionParameters {
+    SessionParameters::parse(SESSION, Path::new("s.xml")).unwrap()
+}
+
+/// Sample `s` of channel `c` is `100 * c + s`.
+fn write_eeg(directory: &Path, n_samples: i16, trailing: &[u8]) -> PathBuf {
+    let mut bytes = Vec::new();
+    for s in 0..n_samples {
+        for c in 0..3 {
+            bytes.extend_from_slice(&(100 * c + s).to_le_bytes());
+        }
+    }
+    bytes.extend_from_slice(trailing);
+    let filepath = directory.join("s.eeg");
+    std::fs::write(&filepath, bytes).unwrap();
+    filepath
+}
+
+#[test]
+fn recordings_are_viewed_without_copies() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = write_eeg(directory.path(), 25, &[1, 2, 3]);
+    let recording = BinaryRecording::open(&filepath, &parameters()).unwrap();
+
+    assert_eq!(recording.n_channels(), 3);
+    assert_eq!(recording.n_samples(), 25);
+    assert_eq!(recording.duration(), 2.5);
+    assert!(recording.is_truncated());
+    assert_eq!(recording.trailing_bytes(), 3);
+
+    let view = recording.view::<i16>().unwrap();
+    assert_eq!(view.dim(), (25, 3));
+    assert_eq!(view[[24, 2]], 224);
+    assert!(matches!(
+        recording.view::<i32>(),
+        Err(Error::Unsupported { .. })
+    ));
+
+    // Windows are slices of the same view.
+    let window = recording.window::<i16>(5..8, 1..3).unwrap();
+    assert_eq!(window.dim(), (3, 2));
+    assert_eq!(window.column(0).to_vec(), vec![105, 106, 107]);
+    assert_eq!(window.row(2).to_vec(), vec![107, 207]);
+    assert_eq!(window.as_ptr(), &view[[5, 1]] as *const i16);
+    assert_eq!(recording.window::<i16>(25..25, 0..3).unwrap().dim(), (0, 3));
+    assert!(recording.window::<i16>(20..26, 0..1).is_err());
+    assert!(recording.window::<i16>(0..1, 2..4).is_err());
+    assert!(recording.window::<i32>(0..1, 0..1).is_err());
+}
+
+#[test]
+fn windows_are_read_by_time_and_channel() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = write_eeg(directory.path(), 25, &[]);
+    let recording = BinaryRecording::open(&filepath, &parameters()).unwrap();
+    assert!(!recording.is_truncated());
+
+    // 10 Hz: one second from 0.5s, then a window running past the end.
+    assert_eq!(recording.sample_range(0.5, 1.0), 5..15);
+    assert_eq!(recording.sample_range(2.0, 10.0), 20..25);
+    assert_eq!(recording.sample_range(30.0, 1.0), 25..25);
+
+    let window = recording.read(5..8, &[2, 0]).unwrap();
+    assert_eq!(window.dim(), (3, 2));
+    assert_eq!(window.column(0).to_vec(), vec![205.0, 206.0, 207.0]);
+    assert_eq!(window.column(1).to_vec(), vec![5.0, 6.0, 7.0]);
+    assert_eq!(
+        recording.read(0..1, &[]).unwrap().row(0).to_vec(),
+        vec![0.0, 100.0, 200.0]
+    );
+
+    let microvolts = recording.read_microvolts(5..6, &[1]).unwrap();
+    let expected = 105.0 * 20.0 / 65536.0 / 1000.0 * 1e6;
+    assert!((microvolts[[0, 0]] - expected).abs() < 1e-9);
+
+    assert!(recording.read(20..26, &[0]).is_err());
+    assert!(recording.read(0..1, &[3]).is_err());
+}
+
+#[test]
+fn other_types_and_byte_orders_are_decoded() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("s.dat");
+    let values: [i32; 4] = [-1, 70000, 3, -70000];
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    std::fs::write(&filepath, bytes).unwrap();
+
+    let format = BinaryFormat {
+        n_channels: 2,
+        sampling_rate: 1.0,
+        dtype: Dtype::I32,
+        endianness: Endianness::Big,
+        microvolts_per_bit: None,
+    };
+    let recording = BinaryRecording::open_with(&filepath, format).unwrap();
+
+    assert_eq!(
+        recording.read(0..2, &[]).unwrap().into_raw_vec(),
+        vec![-1.0, 70000.0, 3.0, -70000.0]
+    );
+    assert!(recording.read_microvolts(0..2, &[]).is_err());
+    if cfg!(target_endian = "little") {
+        assert!(recording.view::<i32>().is_err());
+    }
+}
+
+#[test]
+fn the_format_follows_the_file_kind() {
+    let directory = tempfile::tempdir().unwrap();
+    let parameters = parameters();
+
+    let dat = BinaryFormat::for_file(Path::new("s.dat"), &parameters).unwrap();
+    assert_eq!(dat.sampling_rate, 20000.0);
+    assert_eq!(dat.frame_size(), 6);
+    let lfp = BinaryFormat::for_file(Path::new("s.lfp"), &parameters).unwrap();
+    assert_eq!(lfp.sampling_rate, 10.0);
+    assert!(matches!(
+        BinaryFormat::for_file(Path::new("s.spk.1"), &parameters),
+        Err(Error::Unsupported { .. })
+    ));
+
+    // An empty file is a recording without samples, a missing one is an error.
+    let empty = directory.path().join("e.eeg");
+    std::fs::write(&empty, b"").unwrap();
+    let recording = BinaryRecording::open(&empty, &parameters).unwrap();
+    assert_eq!(recording.n_samples(), 0);
+    assert_eq!(recording.view::<i16>().unwrap().dim(), (0, 3));
+    assert!(matches!(
+        BinaryRecording::open(&directory.path().join("m.eeg"), &parameters),
+        Err(Error::Io { .. })
+    ));
+}
@@ -0,0 +1,214 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use lib::catalog::{Error, Manifest, MANIFEST_FILE, SCHEMA_VERSION};
+use lib::net::Validators;
+use lib::types::{Collection, Dataset, DatasetMetadata, CRCNS};
+
+const HC3_HTML: &str = r#"<html><body><div id="content">
+<h1 id="parent-fieldname-title">hc-3</h1>
+<span class="documentModified"><span>last modified</span> Jul 09, 2019 02:25 PM</span>
+<p>Recordings with tetrodes from 11 rats.</p>
+</div></body></html>"#;
+
+fn catalog() -> Vec<Collection> {
+    let hc3 = Dataset {
+        url: "https://crcns.org/data-sets/hc/hc-3".to_string(),
+        html: HC3_HTML.to_string(),
+        alias: "hc-3".to_string(),
+        content: "Recordings with tetrodes from 11 rats.".to_string(),
+        description: "Rat hippocampus".to_string(),
+        last_modified: 1562682300,
+        validators: Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        },
+        metadata: DatasetMetadata::from_html(HC3_HTML),
+    };
+    vec![Collection {
+        url: "https://crcns.org/data-sets/hc".to_string(),
+        alias: "hc".to_string(),
+        descriptor: "Hippocampus".to_string(),
+        last_modified: 1562682000,
+        datasets: Arc::new(Mutex::new(vec![hc3])),
+        ..Collection::default()
+    }]
+}
+
+fn write(path: &Path, bytes: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, bytes).unwrap();
+}
+
+fn encode<T: bincode::Encode>(value: T) -> Vec<u8> {
+    bincode::encode_to_vec(value, bincode::config::standard()).unwrap()
+}
+
+#[tokio::test]
+async fn catalog_survives_a_round_trip() {
+    let directory = tempfile::tempdir().unwrap();
+    let collections = Arc::new(Mutex::new(catalog()));
+
+    CRCNS::persist(collections.clone(), directory.path().to_path_buf())
+        .await
+        .unwrap();
+    let loaded = CRCNS::load(directory.path().to_path_buf()).await.unwrap();
+
+    assert_eq!(
+        Manifest::from_collections(&loaded),
+        Manifest::from_collections(&collections.lock().unwrap())
+    );
+    let datasets = loaded[0].datasets.lock().unwrap();
+    assert_eq!(datasets[0].metadata.subjects, Some(11));
+    assert_eq!(datasets[0].validators.etag.as_deref(), Some("\"abc\""));
+}
+
+#[tokio::test]
+async fn nothing_persisted_loads_empty() {
+    let directory = tempfile::tempdir().unwrap();
+    let loaded = CRCNS::load(directory.path().join("data")).await.unwrap();
+    assert!(loaded.is_empty());
+}
+
+#[test]
+fn saving_replaces_the_manifest_without_leftovers() {
+    let directory = tempfile::tempdir().unwrap();
+
+    Manifest::from_collections(&catalog())
+        .save(directory.path())
+        .unwrap();
+    Manifest::default().save(directory.path()).unwrap();
+
+    let entries: Vec<String> = std::fs::read_dir(directory.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(entries, [MANIFEST_FILE]);
+    assert_eq!(
+        Manifest::load(directory.path()).unwrap(),
+        Some(Manifest::default())
+    );
+}
+
+#[test]
+fn corrupt_manifest_is_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join(MANIFEST_FILE);
+
+    let mut bytes = Manifest::from_collections(&catalog()).encode().unwrap();
+    bytes.truncate(bytes.len() / 2);
+    write(&path, &bytes);
+    let error = Manifest::load(directory.path()).unwrap_err();
+    assert!(matches!(error, Error::Decode { .. }), "{error}");
+    assert!(error.to_string().contains(MANIFEST_FILE));
+
+    write(&path, b"definitely not a catalog");
+    assert!(matches!(
+        Manifest::load(directory.path()),
+        Err(Error::NotACatalog(_))
+    ));
+}
+
+#[test]
+fn newer_manifest_versions_are_refused() {
+    let directory = tempfile::tempdir().unwrap();
+    let mut bytes = encode((*b"CRCNSCAT", SCHEMA_VERSION + 1));
+    bytes.extend(encode(Manifest::default()));
+    write(&directory.path().join(MANIFEST_FILE), &bytes);
+
+    match Manifest::load(directory.path()) {
+        Err(Error::UnsupportedVersion { version, .. }) => assert_eq!(version, SCHEMA_VERSION + 1),
+        other => panic!("expected a version error, got {other:?}"),
+    }
+}
+
+#[test]
+fn first_per_directory_layout_is_migrated() {
+    let directory = tempfile::tempdir().unwrap();
+
+    // Collection and dataset as the first versions wrote them, with string dates.
+    let dataset = (
+        "https://crcns.org/data-sets/hc/hc-3",
+        HC3_HTML,
+        "hc-3",
+        "Recordings with tetrodes from 11 rats.",
+        "Rat hippocampus",
+        "2024-05-01 10:00:00 UTC",
+    );
+    let collection = (
+        "https://crcns.org/data-sets/hc",
+        "<html></html>",
+        "hc",
+        "Hippocampus",
+        "last modified Jul 09, 2019 02:20 PM",
+        Vec::<()>::new(),
+    );
+    write(&directory.path().join("hc/ds.bin"), &encode(collection));
+    write(&directory.path().join("hc/hc-3/ds.bin"), &encode(dataset));
+    // Downloaded files next to the catalog files are not mistaken for datasets.
+    write(&directory.path().join("hc/hc-3/files/a.res.1"), b"1\n2\n");
+
+    let manifest = Manifest::load(directory.path()).unwrap().unwrap();
+
+    assert_eq!(manifest.collections.len(), 1);
+    let hc = &manifest.collections[0];
+    assert_eq!(hc.alias, "hc");
+    assert_eq!(hc.last_modified, 1562682000);
+    assert_eq!(hc.datasets.len(), 1);
+    let hc3 = &hc.datasets[0];
+    assert_eq!(hc3.alias, "hc-3");
+    // Dates come from the page, metadata is extracted while migrating.
+    assert_eq!(hc3.last_modified, 1562682300);
+    assert_eq!(hc3.metadata.techniques, ["Tetrodes"]);
+    assert_eq!(hc3.metadata.subjects, Some(11));
+}
+
+#[test]
+fn second_per_directory_layout_is_migrated() {
+    let directory = tempfile::tempdir().unwrap();
+
+    let validators = Validators {
+        etag: Some("\"v2\"".to_string()),
+        last_modified: None,
+    };
+    let dataset = (
+        "https://crcns.org/data-sets/hc/hc-3",
+        HC3_HTML,
+        "hc-3",
+        "Recordings with tetrodes from 11 rats.",
+        "Rat hippocampus",
+        1562682300i64,
+        validators.clone(),
+    );
+    // The collection file embeds an older copy of the dataset.
+    let mut stale = dataset.clone();
+    stale.5 = 1;
+    let collection = (
+        "https://crcns.org/data-sets/hc",
+        "<html></html>",
+        "hc",
+        "Hippocampus",
+        1562682000i64,
+        validators.clone(),
+        vec![stale],
+    );
+    write(&directory.path().join("hc/ds.bin"), &encode(collection));
+    write(&directory.path().join("hc/hc-3/ds.bin"), &encode(dataset));
+
+    let manifest = Manifest::load(directory.path()).unwrap().unwrap();
+    let hc3 = &manifest.collections[0].datasets;
+    assert_eq!(hc3.len(), 1);
+    assert_eq!(hc3[0].last_modified, 1562682300);
+    assert_eq!(hc3[0].validators, validators);
+    assert_eq!(hc3[0].metadata.subjects, Some(11));
+}
+
+#[test]
+fn undecodable_legacy_file_is_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    write(&directory.path().join("hc/ds.bin"), b"\xff\xff\xff");
+
+    let error = Manifest::load(directory.path()).unwrap_err();
+    assert!(matches!(error, Error::Decode { .. }));
+    assert!(error.to_string().contains("ds.bin"), "{error}");
+}
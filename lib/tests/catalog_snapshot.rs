@@ -0,0 +1,151 @@
+mod common;
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use lib::catalog::{Error, Manifest, Snapshot};
+use lib::types::{Collection, Dataset};
+
+fn dataset(alias: &str, last_modified: i64, description: &str) -> Dataset {
+    Dataset {
+        alias: alias.to_string(),
+        description: description.to_string(),
+        last_modified,
+        ..Dataset::default()
+    }
+}
+
+fn collection(alias: &str, datasets: Vec<Dataset>) -> Collection {
+    Collection {
+        alias: alias.to_string(),
+        descriptor: alias.to_uppercase(),
+        datasets: Arc::new(Mutex::new(datasets)),
+        ..Collection::default()
+    }
+}
+
+fn descriptions(data_directory: &Path) -> Vec<(String, String)> {
+    let manifest = Manifest::load(data_directory).unwrap().unwrap();
+    let mut descriptions: Vec<(String, String)> = manifest
+        .collections
+        .iter()
+        .flat_map(|c| {
+            c.datasets
+                .iter()
+                .map(|d| (format!("{}/{}", c.alias, d.alias), d.description.clone()))
+        })
+        .collect();
+    descriptions.sort();
+    descriptions
+}
+
+#[test]
+fn snapshot_carries_catalog_and_filelists() {
+    let online = tempfile::tempdir().unwrap();
+    let offline = tempfile::tempdir().unwrap();
+    let archive = online.path().join("catalog.snapshot");
+
+    common::write(online.path(), "hc/hc-3/filelist.txt", "a.res.1 4\n");
+    common::write(online.path(), "hc/hc-3/checksums.md5", "abc  a.res.1\n");
+    let collections = vec![collection("hc", vec![dataset("hc-3", 10, "rats")])];
+
+    let snapshot = Snapshot::capture(&collections, online.path()).unwrap();
+    assert_eq!(snapshot.files.len(), 2);
+    snapshot.export(&archive).unwrap();
+
+    let imported = Snapshot::read(&archive).unwrap();
+    assert_eq!(imported, snapshot);
+
+    let report = imported.import(offline.path()).unwrap();
+    assert_eq!(report.added, ["hc/hc-3"]);
+    assert_eq!(
+        descriptions(offline.path()),
+        [("hc/hc-3".to_string(), "rats".to_string())]
+    );
+    assert_eq!(
+        std::fs::read_to_string(offline.path().join("hc/hc-3/filelist.txt")).unwrap(),
+        "a.res.1 4\n"
+    );
+}
+
+#[test]
+fn import_merges_with_the_persisted_catalog() {
+    let online = tempfile::tempdir().unwrap();
+    let offline = tempfile::tempdir().unwrap();
+
+    // What the offline machine already has.
+    Manifest::from_collections(&[
+        collection(
+            "hc",
+            vec![
+                dataset("hc-2", 20, "local hc-2"),
+                dataset("hc-3", 10, "local hc-3"),
+            ],
+        ),
+        collection("ssc", vec![dataset("ssc-1", 5, "local ssc-1")]),
+    ])
+    .save(offline.path())
+    .unwrap();
+    common::write(offline.path(), "hc/hc-2/filelist.txt", "local\n");
+    common::write(offline.path(), "hc/hc-3/filelist.txt", "stale\n");
+
+    common::write(online.path(), "hc/hc-2/filelist.txt", "remote\n");
+    common::write(online.path(), "hc/hc-3/filelist.txt", "fresh\n");
+    let snapshot = Snapshot::capture(
+        &[
+            collection(
+                "hc",
+                vec![
+                    dataset("hc-2", 20, "remote hc-2"),
+                    dataset("hc-3", 30, "remote hc-3"),
+                ],
+            ),
+            collection("pvc", vec![dataset("pvc-1", 1, "remote pvc-1")]),
+        ],
+        online.path(),
+    )
+    .unwrap();
+
+    let report = snapshot.import(offline.path()).unwrap();
+
+    assert_eq!(report.added, ["pvc/pvc-1"]);
+    assert_eq!(report.updated, ["hc/hc-3"]);
+    assert!(report.removed.is_empty());
+    assert_eq!(report.unchanged, 1);
+    assert_eq!(
+        descriptions(offline.path()),
+        [
+            ("hc/hc-2".to_string(), "local hc-2".to_string()),
+            ("hc/hc-3".to_string(), "remote hc-3".to_string()),
+            ("pvc/pvc-1".to_string(), "remote pvc-1".to_string()),
+            ("ssc/ssc-1".to_string(), "local ssc-1".to_string()),
+        ]
+    );
+
+    let filelist = |d: &str| {
+        std::fs::read_to_string(offline.path().join("hc").join(d).join("filelist.txt")).unwrap()
+    };
+    assert_eq!(filelist("hc-2"), "local\n");
+    assert_eq!(filelist("hc-3"), "fresh\n");
+}
+
+#[test]
+fn damaged_snapshot_is_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    let archive = directory.path().join("catalog.snapshot");
+
+    let mut bytes = Snapshot::default().encode().unwrap();
+    bytes.truncate(bytes.len() - 4);
+    std::fs::write(&archive, bytes).unwrap();
+    assert!(matches!(
+        Snapshot::read(&archive),
+        Err(Error::Decode { .. })
+    ));
+
+    // A manifest is not a snapshot.
+    Manifest::default().save(directory.path()).unwrap();
+    assert!(matches!(
+        Snapshot::read(&directory.path().join("catalog.bin")),
+        Err(Error::NotACatalog(_))
+    ));
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use common::FixtureServer;
+use lib::net::{get_crcns_file, parse_document_modified, write_response};
+use lib::types::{Collection, Dataset, Endpoints, CRCNS};
+
+#[test]
+fn endpoints_resolve_paths_relative_to_catalog_base() {
+    let endpoints = Endpoints::new(
+        url::Url::parse("http://mirror.local/crcns").unwrap(),
+        url::Url::parse("http://mirror.local/portal/index.php").unwrap(),
+    );
+
+    assert_eq!(
+        endpoints.sitemap_url().as_str(),
+        "http://mirror.local/crcns/sitemap"
+    );
+
+    let dataset = url::Url::parse("http://mirror.local/crcns/data-sets/hc/hc-3/").unwrap();
+    assert_eq!(
+        endpoints.catalog_segments(&dataset),
+        Some(vec!["data-sets".into(), "hc".into(), "hc-3".into()])
+    );
+
+    let foreign = url::Url::parse("https://crcns.org/data-sets/hc/hc-3").unwrap();
+    assert_eq!(endpoints.catalog_segments(&foreign), None);
+
+    assert_eq!(
+        endpoints.portal_url("synthetic/filelist.txt").as_str(),
+        "http://mirror.local/portal/synthetic/filelist.txt"
+    );
+}
+
+#[tokio::test]
+async fn sync_scrapes_fixture_catalog() {
+    let server = FixtureServer::start().await;
+    let collections = Arc::new(Mutex::new(Vec::new()));
+
+    CRCNS::get(server.endpoints(), collections.clone())
+        .await
+        .unwrap();
+
+    let mut collections: Vec<Collection> = collections.lock().unwrap().clone();
+    collections.sort_by(|a, b| a.alias.cmp(&b.alias));
+
+    let aliases: Vec<&str> = collections.iter().map(|c| c.alias.as_str()).collect();
+    assert_eq!(aliases, vec!["hc", "pvc"]);
+    assert_eq!(collections[0].descriptor, "Hippocampus");
+
+    let mut hc: Vec<String> = collections[0]
+        .datasets
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| d.alias.clone())
+        .collect();
+    hc.sort();
+    assert_eq!(hc, vec!["hc-2", "hc-3"]);
+
+    let pvc = collections[1].datasets.lock().unwrap().clone();
+    assert_eq!(pvc.len(), 1);
+    assert_eq!(pvc[0].alias, "pvc-1");
+    assert!(pvc[0].description.contains("macaque"));
+    assert_eq!(pvc[0].metadata.species, vec!["Macaque"]);
+
+    // Off-site links from the sitemap are never followed.
+    assert!(server.requests_to("/data-sets/vc").is_empty());
+}
+
+#[tokio::test]
+async fn download_fetches_file_from_fixture_portal() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local_filepath = directory.path().join("hc/hc-3/filelist.txt");
+
+    let response = get_crcns_file(&server.endpoints(), "synthetic/filelist.txt")
+        .await
+        .unwrap();
+    std::fs::create_dir_all(local_filepath.parent().unwrap()).unwrap();
+    let mut local_file = tokio::fs::File::create(&local_filepath).await.unwrap();
+    let written = write_response(&mut local_file, response).await.unwrap();
+
+    let expected =
+        std::fs::read(common::fixtures_directory().join("portal/synthetic/filelist.txt")).unwrap();
+    assert_eq!(written, expected.len() as u64);
+    assert_eq!(std::fs::read(local_filepath).unwrap(), expected);
+
+    let requests = server.requests_to("/project/crcns/download/synthetic/filelist.txt");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(
+        requests[0].form().get("fn").map(|s| s.as_str()),
+        Some("synthetic/filelist.txt")
+    );
+}
+
+#[test]
+fn document_modified_is_read_as_utc() {
+    assert_eq!(
+        parse_document_modified("last modified Jul 09, 2019 02:25 PM"),
+        Some(1562682300)
+    );
+    assert_eq!(
+        parse_document_modified("  Mar 14, 2018\n 11:02 AM "),
+        Some(1521025320)
+    );
+    assert_eq!(parse_document_modified("last modified yesterday"), None);
+}
+
+#[tokio::test]
+async fn unchanged_catalog_is_not_transferred_again() {
+    let server = FixtureServer::start().await;
+    let collections = Arc::new(Mutex::new(Vec::new()));
+
+    let first = CRCNS::get(server.endpoints(), collections.clone())
+        .await
+        .unwrap();
+    assert_eq!(first.added, vec!["hc/hc-2", "hc/hc-3", "pvc/pvc-1"]);
+
+    let hc3 = dataset(&collections, "hc", "hc-3");
+    assert_eq!(hc3.last_modified, 1562682300);
+
+    let second = CRCNS::get(server.endpoints(), collections.clone())
+        .await
+        .unwrap();
+    assert!(!second.has_changes());
+    assert_eq!(second.unchanged, 3);
+
+    let requests = server.requests_to("/data-sets/hc/hc-3");
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].header("if-none-match").is_some());
+    assert_eq!(dataset(&collections, "hc", "hc-3").content, hc3.content);
+}
+
+#[tokio::test]
+async fn sync_reports_updated_and_removed_datasets() {
+    let overrides: Arc<Mutex<HashMap<String, String>>> = Arc::new(Mutex::new(HashMap::new()));
+    let o = overrides.clone();
+    let server = FixtureServer::with_handler(Arc::new(move |request| {
+        match o.lock().unwrap().get(request.path.as_str()) {
+            Some(body) => common::html_page(request, body.clone()),
+            None => common::crcns(request),
+        }
+    }))
+    .await;
+    let collections = Arc::new(Mutex::new(Vec::new()));
+    CRCNS::get(server.endpoints(), collections.clone())
+        .await
+        .unwrap();
+
+    let catalog = common::fixtures_directory().join("catalog");
+    let sitemap = std::fs::read_to_string(catalog.join("sitemap.html")).unwrap();
+    let hc3 = std::fs::read_to_string(catalog.join("data-sets/hc/hc-3.html")).unwrap();
+    {
+        let mut overrides = overrides.lock().unwrap();
+        overrides.insert(
+            "/sitemap".to_string(),
+            sitemap.replace("<a href=\"/data-sets/hc/hc-2\">hc-2</a>", ""),
+        );
+        overrides.insert(
+            "/data-sets/hc/hc-3".to_string(),
+            hc3.replace("Jul 09, 2019 02:25 PM", "Jan 05, 2021 10:00 AM"),
+        );
+    }
+
+    let report = CRCNS::get(server.endpoints(), collections.clone())
+        .await
+        .unwrap();
+
+    assert!(report.added.is_empty());
+    assert_eq!(report.updated, vec!["hc/hc-3"]);
+    assert_eq!(report.removed, vec!["hc/hc-2"]);
+    assert_eq!(report.unchanged, 1);
+
+    let hc = collections.lock().unwrap()[..]
+        .iter()
+        .find(|c| c.alias == "hc")
+        .cloned()
+        .unwrap();
+    let aliases: Vec<String> = hc
+        .datasets
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| d.alias.clone())
+        .collect();
+    assert_eq!(aliases, vec!["hc-3"]);
+}
+
+fn dataset(collections: &Mutex<Vec<Collection>>, collection: &str, alias: &str) -> Dataset {
+    let collections = collections.lock().unwrap();
+    let collection = collections.iter().find(|c| c.alias == collection).unwrap();
+    let datasets = collection.datasets.lock().unwrap();
+    datasets.iter().find(|d| d.alias == alias).unwrap().clone()
+}
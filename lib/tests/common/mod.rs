@@ -0,0 +1,364 @@
+//! Minimal HTTP/1.1 server standing in for crcns.org and the NERSC download portal.
+//!
+//! Catalog pages are served from `tests/fixtures/catalog`, portal files from
+//! `tests/fixtures/portal` once the posted credentials match [`USERNAME`] and
+//! [`PASSWORD`], or the request carries a session cookie handed out on such a login.
+//! Every connection is closed after a single response.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use lib::types::{Endpoints, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub const USERNAME: &str = "lens";
+pub const PASSWORD: &str = "secret";
+pub const PORTAL_PATH: &str = "/project/crcns/download/index.php";
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(name.to_ascii_lowercase().as_str())
+            .map(|v| v.as_str())
+    }
+
+    pub fn form(&self) -> HashMap<String, String> {
+        url::form_urlencoded::parse(&self.body)
+            .into_owned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: body.into(),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Response::new(404, "text/plain", "Not Found")
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+pub type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+pub struct FixtureServer {
+    pub addr: SocketAddr,
+    pub requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl FixtureServer {
+    /// Serves the bundled CRCNS fixtures.
+    pub async fn start() -> Self {
+        FixtureServer::with_handler(Arc::new(crcns)).await
+    }
+
+    pub async fn with_handler(handler: Handler) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let r = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    break;
+                };
+                let handler = handler.clone();
+                let r = r.clone();
+                tokio::spawn(async move {
+                    serve(stream, handler, r).await;
+                });
+            }
+        });
+
+        FixtureServer { addr, requests }
+    }
+
+    pub fn url(&self, path: &str) -> url::Url {
+        url::Url::parse(format!("http://{}{}", self.addr, path).as_str()).unwrap()
+    }
+
+    pub fn endpoints(&self) -> Endpoints {
+        Endpoints::new(self.url("/"), self.url(PORTAL_PATH))
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Logs the tests in as [`USERNAME`], through the account rather than the
+/// environment so that tests running in parallel do not race on it.
+pub fn set_credentials() {
+    lib::credentials::set_current(Some(lib::credentials::Credentials::new(USERNAME, PASSWORD)));
+}
+
+/// Writes `body` at `relative_path` under `root`, creating the directories on the way.
+pub fn write(root: &Path, relative_path: &str, body: impl AsRef<[u8]>) {
+    let path = root.join(relative_path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, body).unwrap();
+}
+
+/// A file of a listing whose remote copy is `body`, expected at `local_path`.
+pub fn listed(remote_path: &str, body: &[u8], local_path: &Path) -> File {
+    File {
+        remote_path: remote_path.to_string(),
+        remote_size: body.len() as u64,
+        remote_md5: format!("{:x}", md5::compute(body)),
+        local_path: local_path.to_str().unwrap().to_string(),
+        ..File::default()
+    }
+}
+
+pub fn fixtures_directory() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Base name of the synthetic Neuroscope session of `fixtures/sessions`.
+pub const SESSION: &str = "synthetic.session";
+
+/// `<SESSION>.<extension>` of the synthetic session, its parameters are made up
+/// and match no real recording.
+pub fn session_fixture(extension: &str) -> PathBuf {
+    fixtures_directory()
+        .join("sessions/synthetic")
+        .join(format!("{SESSION}.{extension}"))
+}
+
+/// Default handler mimicking the layout of crcns.org and the NERSC portal.
+pub fn crcns(request: &Request) -> Response {
+    let portal_directory = PORTAL_PATH.trim_end_matches("index.php");
+
+    if let Some(filepath) = request.path.strip_prefix(portal_directory) {
+        if request.method != "POST" {
+            return Response::new(405, "text/plain", "Method Not Allowed");
+        }
+        let form = request.form();
+        let logged_in = form.get("username").map(|s| s.as_str()) == Some(USERNAME)
+            && form.get("password").map(|s| s.as_str()) == Some(PASSWORD);
+        // Posted credentials are judged on their own, whatever cookie comes along.
+        let posted = form.contains_key("password");
+        if !logged_in && (posted || !has_session(request)) {
+            // The portal answers a rejected login with its login form and a 200.
+            return Response::new(200, "text/html; charset=UTF-8", LOGIN_PAGE);
+        }
+        let response = if filepath == "index.php" {
+            Response::new(200, "text/html; charset=UTF-8", WELCOME_PAGE)
+        } else {
+            match std::fs::read(fixtures_directory().join("portal").join(filepath)) {
+                Ok(body) => ranged(request, body),
+                Err(_) => Response::not_found(),
+            }
+        };
+        return match logged_in {
+            true => response.with_header("Set-Cookie", new_session().as_str()),
+            false => response,
+        };
+    }
+
+    let page = request.path.trim_matches('/');
+    let page = fixtures_directory()
+        .join("catalog")
+        .join(format!("{page}.html"));
+    match std::fs::read(page) {
+        Ok(body) => html_page(request, body),
+        Err(_) => Response::not_found(),
+    }
+}
+
+/// Answers with the HTML `body` and its ETag, or `304` when the request already
+/// holds that ETag.
+pub fn html_page(request: &Request, body: impl Into<Vec<u8>>) -> Response {
+    let body = body.into();
+    let etag = format!("\"{:x}\"", md5::compute(&body));
+    if request.header("if-none-match") == Some(etag.as_str()) {
+        return Response::new(304, "text/html; charset=utf-8", "").with_header("ETag", &etag);
+    }
+    Response::new(200, "text/html; charset=utf-8", body).with_header("ETag", &etag)
+}
+
+static SESSIONS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+static NEXT_SESSION: AtomicU64 = AtomicU64::new(1);
+
+/// A `Set-Cookie` value for a fresh portal session.
+fn new_session() -> String {
+    let token = format!("lens{}", NEXT_SESSION.fetch_add(1, Ordering::Relaxed));
+    SESSIONS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(token.clone());
+    format!("PHPSESSID={token}; Path=/")
+}
+
+fn has_session(request: &Request) -> bool {
+    let Some(cookies) = request.header("cookie") else {
+        return false;
+    };
+    let sessions = SESSIONS.lock().unwrap();
+    cookies
+        .split(';')
+        .filter_map(|c| c.trim().strip_prefix("PHPSESSID="))
+        .any(|token| sessions.as_ref().is_some_and(|s| s.contains(token)))
+}
+
+/// Makes the portal forget every session, as it does when they time out.
+pub fn expire_sessions() {
+    *SESSIONS.lock().unwrap() = None;
+}
+
+/// Answers with `body`, honouring an open-ended `Range: bytes=<offset>-` header.
+pub fn ranged(request: &Request, body: Vec<u8>) -> Response {
+    let offset = request
+        .header("range")
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.strip_suffix('-'))
+        .and_then(|r| r.parse::<usize>().ok());
+
+    match offset {
+        None => Response::new(200, "application/octet-stream", body),
+        Some(offset) if offset >= body.len() => {
+            Response::new(416, "text/plain", "Range Not Satisfiable")
+                .with_header("Content-Range", format!("bytes */{}", body.len()).as_str())
+        }
+        Some(offset) => {
+            let content_range = format!("bytes {}-{}/{}", offset, body.len() - 1, body.len());
+            Response::new(206, "application/octet-stream", body[offset..].to_vec())
+                .with_header("Content-Range", content_range.as_str())
+        }
+    }
+}
+
+pub const LOGIN_PAGE: &str = "<html><head><title>CRCNS - Download</title></head><body>\
+<form method=\"post\" action=\"index.php\">\
+<input type=\"text\" name=\"username\"/><input type=\"password\" name=\"password\"/>\
+<input type=\"submit\" name=\"submit\" value=\"Login\"/></form></body></html>";
+
+pub const WELCOME_PAGE: &str = "<html><head><title>CRCNS - Download</title></head><body>\
+<p>Welcome lens. Select a data set to download.</p></body></html>";
+
+async fn serve(mut stream: TcpStream, handler: Handler, requests: Arc<Mutex<Vec<Request>>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    requests.lock().unwrap().push(request.clone());
+
+    let response = handler(&request);
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in response.headers.iter() {
+        head.push_str(format!("{name}: {value}\r\n").as_str());
+    }
+    head.push_str(format!("Content-Length: {}\r\n", response.body.len()).as_str());
+    head.push_str("Connection: close\r\n\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    if request.method != "HEAD" {
+        let _ = stream.write_all(&response.body).await;
+    }
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let head_end = loop {
+        if let Some(i) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let path = path.split('?').next()?.to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let mut body = buffer[head_end + 4..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        304 => "Not Modified",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::FixtureServer;
+use lib::credentials::{CredentialStore, Credentials};
+use lib::net::{login, Error};
+
+#[test]
+fn file_store_round_trips() {
+    let directory = tempfile::tempdir().unwrap();
+    let store = CredentialStore::File(directory.path().join("lens/credentials"));
+    let credentials = Credentials::new("lens", "pass\nword with spaces");
+
+    assert_eq!(store.load().unwrap(), None);
+    store.save(&credentials).unwrap();
+    assert_eq!(store.load().unwrap(), Some(credentials));
+
+    store.delete().unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    // Logging out twice is not an error.
+    store.delete().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn credentials_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("credentials");
+    let store = CredentialStore::File(filepath.clone());
+    store.save(&Credentials::new("lens", "secret")).unwrap();
+
+    let mode = std::fs::metadata(&filepath).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // A file left readable by others is tightened on the next save.
+    std::fs::set_permissions(&filepath, std::fs::Permissions::from_mode(0o644)).unwrap();
+    store.save(&Credentials::new("lens", "secret")).unwrap();
+    let mode = std::fs::metadata(&filepath).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn malformed_credentials_file_is_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("credentials");
+    std::fs::write(&filepath, "only a username").unwrap();
+
+    let result = CredentialStore::File(filepath).load();
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn debug_output_hides_the_password() {
+    let debug = format!("{:?}", Credentials::new(" lens ", "hunter2"));
+
+    assert!(debug.contains("\"lens\""));
+    assert!(!debug.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn login_accepts_valid_credentials() {
+    let server = FixtureServer::start().await;
+
+    let credentials = Credentials::new(common::USERNAME, common::PASSWORD);
+    login(&server.endpoints(), &credentials).await.unwrap();
+
+    let requests = server.requests_to(common::PORTAL_PATH);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].form().get("fn"), None);
+}
+
+#[tokio::test]
+async fn login_rejects_invalid_credentials() {
+    let server = FixtureServer::start().await;
+
+    let credentials = Credentials::new(common::USERNAME, "wrong");
+    let result = login(&server.endpoints(), &credentials).await;
+
+    match result {
+        Err(Error::Auth(reason)) => assert!(!reason.contains("wrong")),
+        other => panic!("expected an auth error, got {other:?}"),
+    }
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use lib::types::DatasetMetadata;
+
+const GB: u64 = 1 << 30;
+
+/// Metadata of a page of `fixtures/metadata`. These pages are synthetic, each one
+/// labels its fields in a single way.
+fn metadata(page: &str) -> DatasetMetadata {
+    let path = common::fixtures_directory().join("metadata").join(page);
+    DatasetMetadata::from_html(std::fs::read_to_string(path).unwrap().as_str())
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn sections_under_headings_are_read() {
+    let headings = metadata("headings.html");
+
+    assert_eq!(headings.species, strings(&["Rat"]));
+    assert_eq!(
+        headings.brain_regions,
+        strings(&[
+            "CA1",
+            "CA3",
+            "Dentate gyrus",
+            "Entorhinal cortex",
+            "Hippocampus"
+        ])
+    );
+    assert_eq!(
+        headings.techniques,
+        strings(&[
+            "Tetrodes",
+            "Silicon probes",
+            "Extracellular recording",
+            "LFP"
+        ])
+    );
+    assert_eq!(headings.subjects, Some(11));
+    assert_eq!(headings.sessions, Some(442));
+    assert_eq!(
+        headings.formats,
+        strings(&["RES", "CLU", "SPK", "FET", "EEG", "WHL", "XML"])
+    );
+    assert_eq!(headings.total_size, Some(433 * GB));
+    assert_eq!(headings.publications.len(), 2);
+    assert!(headings.publications[0].starts_with("Author A, Author B"));
+    assert_eq!(
+        headings.contributors,
+        strings(&["Alice Author", "Bob Author", "Carol Author", "Dan Author"])
+    );
+    assert_eq!(headings.funding, strings(&["Grant 0001 and 0002."]));
+}
+
+#[test]
+fn table_headers_label_their_cells() {
+    let table = metadata("table.html");
+
+    assert_eq!(table.species, strings(&["Macaque"]));
+    assert_eq!(table.brain_regions, strings(&["V1"]));
+    assert_eq!(table.techniques, strings(&["Extracellular recording"]));
+    assert_eq!(table.subjects, Some(4));
+    assert_eq!(table.sessions, None);
+    assert_eq!(table.formats, strings(&["MAT"]));
+    assert_eq!(table.total_size, Some((1.2 * GB as f64) as u64));
+    assert_eq!(table.contributors, strings(&["Author A", "Author B"]));
+    assert!(table.publications.is_empty());
+}
+
+#[test]
+fn definition_lists_and_number_words_are_read() {
+    let definitions = metadata("definitions.html");
+
+    assert_eq!(definitions.species, strings(&["Cat"]));
+    assert_eq!(definitions.sessions, Some(12));
+    assert_eq!(definitions.subjects, Some(4));
+    assert_eq!(definitions.techniques, strings(&["Utah array"]));
+    assert_eq!(definitions.formats, strings(&["HDF5", "Text"]));
+    assert_eq!(definitions.total_size, Some(850 << 20));
+    assert_eq!(definitions.funding, strings(&["Grant 0003"]));
+}
+
+#[test]
+fn free_text_is_searched_when_nothing_is_labelled() {
+    let free_text = metadata("free-text.html");
+
+    assert_eq!(free_text.species, strings(&["Mouse"]));
+    assert_eq!(free_text.brain_regions, strings(&["Barrel cortex"]));
+    assert_eq!(
+        free_text.techniques,
+        strings(&["Juxtacellular recording", "Patch clamp"])
+    );
+    assert_eq!(free_text.subjects, Some(24));
+    assert_eq!(free_text.sessions, Some(87));
+    assert_eq!(free_text.formats, strings(&["MAT"]));
+    assert_eq!(free_text.total_size, Some((3.4 * GB as f64) as u64));
+    assert!(free_text.contributors.is_empty());
+}
+
+#[test]
+fn pages_without_content_give_empty_metadata() {
+    assert_eq!(
+        DatasetMetadata::from_html("<html><body><p>Not found</p></body></html>"),
+        DatasetMetadata::default()
+    );
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use lib::storage::{
+    evict_to_quota, eviction_plan, free_dataset, is_evictable, DiskUsage, StoragePolicy,
+};
+
+/// hc/hc-3 with a listing, parameters and three raw files, hc/hc-2 with one.
+fn data_directory() -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path();
+    std::fs::create_dir_all(data.join("hc/hc-3")).unwrap();
+    std::fs::write(
+        data.join("hc/hc-3/filelist.txt"),
+        "# hc-3 filelist: path size\ns1/s1.xml 10\ns1/s1.eeg 1000\ns1/s1.res.1 200\n",
+    )
+    .unwrap();
+    common::write(data, "hc/hc-3/s1/s1.xml", vec![0u8; 10]);
+    common::write(data, "hc/hc-3/s1/s1.eeg", vec![0u8; 1000]);
+    common::write(data, "hc/hc-3/s1/s1.res.1", vec![0u8; 200]);
+    common::write(data, "hc/hc-3/s1/s1.clu.1.part", vec![0u8; 50]);
+    common::write(data, "hc/hc-2/s2/s2.eeg", vec![0u8; 300]);
+    // Outside of any dataset.
+    common::write(data, "catalog.bin", vec![0u8; 5]);
+    directory
+}
+
+fn policy(quota: u64) -> StoragePolicy {
+    let mut policy = StoragePolicy {
+        quota: Some(quota),
+        ..StoragePolicy::default()
+    };
+    policy.touch("hc/hc-2/s2/s2.eeg", 10);
+    policy.touch("hc/hc-3/s1/s1.eeg", 20);
+    policy.touch("hc/hc-3/s1/s1.res.1", 30);
+    policy
+}
+
+#[test]
+fn scan_totals_local_and_listed_bytes() {
+    let directory = data_directory();
+    let usage = DiskUsage::scan(directory.path()).unwrap();
+
+    let hc3 = usage.dataset("hc", "hc-3").unwrap();
+    assert_eq!(
+        hc3.local_bytes,
+        10 + 1000 + 200 + 50 + hc3_filelist_len(&directory)
+    );
+    assert_eq!(hc3.remote_bytes, Some(1210));
+    assert_eq!(usage.dataset("hc", "hc-2").unwrap().remote_bytes, None);
+
+    assert_eq!(usage.local_bytes(), hc3.local_bytes + 300);
+    assert_eq!(usage.collection("hc"), (usage.local_bytes(), 1210));
+    assert_eq!(usage.file("hc/hc-3/s1/s1.eeg").unwrap().size, 1000);
+    assert!(usage.file("catalog.bin").is_none());
+}
+
+#[test]
+fn pins_cover_files_and_directories() {
+    let mut policy = StoragePolicy::default();
+    policy.set_pinned("hc/hc-3", true);
+    policy.set_pinned("hc/hc-2/s2/s2.eeg", true);
+
+    assert!(policy.is_pinned("hc/hc-3/s1/s1.eeg"));
+    assert!(policy.is_pinned("hc/hc-2/s2/s2.eeg"));
+    assert!(!policy.is_pinned("hc/hc-30/s1.eeg"));
+
+    policy.set_pinned("hc/hc-3", false);
+    assert!(!policy.is_pinned("hc/hc-3/s1/s1.eeg"));
+}
+
+#[test]
+fn plan_evicts_least_recently_used_until_under_quota() {
+    let directory = data_directory();
+    let usage = DiskUsage::scan(directory.path()).unwrap();
+    let quota = usage.local_bytes() - 400;
+
+    let plan = eviction_plan(&usage, &policy(quota), &[]);
+    let paths: Vec<&str> = plan.iter().map(|f| f.relative_path.as_str()).collect();
+    assert_eq!(paths, vec!["hc/hc-2/s2/s2.eeg", "hc/hc-3/s1/s1.eeg"]);
+
+    let keep = vec!["hc/hc-3/s1/s1.eeg".to_string()];
+    let plan = eviction_plan(&usage, &policy(quota), &keep);
+    let paths: Vec<&str> = plan.iter().map(|f| f.relative_path.as_str()).collect();
+    assert_eq!(paths, vec!["hc/hc-2/s2/s2.eeg", "hc/hc-3/s1/s1.res.1"]);
+
+    assert!(eviction_plan(&usage, &policy(usage.local_bytes()), &[]).is_empty());
+    assert!(eviction_plan(&usage, &StoragePolicy::default(), &[]).is_empty());
+}
+
+#[test]
+fn metadata_partial_and_pinned_files_are_never_evicted() {
+    let directory = data_directory();
+    let usage = DiskUsage::scan(directory.path()).unwrap();
+    let mut policy = policy(0);
+    policy.set_pinned("hc/hc-2", true);
+
+    let evicted = evict_to_quota(&usage, &mut policy, &[]).unwrap();
+    let paths: Vec<&str> = evicted.iter().map(|f| f.relative_path.as_str()).collect();
+    assert_eq!(paths, vec!["hc/hc-3/s1/s1.eeg", "hc/hc-3/s1/s1.res.1"]);
+
+    let data = directory.path();
+    assert!(!data.join("hc/hc-3/s1/s1.eeg").exists());
+    assert!(data.join("hc/hc-3/s1/s1.xml").exists());
+    assert!(data.join("hc/hc-3/filelist.txt").exists());
+    assert!(data.join("hc/hc-3/s1/s1.clu.1.part").exists());
+    assert!(data.join("hc/hc-2/s2/s2.eeg").exists());
+    assert!(!policy.last_used.contains_key("hc/hc-3/s1/s1.eeg"));
+
+    let rescanned = DiskUsage::scan(data).unwrap();
+    assert!(rescanned
+        .datasets
+        .iter()
+        .flat_map(|d| d.files.iter())
+        .all(|f| !is_evictable(f, &policy)));
+}
+
+#[test]
+fn free_dataset_keeps_listings_and_parameters() {
+    let directory = data_directory();
+    let usage = DiskUsage::scan(directory.path()).unwrap();
+    let mut policy = StoragePolicy::default();
+
+    let freed = free_dataset(usage.dataset("hc", "hc-3").unwrap(), &mut policy).unwrap();
+    assert_eq!(freed, 1200);
+
+    let usage = DiskUsage::scan(directory.path()).unwrap();
+    let remaining: Vec<&str> = usage
+        .dataset("hc", "hc-3")
+        .unwrap()
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(
+        remaining,
+        vec![
+            "hc/hc-3/filelist.txt",
+            "hc/hc-3/s1/s1.clu.1.part",
+            "hc/hc-3/s1/s1.xml"
+        ]
+    );
+    assert_eq!(usage.dataset("hc", "hc-2").unwrap().local_bytes, 300);
+}
+
+#[test]
+fn policy_round_trips_and_rejects_corrupt_files() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("storage.bin");
+    assert_eq!(
+        StoragePolicy::load(&filepath).unwrap(),
+        StoragePolicy::default()
+    );
+
+    let mut policy = policy(1 << 30);
+    policy.set_pinned("hc/hc-3", true);
+    policy.save(&filepath).unwrap();
+    assert_eq!(StoragePolicy::load(&filepath).unwrap(), policy);
+
+    std::fs::write(&filepath, b"not a policy").unwrap();
+    let error = StoragePolicy::load(&filepath).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn corrupt_policy_is_set_aside_instead_of_overwritten() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("storage.bin");
+    let backup = directory.path().join("storage.bin.bak");
+
+    let (policy, message) = StoragePolicy::load_or_set_aside(&filepath);
+    assert_eq!((policy, message), (StoragePolicy::default(), None));
+
+    std::fs::write(&filepath, b"not a policy").unwrap();
+    let (policy, message) = StoragePolicy::load_or_set_aside(&filepath);
+    assert_eq!(policy, StoragePolicy::default());
+    assert!(message.unwrap().contains("storage.bin.bak"));
+    assert!(!filepath.exists());
+
+    policy.save(&filepath).unwrap();
+    assert_eq!(std::fs::read(&backup).unwrap(), b"not a policy");
+}
+
+fn hc3_filelist_len(directory: &tempfile::TempDir) -> u64 {
+    std::fs::metadata(directory.path().join("hc/hc-3/filelist.txt"))
+        .unwrap()
+        .len()
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use lib::downloads::{DownloadManager, DownloadPlan, JobState, PlanRules};
+use lib::types::{File, FileStatus};
+
+fn file(remote_path: &str, remote_size: u64) -> File {
+    File {
+        remote_path: remote_path.to_string(),
+        remote_size,
+        local_path: format!("/tmp/data/hc/hc-3/{remote_path}"),
+        ..File::default()
+    }
+}
+
+fn hc3() -> Vec<File> {
+    vec![
+        file("ec012ec.11/ec012ec.187/ec012ec.187.xml", 10),
+        file("ec012ec.11/ec012ec.187/ec012ec.187.dat", 9000),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.xml", 10),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.eeg", 500),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.dat", 8000),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.res.1", 40),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.clu.1", 20),
+        file("ec012ec.11/ec012ec.188/ec012ec.188.whl", 30),
+        file("ec012ec.11/ec012ec.188.tar.gz", 20000),
+    ]
+}
+
+fn paths(files: &[File]) -> Vec<&str> {
+    files
+        .iter()
+        .map(|f| f.remote_path.rsplit('/').next().unwrap())
+        .collect()
+}
+
+#[test]
+fn include_and_exclude_patterns_select_files() {
+    let rules = PlanRules::parse("ec012ec.11/ec012ec.188/* *.xml", "*.dat, *.whl", 0);
+    let plan = DownloadPlan::build(&hc3(), &rules).unwrap();
+
+    assert_eq!(
+        paths(&plan.selected),
+        vec![
+            "ec012ec.187.xml",
+            "ec012ec.188.xml",
+            "ec012ec.188.eeg",
+            "ec012ec.188.res.1",
+            "ec012ec.188.clu.1"
+        ]
+    );
+    assert_eq!(plan.bytes(), 580);
+    assert!(plan.over_budget.is_empty());
+}
+
+#[test]
+fn no_include_pattern_selects_everything() {
+    let plan = DownloadPlan::build(&hc3(), &PlanRules::default()).unwrap();
+    assert_eq!(plan.selected.len(), 9);
+}
+
+#[test]
+fn budget_skips_what_no_longer_fits() {
+    let rules = PlanRules::parse("*.xml *.eeg *.res.* *.clu.* *.dat", "", 9000);
+    let plan = DownloadPlan::build(&hc3(), &rules).unwrap();
+
+    // The first .dat would take the whole budget, the second one still fits.
+    assert_eq!(
+        paths(&plan.selected),
+        vec![
+            "ec012ec.187.xml",
+            "ec012ec.188.xml",
+            "ec012ec.188.eeg",
+            "ec012ec.188.dat",
+            "ec012ec.188.res.1",
+            "ec012ec.188.clu.1"
+        ]
+    );
+    assert_eq!(paths(&plan.over_budget), vec!["ec012ec.187.dat"]);
+    assert_eq!(plan.bytes(), 8580);
+}
+
+#[test]
+fn intact_files_are_not_planned_again() {
+    let mut files = hc3();
+    files[0].status = FileStatus::Ok;
+    files[2].status = FileStatus::Mismatch;
+
+    let plan = DownloadPlan::build(&files, &PlanRules::parse("*.xml", "", 0)).unwrap();
+    assert_eq!(paths(&plan.selected), vec!["ec012ec.188.xml"]);
+    assert_eq!(plan.present, 1);
+}
+
+#[test]
+fn estimate_rounds_up_to_the_second() {
+    let plan = DownloadPlan::build(&hc3(), &PlanRules::parse("*.eeg *.whl", "", 0)).unwrap();
+    assert_eq!(plan.estimated_duration(100), Duration::from_secs(6));
+    assert_eq!(plan.estimated_duration(0), Duration::from_secs(530));
+}
+
+#[test]
+fn invalid_patterns_are_errors() {
+    let rules = PlanRules::parse("ec012ec.{188", "", 0);
+    assert!(DownloadPlan::build(&hc3(), &rules).is_err());
+}
+
+#[test]
+fn only_the_selection_is_queued() {
+    let rules = PlanRules::parse("*.res.* *.clu.*", "", 0);
+    let plan = DownloadPlan::build(&hc3(), &rules).unwrap();
+    let manager = DownloadManager::default();
+
+    let ids = plan.enqueue(&manager, "hc", "hc-3");
+
+    let jobs = manager.jobs();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(jobs.len(), 2);
+    assert!(jobs.iter().all(|j| j.state == JobState::Queued));
+    assert_eq!(
+        jobs.iter()
+            .map(|j| j.remote_path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "ec012ec.11/ec012ec.188/ec012ec.188.res.1",
+            "ec012ec.11/ec012ec.188/ec012ec.188.clu.1"
+        ]
+    );
+    assert_eq!(jobs[0].remote_size, 40);
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::{FixtureServer, Response};
+use lib::downloads::{DownloadConfig, DownloadManager, Job, JobState};
+use lib::files::part_path;
+use lib::net::Bandwidth;
+
+const BODY: &[u8] = b"spikes and local field potentials\n";
+
+/// Serves `BODY` for every file except those whose path contains `missing`.
+async fn server() -> FixtureServer {
+    FixtureServer::with_handler(Arc::new(|request| {
+        if request.path.contains("missing") {
+            Response::not_found()
+        } else {
+            common::ranged(request, BODY.to_vec())
+        }
+    }))
+    .await
+}
+
+fn job(directory: &std::path::Path, remote_path: &str) -> Job {
+    Job::new("hc", "hc-3", remote_path, directory.join(remote_path))
+}
+
+fn portal_file(remote_path: &str) -> String {
+    format!("/project/crcns/download/hc-3/{remote_path}")
+}
+
+fn single_worker() -> DownloadManager {
+    let manager = DownloadManager::default();
+    manager.set_config(DownloadConfig {
+        max_concurrent: 1,
+        max_bytes_per_second: None,
+    });
+    manager
+}
+
+#[tokio::test]
+async fn jobs_run_by_priority_then_queue_order() {
+    common::set_credentials();
+    let server = server().await;
+    let directory = tempfile::tempdir().unwrap();
+    let manager = single_worker();
+
+    let low = manager.enqueue(job(directory.path(), "low.dat"));
+    let first = manager.enqueue(job(directory.path(), "first.dat").with_priority(5));
+    let second = manager.enqueue(job(directory.path(), "second.dat").with_priority(5));
+    manager.start(server.endpoints());
+
+    for id in [low, first, second] {
+        manager.wait(id).await.unwrap();
+    }
+
+    let order: Vec<String> = server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r.path.clone())
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            portal_file("first.dat"),
+            portal_file("second.dat"),
+            portal_file("low.dat")
+        ]
+    );
+    assert_eq!(
+        std::fs::read(directory.path().join("low.dat")).unwrap(),
+        BODY
+    );
+}
+
+#[tokio::test]
+async fn the_same_file_is_queued_once() {
+    let directory = tempfile::tempdir().unwrap();
+    let manager = DownloadManager::default();
+
+    let id = manager.enqueue(job(directory.path(), "a.dat"));
+    let again = manager.enqueue(job(directory.path(), "a.dat").with_priority(3));
+
+    assert_eq!(id, again);
+    assert_eq!(manager.jobs().len(), 1);
+    assert_eq!(manager.job(id).unwrap().priority, 3);
+}
+
+#[tokio::test]
+async fn paused_jobs_wait_until_resumed() {
+    common::set_credentials();
+    let server = server().await;
+    let directory = tempfile::tempdir().unwrap();
+    let manager = single_worker();
+
+    let paused = manager.enqueue(job(directory.path(), "paused.dat").with_priority(1));
+    let other = manager.enqueue(job(directory.path(), "other.dat"));
+    manager.pause(paused);
+    manager.start(server.endpoints());
+
+    manager.wait(other).await.unwrap();
+    assert_eq!(manager.job(paused).unwrap().state, JobState::Paused);
+    assert!(server.requests_to(&portal_file("paused.dat")).is_empty());
+
+    manager.resume(paused);
+    manager.wait(paused).await.unwrap();
+    assert!(directory.path().join("paused.dat").exists());
+}
+
+#[tokio::test]
+async fn cancelling_discards_the_part() {
+    let directory = tempfile::tempdir().unwrap();
+    let manager = DownloadManager::default();
+    let local = directory.path().join("a.dat");
+    std::fs::write(part_path(&local), &BODY[..4]).unwrap();
+
+    let id = manager.enqueue(job(directory.path(), "a.dat"));
+    assert_eq!(manager.job(id).unwrap().downloaded, 4);
+
+    manager.cancel(id);
+
+    assert!(manager.job(id).is_none());
+    assert!(!part_path(&local).exists());
+    assert!(manager.wait(id).await.is_err());
+}
+
+#[tokio::test]
+async fn failed_jobs_can_be_retried() {
+    common::set_credentials();
+    let server = server().await;
+    let directory = tempfile::tempdir().unwrap();
+    let manager = DownloadManager::default();
+    manager.start(server.endpoints());
+
+    let id = manager.enqueue(job(directory.path(), "missing.dat"));
+    assert!(manager.wait(id).await.is_err());
+    assert!(matches!(
+        manager.job(id).unwrap().state,
+        JobState::Failed(_)
+    ));
+
+    manager.retry(id);
+    assert!(manager.wait(id).await.is_err());
+    assert_eq!(server.requests_to(&portal_file("missing.dat")).len(), 2);
+}
+
+#[tokio::test]
+async fn the_queue_survives_a_restart() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("downloads.bin");
+
+    let manager = DownloadManager::load(filepath.clone());
+    manager.set_config(DownloadConfig {
+        max_concurrent: 3,
+        max_bytes_per_second: Some(4096),
+    });
+    let paused = manager.enqueue(job(directory.path(), "paused.dat"));
+    let queued = manager.enqueue(job(directory.path(), "queued.dat").with_priority(2));
+    manager.pause(paused);
+    drop(manager);
+
+    let restored = DownloadManager::load(filepath);
+    assert_eq!(restored.config().max_concurrent, 3);
+    assert_eq!(restored.config().max_bytes_per_second, Some(4096));
+    assert_eq!(restored.job(paused).unwrap().state, JobState::Paused);
+    assert_eq!(restored.job(queued).unwrap().state, JobState::Queued);
+    assert_eq!(restored.job(queued).unwrap().priority, 2);
+
+    // Ids keep growing across restarts.
+    let next = restored.enqueue(job(directory.path(), "next.dat"));
+    assert!(next > queued);
+}
+
+#[tokio::test]
+async fn stopped_jobs_stay_queued_for_the_next_start() {
+    common::set_credentials();
+    let server = server().await;
+    let directory = tempfile::tempdir().unwrap();
+    let manager = single_worker();
+    manager.set_config(DownloadConfig {
+        max_concurrent: 1,
+        max_bytes_per_second: Some(8),
+    });
+
+    let id = manager.enqueue(job(directory.path(), "slow.dat"));
+    manager.start(server.endpoints());
+    while manager.job(id).unwrap().state != JobState::Running {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    manager.stop();
+    while manager.job(id).unwrap().state == JobState::Running {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(manager.job(id).unwrap().state, JobState::Queued);
+    assert!(!directory.path().join("slow.dat").exists());
+
+    manager.set_config(DownloadConfig {
+        max_concurrent: 1,
+        max_bytes_per_second: None,
+    });
+    manager.start(server.endpoints());
+    manager.wait(id).await.unwrap();
+    assert_eq!(
+        std::fs::read(directory.path().join("slow.dat")).unwrap(),
+        BODY
+    );
+}
+
+#[tokio::test]
+async fn bandwidth_spreads_chunks_over_time() {
+    let bandwidth = Bandwidth::new(Some(10_000));
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        bandwidth.consume(1_000).await;
+    }
+
+    // The third chunk may only start once the first two had their 100ms each.
+    assert!(start.elapsed() >= Duration::from_millis(190));
+}
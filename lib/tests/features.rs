@@ -0,0 +1,103 @@
+mod common;
+
+use std::path::Path;
+
+use lib::recording::{
+    parse_clu, parse_fet, Error, Features, SessionParameters, SpikeGroup, TIME_COLUMN,
+};
+
+fn shank_1() -> (SpikeGroup, Features) {
+    let parameters = SessionParameters::read(&common::session_fixture("xml")).unwrap();
+    let group = parameters.spike_group(1).unwrap().clone();
+    let features = Features::read(&common::session_fixture("fet.1"), &group).unwrap();
+    (group, features)
+}
+
+#[test]
+fn columns_are_named_after_channels_and_components() {
+    let (_, features) = shank_1();
+
+    assert_eq!(features.n_spikes(), 6);
+    assert_eq!(features.columns.len(), 14);
+    assert_eq!(
+        &features.columns[..4],
+        ["ch0_pc1", "ch0_pc2", "ch0_pc3", "ch1_pc1"]
+    );
+    assert_eq!(features.columns[11], "ch3_pc3");
+    assert_eq!(features.columns[12], "extra1");
+    assert_eq!(features.columns[13], TIME_COLUMN);
+
+    assert_eq!(
+        features.column("ch1_pc2").unwrap().to_vec(),
+        vec![14, 24, 34, 44, 54, 64]
+    );
+    // Spike times are the .res samples.
+    let res = std::fs::read_to_string(common::session_fixture("res.1")).unwrap();
+    let times: Vec<i64> = res.lines().map(|l| l.parse().unwrap()).collect();
+    assert_eq!(features.column(TIME_COLUMN).unwrap().to_vec(), times);
+    assert_eq!(features.clusters, None);
+}
+
+#[test]
+fn clusters_are_joined_by_spike() {
+    let (_, features) = shank_1();
+    let clu = common::session_fixture("clu.1");
+    let clusters = parse_clu(&std::fs::read_to_string(&clu).unwrap(), &clu).unwrap();
+    let features = features.with_clusters(clusters).unwrap();
+
+    let scatter = features.scatter(0, 13);
+    assert_eq!(
+        scatter.iter().map(|(c, _)| *c).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    let (_, cluster_3) = &scatter[3];
+    assert_eq!(cluster_3, &vec![[30.0, 20000.0], [60.0, 40000.0]]);
+    assert!(features.scatter(0, 14).is_empty());
+
+    let frame = features.to_dataframe().unwrap();
+    assert_eq!(frame.shape(), (6, 15));
+    assert_eq!(
+        frame.get_column_names()[..2].to_vec(),
+        vec!["ch0_pc1", "ch0_pc2"]
+    );
+    assert_eq!(frame.get_column_names()[14], "cluster");
+
+    assert!(shank_1().1.with_clusters(vec![2, 3]).is_err());
+    let (_, mut unclustered) = shank_1();
+    assert!(unclustered.set_clusters(vec![2, 3]).is_err());
+    assert_eq!(unclustered, shank_1().1);
+}
+
+#[test]
+fn unclustered_features_share_one_group() {
+    let (_, features) = shank_1();
+    let scatter = features.scatter(1, 2);
+    assert_eq!(scatter.len(), 1);
+    assert_eq!(scatter[0].1.len(), 6);
+}
+
+#[test]
+fn malformed_files_are_parse_errors() {
+    let (group, _) = shank_1();
+    let path = Path::new("s.fet.1");
+    let row = |n: usize| vec!["1"; n].join(" ");
+
+    for (content, reason) in [
+        (String::new(), "no number of features"),
+        ("x\n".to_string(), "not a number of features"),
+        ("4\n1 2 3 4\n".to_string(), "12 principal components"),
+        (
+            format!("13\n{}\n{}\n", row(13), row(12)),
+            "line 3: 12 values",
+        ),
+        (format!("13\n{} x\n", row(12)), "line 2: \"x\""),
+    ] {
+        let error = parse_fet(&content, path, &group).unwrap_err();
+        assert!(matches!(error, Error::Parse { .. }));
+        assert!(error.to_string().contains(reason), "{error}");
+    }
+
+    // Without extra features, the time follows the last component.
+    let features = parse_fet(&format!("13\n{}\n", row(13)), path, &group).unwrap();
+    assert_eq!(features.columns[12], TIME_COLUMN);
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use lib::files::{normalize_path, parse_checksums, parse_filelist, ListedFile, Listing};
+
+fn listings() -> std::path::PathBuf {
+    common::fixtures_directory().join("listings")
+}
+
+#[test]
+fn portal_listing_joins_every_file() {
+    let listing = Listing::read(&common::fixtures_directory().join("portal/synthetic")).unwrap();
+
+    assert!(listing.is_consistent());
+    assert_eq!(listing.files.len(), 5);
+    assert_eq!(
+        listing.files[0],
+        ListedFile {
+            path: "s1.tar.gz".to_string(),
+            size: Some(1536000000),
+            md5: Some("c4ca4238a0b923820dcc509a6f75849b".to_string()),
+        }
+    );
+    let eeg = listing
+        .files
+        .iter()
+        .find(|f| f.path.ends_with(".eeg"))
+        .unwrap();
+    assert_eq!(eeg.size, Some(277200000));
+    assert_eq!(eeg.md5.as_deref(), Some("54c8e1b7e0a8d0f6b0b7e7f3a2f8c6d1"));
+}
+
+#[test]
+fn messy_listing_is_joined_by_path_not_by_line() {
+    let listing = Listing::read(&listings().join("mixed-formats")).unwrap();
+
+    let paths: Vec<&str> = listing.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "data/rat 1/session_01/spikes.mat",
+            "data/rat 1/session_02/spikes.mat",
+            "data/rat 2/session_01/readme.txt",
+            "data/rat 2/session_01/spikes.mat",
+            "data/rat 2/session_01/whisker.avi",
+            "docs/description.pdf",
+        ]
+    );
+
+    let md5 = |path: &str| {
+        listing
+            .files
+            .iter()
+            .find(|f| f.path == path)
+            .and_then(|f| f.md5.clone())
+    };
+    assert_eq!(
+        md5("data/rat 1/session_01/spikes.mat").as_deref(),
+        Some("5d41402abc4b2a76b9719d911017c592")
+    );
+    assert_eq!(
+        md5("data/rat 1/session_02/spikes.mat").as_deref(),
+        Some("7d793037a0760186574b0282f2f435e7")
+    );
+    assert_eq!(
+        md5("data/rat 2/session_01/spikes.mat").as_deref(),
+        Some("a3f1c0e2b7d94a6f8e5c2b1d0f9e8a7c")
+    );
+
+    assert!(!listing.is_consistent());
+    assert_eq!(
+        listing.only_in_filelist,
+        vec!["data/rat 2/session_01/whisker.avi"]
+    );
+    assert_eq!(
+        listing.only_in_checksums,
+        vec!["data/rat 2/session_01/readme.txt"]
+    );
+    let readme = &listing.files[2];
+    assert_eq!(readme.size, None);
+}
+
+#[test]
+fn filelist_accepts_size_first_and_skips_lines_without_size() {
+    let filelist = parse_filelist("# size path\n12 a/b.dat\nc.dat 3\n", "inline").unwrap();
+    assert_eq!(filelist.sizes.get("a/b.dat"), Some(&12));
+    assert_eq!(filelist.sizes.get("c.dat"), Some(&3));
+    assert!(filelist.skipped.is_empty());
+
+    let filelist = parse_filelist("a.dat 1\nb.dat twelve\nc.dat\n", "inline").unwrap();
+    assert_eq!(filelist.sizes.len(), 1);
+    assert_eq!(filelist.sizes.get("a.dat"), Some(&1));
+    assert_eq!(
+        filelist.skipped,
+        vec!["line 2: b.dat twelve", "line 3: c.dat"]
+    );
+}
+
+#[test]
+fn a_line_without_size_does_not_drop_the_listing() {
+    let directory = tempfile::tempdir().unwrap();
+    common::write(directory.path(), "filelist.txt", "a.dat 1\nb.dat\n");
+    common::write(
+        directory.path(),
+        "checksums.md5",
+        "0cc175b9c0f1b6a831c399e269772661  a.dat\n",
+    );
+
+    let listing = Listing::read(directory.path()).unwrap();
+
+    assert!(listing.is_consistent());
+    assert_eq!(listing.files.len(), 1);
+    assert_eq!(listing.skipped, vec!["line 2: b.dat"]);
+}
+
+#[test]
+fn checksums_reject_malformed_digests() {
+    let error = parse_checksums("0123  short.dat\n", "inline").unwrap_err();
+    assert!(error.to_string().contains("line 1"), "{error}");
+}
+
+#[test]
+fn paths_are_normalized_and_kept_inside_the_dataset() {
+    assert_eq!(
+        normalize_path("./a//b\\c.dat ").as_deref(),
+        Some("a/b/c.dat")
+    );
+    assert_eq!(normalize_path("/a/./b").as_deref(), Some("a/b"));
+    assert_eq!(normalize_path("./"), None);
+    assert_eq!(normalize_path("a/../../etc/passwd"), None);
+
+    let error = parse_filelist("../outside 1\n", "inline").unwrap_err();
+    assert!(error.to_string().contains("invalid path"), "{error}");
+}
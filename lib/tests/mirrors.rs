@@ -0,0 +1,201 @@
+mod common;
+
+use std::path::Path;
+
+use lib::files::{apply_mirrors, reconcile, HashCache};
+use lib::net::Error;
+use lib::settings::{Settings, Workspace};
+use lib::storage::{promote, resolve, Mirrors, VerifiedCopies};
+use lib::types::{File, FileStatus};
+
+const EEG: &[u8] = b"0123456789";
+const RELATIVE: &str = "hc/hc-3/s1/s1.eeg";
+
+fn listed(data_directory: &Path) -> File {
+    common::listed("s1/s1.eeg", EEG, &data_directory.join(RELATIVE))
+}
+
+#[tokio::test]
+async fn readers_only_use_verified_copies() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path().join("data");
+    let (corrupt, intact) = (directory.path().join("a"), directory.path().join("b"));
+    common::write(&corrupt, RELATIVE, b"9876543210");
+    let mirrors = Mirrors::new(vec![corrupt, intact.clone()]);
+    let mut verified = VerifiedCopies::default();
+
+    assert_eq!(resolve(&data, &verified, RELATIVE), None);
+
+    // The first copy is not the listed file, nothing is verified.
+    let mut cache = HashCache::default();
+    let mut reconciliation = reconcile(&data, "hc", "hc-3", &[listed(&data)], &mut cache)
+        .await
+        .unwrap();
+    apply_mirrors(&mut reconciliation, &data, &mirrors, &mut cache)
+        .await
+        .unwrap();
+    verified.update(&data, "hc", "hc-3", &reconciliation.files);
+    assert!(verified.is_empty());
+    assert_eq!(resolve(&data, &verified, RELATIVE), None);
+
+    // The intact one is, once a reconciliation found it.
+    common::write(&intact, RELATIVE, EEG);
+    apply_mirrors(&mut reconciliation, &data, &mirrors, &mut cache)
+        .await
+        .unwrap();
+    verified.update(&data, "hc", "hc-3", &reconciliation.files);
+    assert_eq!(
+        resolve(&data, &verified, RELATIVE),
+        Some(intact.join(RELATIVE))
+    );
+
+    common::write(&data, RELATIVE, EEG);
+    assert_eq!(
+        resolve(&data, &verified, RELATIVE),
+        Some(data.join(RELATIVE))
+    );
+
+    // Reconciling the dataset again forgets copies it no longer points at.
+    reconciliation.files[0].mirror = None;
+    verified.update(&data, "hc", "hc-3", &reconciliation.files);
+    assert!(verified.is_empty());
+}
+
+#[tokio::test]
+async fn corrupt_mirror_copies_are_skipped() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path().join("data");
+    let (first, second) = (directory.path().join("a"), directory.path().join("b"));
+    common::write(&first, RELATIVE, b"9876543210");
+    common::write(&second, RELATIVE, EEG);
+    let mirrors = Mirrors::new(vec![first, second.clone()]);
+
+    let mut cache = HashCache::default();
+    let copy = mirrors
+        .find_intact(RELATIVE, &listed(&data), &mut cache)
+        .await
+        .unwrap();
+
+    assert_eq!(copy, Some(second.join(RELATIVE)));
+    assert_eq!(cache.len(), 2);
+}
+
+#[tokio::test]
+async fn hashes_of_gone_mirror_copies_are_dropped() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path().join("data");
+    let (first, second) = (directory.path().join("a"), directory.path().join("b"));
+    common::write(&first, RELATIVE, b"9876543210");
+    common::write(&second, RELATIVE, EEG);
+    let mirrors = Mirrors::new(vec![first.clone(), second.clone()]);
+    let mut cache = HashCache::default();
+    mirrors
+        .find_intact(RELATIVE, &listed(&data), &mut cache)
+        .await
+        .unwrap();
+
+    cache.retain_mirrored(&mirrors).await;
+    assert_eq!(cache.len(), 2);
+
+    std::fs::remove_file(first.join(RELATIVE)).unwrap();
+    cache.retain_mirrored(&mirrors).await;
+    assert_eq!(cache.len(), 1);
+
+    // Copies of a mirror taken out of the settings are forgotten too.
+    cache.retain_mirrored(&Mirrors::new(vec![first])).await;
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn missing_files_are_served_from_a_mirror() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path().join("data");
+    let mirror = directory.path().join("mirror");
+    common::write(&mirror, RELATIVE, EEG);
+    common::write(&mirror, "hc/hc-3/s1/s1.res.1", b"abc");
+    common::write(&data, "hc/hc-3/s1/s1.res.1", b"xyz");
+
+    let res = common::listed("s1/s1.res.1", b"abc", &data.join("hc/hc-3/s1/s1.res.1"));
+    let files = vec![listed(&data), res];
+
+    let mut cache = HashCache::default();
+    let mut reconciliation = reconcile(&data, "hc", "hc-3", &files, &mut cache)
+        .await
+        .unwrap();
+    assert_eq!(reconciliation.completeness.ok, 0);
+
+    let mirrors = Mirrors::new(vec![mirror.clone()]);
+    apply_mirrors(&mut reconciliation, &data, &mirrors, &mut cache)
+        .await
+        .unwrap();
+
+    let eeg = &reconciliation.files[0];
+    assert_eq!(eeg.status, FileStatus::Ok);
+    assert_eq!(
+        eeg.mirror.as_deref(),
+        Some(mirror.join(RELATIVE).to_str().unwrap())
+    );
+    // A local copy, even a bad one, is not replaced by the mirror's.
+    assert_eq!(reconciliation.files[1].status, FileStatus::Mismatch);
+    assert_eq!(reconciliation.files[1].mirror, None);
+    assert_eq!(reconciliation.completeness.ok, 1);
+}
+
+#[tokio::test]
+async fn promoting_copies_once() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path().join("data");
+    let mirror = directory.path().join("mirror");
+    common::write(&data, RELATIVE, EEG);
+
+    let target = promote(&data.join(RELATIVE), &mirror, RELATIVE)
+        .await
+        .unwrap();
+    assert_eq!(target, mirror.join(RELATIVE));
+    assert_eq!(std::fs::read(&target).unwrap(), EEG);
+    assert!(!mirror.join("hc/hc-3/s1/s1.eeg.part").exists());
+
+    let error = promote(&data.join(RELATIVE), &mirror, RELATIVE)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&error, Error::Io(e) if e.kind() == std::io::ErrorKind::AlreadyExists),
+        "{error}"
+    );
+
+    // A copy of the same size but other content is replaced.
+    common::write(&mirror, RELATIVE, b"9876543210");
+    let target = promote(&data.join(RELATIVE), &mirror, RELATIVE)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), EEG);
+    assert!(!mirror.join("hc/hc-3/s1/s1.eeg.part").exists());
+}
+
+#[test]
+fn mirrors_are_read_from_the_settings() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("settings.toml");
+    std::fs::write(
+        &filepath,
+        "[[workspace]]\nname = \"lab\"\nroot = \"/srv/crcns\"\nmirrors = [\"/shared/crcns/data\"]\n\n\
+         [[workspace]]\nname = \"laptop\"\nroot = \"/home/me/crcns\"\n",
+    )
+    .unwrap();
+
+    let settings = Settings::load_from(&filepath).unwrap();
+    let lab = settings.workspace("lab").unwrap();
+    assert_eq!(
+        lab.mirrors(),
+        Mirrors::new(vec!["/shared/crcns/data".into()])
+    );
+    assert!(settings.workspace("laptop").unwrap().mirrors().is_empty());
+
+    settings.save_to(&filepath).unwrap();
+    let saved = std::fs::read_to_string(&filepath).unwrap();
+    assert_eq!(saved.matches("mirrors").count(), 1);
+    assert_eq!(
+        Workspace::new("lab", "/srv/crcns").with_mirrors(vec!["/shared/crcns/data".into()]),
+        *lab
+    );
+}
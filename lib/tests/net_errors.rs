@@ -0,0 +1,134 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{FixtureServer, Response};
+use lib::net::{get_crcns_file, get_url_html, Error, RetryPolicy};
+use lib::types::{Collection, Dataset, CRCNS};
+
+fn quick_retry(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(4),
+    }
+}
+
+#[test]
+fn backoff_doubles_up_to_the_limit() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(500),
+    };
+    assert_eq!(policy.backoff(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff(2), Duration::from_millis(400));
+    assert_eq!(policy.backoff(3), Duration::from_millis(500));
+    assert_eq!(policy.backoff(40), Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let a = attempts.clone();
+    let server = FixtureServer::with_handler(Arc::new(move |request| {
+        if a.fetch_add(1, Ordering::SeqCst) < 2 {
+            Response::new(503, "text/plain", "busy")
+        } else {
+            common::crcns(request)
+        }
+    }))
+    .await;
+
+    let html = get_url_html(server.url("/sitemap"), &quick_retry(3))
+        .await
+        .unwrap();
+    assert!(html.contains("portal-sitemap"));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn retries_give_up_after_the_limit() {
+    let server =
+        FixtureServer::with_handler(Arc::new(|_| Response::new(503, "text/plain", "busy"))).await;
+
+    let result = get_url_html(server.url("/sitemap"), &quick_retry(2)).await;
+    match result {
+        Err(Error::Status { status, .. }) => assert_eq!(status.as_u16(), 503),
+        other => panic!("expected a status error, got {other:?}"),
+    }
+    assert_eq!(server.requests_to("/sitemap").len(), 3);
+}
+
+#[tokio::test]
+async fn missing_pages_are_not_retried() {
+    let server = FixtureServer::start().await;
+
+    let result = get_url_html(server.url("/data-sets/nope"), &quick_retry(3)).await;
+    match result {
+        Err(e @ Error::Status { .. }) => assert!(!e.is_transient()),
+        other => panic!("expected a status error, got {other:?}"),
+    }
+    assert_eq!(server.requests_to("/data-sets/nope").len(), 1);
+}
+
+#[tokio::test]
+async fn pages_without_expected_structure_are_parse_errors() {
+    let server = FixtureServer::start().await;
+    let endpoints = server.endpoints().with_retry(RetryPolicy::none());
+
+    let collection = Collection::from_url(&endpoints, server.url("/sitemap")).await;
+    assert!(matches!(collection, Err(Error::Parse { .. })));
+
+    let dataset = Dataset::from_url(&endpoints, server.url("/data-sets/hc/hc-3"))
+        .await
+        .unwrap();
+    assert_eq!(dataset.alias, "hc-3");
+}
+
+#[tokio::test]
+async fn a_broken_dataset_page_does_not_stop_the_sync() {
+    let server = FixtureServer::with_handler(Arc::new(|request| {
+        if request.path == "/data-sets/hc/hc-2" {
+            Response::new(500, "text/plain", "boom")
+        } else {
+            common::crcns(request)
+        }
+    }))
+    .await;
+    let endpoints = server.endpoints().with_retry(quick_retry(1));
+    let collections = Arc::new(Mutex::new(Vec::new()));
+
+    CRCNS::get(endpoints, collections.clone()).await.unwrap();
+
+    let collections = collections.lock().unwrap().clone();
+    assert_eq!(collections.len(), 2);
+    let hc = collections.iter().find(|c| c.alias == "hc").unwrap();
+    let datasets = hc.datasets.lock().unwrap().clone();
+    assert_eq!(datasets.len(), 1);
+    assert_eq!(datasets[0].alias, "hc-3");
+}
+
+#[tokio::test]
+async fn an_unreachable_catalog_is_an_error() {
+    let server = FixtureServer::with_handler(Arc::new(|_| Response::not_found())).await;
+    let collections = Arc::new(Mutex::new(Vec::new()));
+
+    let result = CRCNS::get(server.endpoints(), collections.clone()).await;
+    assert!(matches!(result, Err(Error::Status { .. })));
+    assert!(collections.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn portal_rejections_are_auth_errors() {
+    common::set_credentials();
+    let server =
+        FixtureServer::with_handler(Arc::new(|_| Response::new(403, "text/plain", "Forbidden")))
+            .await;
+
+    let result = get_crcns_file(&server.endpoints(), "synthetic/filelist.txt").await;
+    assert!(matches!(result, Err(Error::Auth(_))));
+}
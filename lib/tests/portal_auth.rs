@@ -0,0 +1,111 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FixtureServer, Response};
+use lib::files::part_path;
+use lib::net::{download, get_crcns_file, Error, Expected};
+
+const REMOTE: &str = "synthetic/filelist.txt";
+
+const TERMS_PAGE: &str = "<!DOCTYPE html><html><body><h1>CRCNS</h1>\
+<p>You must accept the data sharing agreement before downloading.</p></body></html>";
+
+async fn serving(content_type: &'static str, body: &'static str) -> FixtureServer {
+    FixtureServer::with_handler(Arc::new(move |_| Response::new(200, content_type, body))).await
+}
+
+/// Sends every file request back to the portal script, which answers with `body`
+/// labelled as data.
+async fn redirecting(body: &'static str) -> FixtureServer {
+    FixtureServer::with_handler(Arc::new(move |request| match request.path.as_str() {
+        common::PORTAL_PATH => Response::new(200, "application/octet-stream", body),
+        _ => Response::new(302, "text/plain", "").with_header("Location", common::PORTAL_PATH),
+    }))
+    .await
+}
+
+#[tokio::test]
+async fn login_page_is_an_auth_error() {
+    common::set_credentials();
+    let server = serving("text/html; charset=UTF-8", common::LOGIN_PAGE).await;
+
+    let result = get_crcns_file(&server.endpoints(), REMOTE).await;
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, Error::Auth(_)));
+    assert!(error.advice().is_some());
+}
+
+#[tokio::test]
+async fn agreement_page_is_a_terms_error() {
+    common::set_credentials();
+    let server = serving("text/html", TERMS_PAGE).await;
+
+    let result = get_crcns_file(&server.endpoints(), REMOTE).await;
+
+    assert!(matches!(result, Err(Error::TermsNotAccepted(_))));
+}
+
+#[tokio::test]
+async fn unexpected_page_is_not_data() {
+    common::set_credentials();
+    let server = serving("text/html", "<html><body>Maintenance</body></html>").await;
+
+    let result = get_crcns_file(&server.endpoints(), REMOTE).await;
+
+    assert!(matches!(result, Err(Error::Parse { .. })));
+}
+
+#[tokio::test]
+async fn pages_are_served_when_asked_for() {
+    common::set_credentials();
+    let server = serving("text/html", "<html><body>Description</body></html>").await;
+
+    let result = get_crcns_file(&server.endpoints(), "synthetic/description.html").await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn mislabelled_login_page_is_not_saved() {
+    common::set_credentials();
+    let server = redirecting(common::LOGIN_PAGE).await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+
+    let result = download(&server.endpoints(), REMOTE, &local, &Expected::default()).await;
+
+    assert!(matches!(result, Err(Error::Auth(_))));
+    assert!(!local.exists());
+    assert!(!part_path(&local).exists());
+}
+
+#[tokio::test]
+async fn rejected_resume_discards_the_part() {
+    common::set_credentials();
+    let server = redirecting(TERMS_PAGE).await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+    std::fs::write(part_path(&local), b"partial listing").unwrap();
+
+    let result = download(&server.endpoints(), REMOTE, &local, &Expected::default()).await;
+
+    assert!(matches!(result, Err(Error::TermsNotAccepted(_))));
+    assert!(!part_path(&local).exists());
+}
+
+#[tokio::test]
+async fn data_starting_like_a_page_is_saved() {
+    common::set_credentials();
+    let server = serving("application/octet-stream", common::LOGIN_PAGE).await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+
+    let size = download(&server.endpoints(), REMOTE, &local, &Expected::default())
+        .await
+        .unwrap();
+
+    assert_eq!(size, common::LOGIN_PAGE.len() as u64);
+    assert_eq!(std::fs::read_to_string(&local).unwrap(), common::LOGIN_PAGE);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::FixtureServer;
+use lib::net::{get_crcns_file, session};
+
+const REMOTE: &str = "synthetic/filelist.txt";
+const PORTAL_FILE: &str = "/project/crcns/download/synthetic/filelist.txt";
+
+fn sent_password(request: &common::Request) -> bool {
+    request.form().contains_key("password")
+}
+
+// One test so that expiring and dropping the shared session cannot race other tests.
+#[tokio::test]
+async fn portal_session_is_reused_and_renewed() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let endpoints = server.endpoints();
+
+    // Credentials go out once, then the session cookie is enough.
+    for _ in 0..3 {
+        let response = get_crcns_file(&endpoints, REMOTE).await.unwrap();
+        assert_eq!(response.status(), 200);
+        response.bytes().await.unwrap();
+    }
+    let requests = server.requests_to(PORTAL_FILE);
+    assert_eq!(requests.len(), 3);
+    assert!(sent_password(&requests[0]));
+    assert!(!sent_password(&requests[1]));
+    assert!(!sent_password(&requests[2]));
+    assert!(requests[1].header("cookie").is_some());
+
+    // An expired session is renewed without surfacing an error.
+    common::expire_sessions();
+    let response = get_crcns_file(&endpoints, REMOTE).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let requests = server.requests_to(PORTAL_FILE);
+    assert_eq!(requests.len(), 5);
+    assert!(!sent_password(&requests[3]));
+    assert!(sent_password(&requests[4]));
+
+    // Logging out drops the cookies, the next request logs in again.
+    session().logout();
+    get_crcns_file(&endpoints, REMOTE).await.unwrap();
+    let requests = server.requests_to(PORTAL_FILE);
+    assert_eq!(requests.len(), 6);
+    assert!(sent_password(&requests[5]));
+    assert!(requests[5].header("cookie").is_none());
+}
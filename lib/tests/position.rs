@@ -0,0 +1,89 @@
+use std::f64::consts::FRAC_PI_2;
+use std::path::Path;
+
+use lib::recording::{interpolate_gaps, parse_whl, PositionSeries, WHL_SAMPLING_RATE};
+
+#[test]
+fn whl_lines_are_read_per_led() {
+    let content = "10 20 12 20\n-1 -1 14 20\n\n11.5 21 -1 -1\n";
+    let leds = parse_whl(content, Path::new("s.whl")).unwrap();
+
+    assert_eq!(
+        leds,
+        vec![
+            vec![Some([10.0, 20.0]), Some([12.0, 20.0])],
+            vec![None, Some([14.0, 20.0])],
+            vec![Some([11.5, 21.0]), None],
+        ]
+    );
+    assert!(parse_whl("1 2 3\n", Path::new("s.whl")).is_err());
+    assert!(parse_whl("1 2 3 4\n1 2\n", Path::new("s.whl")).is_err());
+    assert!(parse_whl("1 2 x 4\n", Path::new("s.whl")).is_err());
+}
+
+#[test]
+fn short_gaps_are_interpolated() {
+    let mut track = vec![
+        Some([0.0, 0.0]),
+        None,
+        None,
+        Some([3.0, 6.0]),
+        None,
+        None,
+        None,
+        Some([0.0, 0.0]),
+        None,
+    ];
+    interpolate_gaps(&mut track, 2);
+
+    assert_eq!(track[1], Some([1.0, 2.0]));
+    assert_eq!(track[2], Some([2.0, 4.0]));
+    // Too long, and not closed at the end.
+    assert!(track[4..7].iter().all(Option::is_none));
+    assert_eq!(track[8], None);
+}
+
+#[test]
+fn head_direction_and_speed_are_derived() {
+    // The first LED ahead of the second one in y, moving 1 unit per sample in x.
+    let leds = (0..4)
+        .map(|i| vec![Some([i as f64, 1.0]), Some([i as f64, 0.0])])
+        .collect();
+    let series = PositionSeries::from_leds(leds, 10.0, 0);
+
+    assert_eq!(series.samples.len(), 4);
+    assert_eq!(series.duration(), 0.4);
+    assert_eq!(series.samples[2].time, 0.2);
+    assert_eq!(series.samples[2].position, Some([2.0, 0.5]));
+    assert_eq!(series.samples[2].head_direction, Some(FRAC_PI_2));
+    for sample in &series.samples {
+        assert!((sample.speed.unwrap() - 10.0).abs() < 1e-9);
+    }
+    assert!((series.max_speed().unwrap() - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn missing_samples_have_no_position() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("s.whl");
+    let mut content = String::from("0 0 0 -1\n");
+    // A gap of a whole second is left as it is.
+    for _ in 0..WHL_SAMPLING_RATE as usize {
+        content.push_str("-1 -1 -1 -1\n");
+    }
+    content.push_str("5 0 -1 -1\n6 0 -1 -1\n");
+    std::fs::write(&filepath, content).unwrap();
+
+    let series = PositionSeries::read(&filepath).unwrap();
+    assert_eq!(series.sampling_rate, WHL_SAMPLING_RATE);
+    assert_eq!(series.samples[0].position, Some([0.0, 0.0]));
+    assert_eq!(series.samples[0].head_direction, None);
+    assert_eq!(series.samples[0].speed, None);
+    assert_eq!(series.samples[1].position, None);
+
+    let last = series.samples.last().unwrap();
+    assert_eq!(last.position, Some([6.0, 0.0]));
+    assert!((last.speed.unwrap() - WHL_SAMPLING_RATE).abs() < 1e-9);
+
+    assert!(PositionSeries::read(&directory.path().join("m.whl")).is_err());
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use std::path::Path;
+
+use lib::files::{file_extension, reconcile, HashCache};
+use lib::types::{File, FileStatus};
+
+const EEG: &[u8] = b"0123456789";
+const RES: &[u8] = b"abc";
+
+/// `remote_path` of hc-3, downloaded under `data_directory`.
+fn listed(data_directory: &Path, remote_path: &str, body: &[u8]) -> File {
+    common::listed(
+        remote_path,
+        body,
+        &data_directory.join("hc/hc-3").join(remote_path),
+    )
+}
+
+fn listing(data_directory: &Path) -> Vec<File> {
+    vec![
+        listed(data_directory, "s1/s1.eeg", EEG),
+        listed(data_directory, "s1/s1.res.1", RES),
+        listed(data_directory, "s1/s1.clu.1", b"12"),
+        listed(data_directory, "s1/s1.fet.1", b"3 4"),
+    ]
+}
+
+#[tokio::test]
+async fn local_fields_and_statuses_are_filled() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path();
+    common::write(data, "hc/hc-3/filelist.txt", b"# listing");
+    common::write(data, "hc/hc-3/s1/s1.eeg", EEG);
+    common::write(data, "hc/hc-3/s1/s1.res.1", b"xyz");
+    common::write(data, "hc/hc-3/s1/s1.clu.1.part", b"1");
+
+    let mut cache = HashCache::default();
+    let reconciliation = reconcile(data, "hc", "hc-3", &listing(data), &mut cache)
+        .await
+        .unwrap();
+
+    let statuses: Vec<FileStatus> = reconciliation.files.iter().map(|f| f.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            FileStatus::Ok,
+            FileStatus::Mismatch,
+            FileStatus::Partial,
+            FileStatus::Missing
+        ]
+    );
+
+    let eeg = &reconciliation.files[0];
+    assert_eq!(eeg.local_size, EEG.len() as u64);
+    assert_eq!(eeg.local_md5, format!("{:x}", md5::compute(EEG)));
+    assert_eq!(eeg.extension, "eeg");
+    assert_eq!(reconciliation.files[1].extension, "res");
+    assert_eq!(reconciliation.files[2].local_size, 0);
+
+    let completeness = reconciliation.completeness;
+    assert_eq!(
+        (
+            completeness.listed,
+            completeness.ok,
+            completeness.mismatched,
+            completeness.partial,
+            completeness.missing
+        ),
+        (4, 1, 1, 1, 1)
+    );
+    assert_eq!(completeness.ok_bytes, EEG.len() as u64);
+    assert!(!completeness.is_complete());
+    assert!(reconciliation.untracked.is_empty());
+}
+
+#[tokio::test]
+async fn unlisted_files_are_untracked() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path();
+    common::write(data, "hc/hc-3/checksums.md5", b"");
+    common::write(data, "hc/hc-3/s1/s1.eeg", EEG);
+    common::write(data, "hc/hc-3/s1/notes.txt", b"mine");
+    common::write(data, "hc/hc-3/s2/s2.eeg.part", b"0");
+    common::write(data, "hc/hc-2/s1/s1.eeg", EEG);
+
+    let files = vec![listed(data, "s1/s1.eeg", EEG)];
+    let reconciliation = reconcile(data, "hc", "hc-3", &files, &mut HashCache::default())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        reconciliation.untracked,
+        vec!["hc/hc-3/s1/notes.txt", "hc/hc-3/s2/s2.eeg.part"]
+    );
+    assert!(reconciliation.completeness.is_complete());
+    assert_eq!(reconciliation.completeness.fraction(), 1.0);
+}
+
+#[tokio::test]
+async fn hashes_are_reused_until_the_file_changes() {
+    let directory = tempfile::tempdir().unwrap();
+    let data = directory.path();
+    common::write(data, "hc/hc-3/s1/s1.eeg", EEG);
+    let files = vec![listed(data, "s1/s1.eeg", EEG)];
+
+    let mut cache = HashCache::default();
+    reconcile(data, "hc", "hc-3", &files, &mut cache)
+        .await
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+
+    let cache_filepath = data.join("hashes.bin");
+    cache.save(&cache_filepath).unwrap();
+    let mut cache = HashCache::load(&cache_filepath).unwrap();
+
+    // Same size and modification time, the cached hash wins over the content.
+    let eeg = data.join("hc/hc-3/s1/s1.eeg");
+    let modified = std::fs::metadata(&eeg).unwrap().modified().unwrap();
+    std::fs::write(&eeg, b"9876543210").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&eeg)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    let reconciliation = reconcile(data, "hc", "hc-3", &files, &mut cache)
+        .await
+        .unwrap();
+    assert_eq!(reconciliation.files[0].status, FileStatus::Ok);
+
+    // A later modification time invalidates it.
+    std::fs::File::options()
+        .write(true)
+        .open(&eeg)
+        .unwrap()
+        .set_modified(modified + std::time::Duration::from_secs(60))
+        .unwrap();
+    let reconciliation = reconcile(data, "hc", "hc-3", &files, &mut cache)
+        .await
+        .unwrap();
+    assert_eq!(reconciliation.files[0].status, FileStatus::Mismatch);
+
+    // Entries of deleted files are dropped.
+    std::fs::remove_file(&eeg).unwrap();
+    reconcile(data, "hc", "hc-3", &files, &mut cache)
+        .await
+        .unwrap();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn extensions_ignore_shank_numbers() {
+    assert_eq!(file_extension("ec012ec.11/ec012ec.188.eeg"), "eeg");
+    assert_eq!(file_extension("ec012ec.188.res.1"), "res");
+    assert_eq!(file_extension("ec012ec.188.spk.12"), "spk");
+    assert_eq!(file_extension("ec012ec.11.tar.gz"), "tar.gz");
+    assert_eq!(file_extension("README.TXT"), "txt");
+    assert_eq!(file_extension("Makefile"), "");
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FixtureServer, Response};
+use lib::files::part_path;
+use lib::net::{download, Error, Expected, RetryPolicy};
+
+const REMOTE: &str = "synthetic/filelist.txt";
+
+fn fixture() -> Vec<u8> {
+    std::fs::read(common::fixtures_directory().join("portal").join(REMOTE)).unwrap()
+}
+
+fn expected(body: &[u8]) -> Expected {
+    Expected {
+        size: Some(body.len() as u64),
+        md5: Some(format!("{:x}", md5::compute(body))),
+    }
+}
+
+#[tokio::test]
+async fn fresh_download_is_moved_into_place() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("hc/hc-3/filelist.txt");
+    let body = fixture();
+
+    let size = download(&server.endpoints(), REMOTE, &local, &expected(&body))
+        .await
+        .unwrap();
+
+    assert_eq!(size, body.len() as u64);
+    assert_eq!(std::fs::read(&local).unwrap(), body);
+    assert!(!part_path(&local).exists());
+}
+
+#[tokio::test]
+async fn interrupted_download_resumes_from_the_part() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+    let body = fixture();
+
+    std::fs::write(part_path(&local), &body[..100]).unwrap();
+
+    download(&server.endpoints(), REMOTE, &local, &expected(&body))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&local).unwrap(), body);
+    let requests = server.requests_to("/project/crcns/download/synthetic/filelist.txt");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("range"), Some("bytes=100-"));
+}
+
+#[tokio::test]
+async fn ignored_range_restarts_the_download() {
+    common::set_credentials();
+    let body = fixture();
+    let b = body.clone();
+    let server = FixtureServer::with_handler(Arc::new(move |_| {
+        Response::new(200, "application/octet-stream", b.clone())
+    }))
+    .await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+
+    std::fs::write(part_path(&local), b"stale bytes from another attempt").unwrap();
+
+    download(&server.endpoints(), REMOTE, &local, &expected(&body))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&local).unwrap(), body);
+}
+
+#[tokio::test]
+async fn complete_part_is_committed_without_a_request() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+    let body = fixture();
+
+    std::fs::write(part_path(&local), &body).unwrap();
+
+    download(&server.endpoints(), REMOTE, &local, &expected(&body))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&local).unwrap(), body);
+    assert!(server.requests.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn truncated_download_is_kept_as_part() {
+    common::set_credentials();
+    let body = fixture();
+    let b = body.clone();
+    let server = FixtureServer::with_handler(Arc::new(move |request| {
+        let mut response = common::ranged(request, b.clone());
+        response.body.truncate(response.body.len() / 2);
+        response
+    }))
+    .await;
+    let endpoints = server.endpoints().with_retry(RetryPolicy::none());
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+
+    let result = download(&endpoints, REMOTE, &local, &expected(&body)).await;
+
+    assert!(matches!(result, Err(Error::Integrity { .. })));
+    assert!(!local.exists());
+    assert_eq!(
+        std::fs::metadata(part_path(&local)).unwrap().len(),
+        (body.len() / 2) as u64
+    );
+}
+
+#[tokio::test]
+async fn checksum_mismatch_discards_the_part() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+    let body = fixture();
+    let wrong = Expected {
+        size: Some(body.len() as u64),
+        md5: Some("00000000000000000000000000000000".to_string()),
+    };
+
+    let result = download(&server.endpoints(), REMOTE, &local, &wrong).await;
+
+    assert!(matches!(result, Err(Error::Integrity { .. })));
+    assert!(!local.exists());
+    assert!(!part_path(&local).exists());
+}
+
+#[tokio::test]
+async fn corrupt_resumed_prefix_fails_the_checksum() {
+    common::set_credentials();
+    let server = FixtureServer::start().await;
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("filelist.txt");
+    let body = fixture();
+
+    std::fs::write(part_path(&local), vec![b'x'; 100]).unwrap();
+
+    let result = download(&server.endpoints(), REMOTE, &local, &expected(&body)).await;
+
+    assert!(matches!(result, Err(Error::Integrity { .. })));
+    assert!(!local.exists());
+    assert!(!part_path(&local).exists());
+}
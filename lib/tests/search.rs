@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use lib::search::{tokenize, Clause, Hit, Query, SearchIndex};
+use lib::types::{Collection, Dataset, DatasetMetadata};
+
+fn dataset(alias: &str, description: &str, content: &str) -> Dataset {
+    Dataset {
+        alias: alias.to_string(),
+        description: description.to_string(),
+        content: content.to_string(),
+        ..Dataset::default()
+    }
+}
+
+fn collection(alias: &str, descriptor: &str, datasets: Vec<Dataset>) -> Collection {
+    Collection {
+        alias: alias.to_string(),
+        descriptor: descriptor.to_string(),
+        datasets: Arc::new(Mutex::new(datasets)),
+        ..Collection::default()
+    }
+}
+
+fn catalog() -> SearchIndex {
+    let mut hc3 = dataset(
+        "hc-3",
+        "Multiple single unit recordings from different rat hippocampal and entorhinal regions.",
+        "Recordings made from CA1, CA3 and entorhinal cortex of rats running on a linear track.",
+    );
+    hc3.metadata = DatasetMetadata {
+        species: vec!["Rat".to_string()],
+        techniques: vec!["Tetrodes".to_string()],
+        ..DatasetMetadata::default()
+    };
+
+    SearchIndex::build(&[
+        collection(
+            "hc",
+            "Hippocampus",
+            vec![
+                dataset(
+                    "hc-2",
+                    "Simultaneous intracellular and extracellular recordings from hippocampus.",
+                    "Recordings from CA1 pyramidal cells of anesthetized rats.",
+                ),
+                hc3,
+            ],
+        ),
+        collection(
+            "pvc",
+            "Primary visual cortex",
+            vec![dataset(
+                "pvc-1",
+                "Single-unit recordings from primary visual cortex of macaque monkeys.",
+                "Responses of V1 neurons to natural movies. The visual cortex of the macaque.",
+            )],
+        ),
+    ])
+}
+
+fn aliases(hits: &[Hit]) -> Vec<String> {
+    hits.iter()
+        .map(|h| match &h.dataset {
+            Some(d) => format!("{}/{}", h.collection, d),
+            None => h.collection.clone(),
+        })
+        .collect()
+}
+
+fn search(index: &SearchIndex, text: &str) -> Vec<String> {
+    aliases(&index.search(&Query::parse(text), 10))
+}
+
+#[test]
+fn tokens_are_lowercase_alphanumeric_runs() {
+    assert_eq!(
+        tokenize("hc-3: CA1, Long-Evans"),
+        ["hc", "3", "ca1", "long", "evans"]
+    );
+}
+
+#[test]
+fn queries_parse_terms_prefixes_and_phrases() {
+    assert_eq!(
+        Query::parse(r#"Rat hippo* "visual  cortex" hc-3"#).clauses,
+        vec![
+            Clause::Term("rat".into()),
+            Clause::Prefix("hippo".into()),
+            Clause::Phrase(vec!["visual".into(), "cortex".into()]),
+            Clause::Phrase(vec!["hc".into(), "3".into()]),
+        ]
+    );
+    assert!(Query::parse("  \"\" * ").is_empty());
+}
+
+#[test]
+fn every_term_must_match() {
+    let index = catalog();
+    assert_eq!(index.len(), 5);
+
+    assert_eq!(search(&index, "entorhinal"), ["hc/hc-3"]);
+    assert_eq!(search(&index, "ca1 anesthetized"), ["hc/hc-2"]);
+    assert!(search(&index, "ca1 macaque").is_empty());
+    assert!(search(&index, "").is_empty());
+}
+
+#[test]
+fn prefixes_match_any_token_they_start() {
+    let index = catalog();
+    let hits = search(&index, "hippo*");
+
+    assert_eq!(hits.len(), 3);
+    assert!(hits.contains(&"hc".to_string()));
+    assert!(hits.contains(&"hc/hc-2".to_string()));
+    assert!(hits.contains(&"hc/hc-3".to_string()));
+}
+
+#[test]
+fn phrases_need_consecutive_tokens() {
+    let index = catalog();
+
+    assert_eq!(search(&index, "\"visual cortex\""), ["pvc/pvc-1", "pvc"]);
+    assert!(search(&index, "\"cortex visual\"").is_empty());
+    assert_eq!(search(&index, "hc-3"), ["hc/hc-3"]);
+}
+
+#[test]
+fn metadata_ranks_above_content() {
+    let index = catalog();
+
+    // Extracted metadata is searchable and weighs more than the page text.
+    assert_eq!(search(&index, "tetrodes"), ["hc/hc-3"]);
+    let rats = search(&index, "rat*");
+    assert_eq!(rats, ["hc/hc-3", "hc/hc-2"]);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use lib::recording::{session_xml_path, Error, SessionParameters, SpikeGroup};
+
+fn fixture() -> PathBuf {
+    common::session_fixture("xml")
+}
+
+#[test]
+fn session_parameters_are_read_from_the_xml() {
+    let parameters = SessionParameters::read(&fixture()).unwrap();
+
+    assert_eq!(parameters.n_bits, 16);
+    assert_eq!(parameters.bytes_per_sample(), 2);
+    assert_eq!(parameters.n_channels, 10);
+    assert_eq!(parameters.sampling_rate, 20000.0);
+    assert_eq!(parameters.lfp_sampling_rate, 1250.0);
+    assert_eq!(parameters.anatomical_groups.len(), 3);
+    assert_eq!(parameters.anatomical_groups[2].channels, vec![8, 9]);
+    assert_eq!(parameters.skipped_channels, vec![2, 8]);
+    assert!(parameters.is_skipped(2) && !parameters.is_skipped(3));
+
+    assert_eq!(parameters.spike_groups.len(), 2);
+    assert_eq!(parameters.spike_group(0), None);
+    assert_eq!(
+        parameters.spike_group(2),
+        Some(&SpikeGroup {
+            channels: vec![4, 5, 6, 7],
+            n_samples: 54,
+            peak_sample_index: 26,
+            n_features: 4,
+        })
+    );
+
+    let microvolts = parameters.microvolts_per_bit().unwrap();
+    assert!((microvolts - 20.0 / 65536.0 / 1000.0 * 1e6).abs() < 1e-9);
+}
+
+#[test]
+fn older_files_share_the_waveform_layout() {
+    let xml = "<parameters>\
+        <acquisitionSystem><nBits>16</nBits><nChannels>4</nChannels>\
+        <samplingRate>20000</samplingRate></acquisitionSystem>\
+        <spikes><nSamples>32</nSamples><peakSampleIndex>16</peakSampleIndex></spikes>\
+        <spikeDetection><channelGroups><group><channels>\
+        <channel>0</channel><channel>1</channel><channel>2</channel><channel>3</channel>\
+        </channels></group></channelGroups></spikeDetection>\
+        </parameters>";
+    let parameters = SessionParameters::parse(xml, Path::new("old.xml")).unwrap();
+
+    // Defaults for what the file leaves out.
+    assert_eq!(parameters.lfp_sampling_rate, 1250.0);
+    assert_eq!(parameters.microvolts_per_bit(), None);
+    assert_eq!(
+        parameters.spike_group(1),
+        Some(&SpikeGroup {
+            channels: vec![0, 1, 2, 3],
+            n_samples: 32,
+            peak_sample_index: 16,
+            n_features: 3,
+        })
+    );
+}
+
+#[test]
+fn missing_xml_names_the_expected_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let recording = directory.path().join("ec012ec.188.eeg");
+
+    let error = SessionParameters::for_recording(&recording).unwrap_err();
+    let expected = directory.path().join("ec012ec.188.xml");
+    assert!(matches!(&error, Error::MissingParameters(path) if *path == expected));
+    assert!(error.to_string().contains("ec012ec.188.xml"));
+}
+
+#[test]
+fn invalid_parameters_are_parse_errors() {
+    let cases = [
+        ("<parameters/>", "nBits is missing"),
+        ("<parameters", ""),
+        (
+            "<parameters><acquisitionSystem><nBits>12</nBits></acquisitionSystem></parameters>",
+            "nBits is 12",
+        ),
+        (
+            "<parameters><acquisitionSystem><nBits>16</nBits><nChannels>2</nChannels>\
+             <samplingRate>20000</samplingRate></acquisitionSystem>\
+             <anatomicalDescription><channelGroups><group><channel>2</channel></group>\
+             </channelGroups></anatomicalDescription></parameters>",
+            "channel 2 is out of the 2 recorded",
+        ),
+        (
+            "<parameters><acquisitionSystem><nBits>16</nBits><nChannels>2</nChannels>\
+             <samplingRate>fast</samplingRate></acquisitionSystem></parameters>",
+            "samplingRate is \"fast\"",
+        ),
+        (
+            "<parameters><acquisitionSystem><nBits>16</nBits><nChannels>2</nChannels>\
+             <samplingRate>20000</samplingRate></acquisitionSystem>\
+             <spikeDetection><channelGroups><group><channels><channel>0</channel></channels>\
+             </group></channelGroups></spikeDetection></parameters>",
+            "spike group 1 has no nSamples",
+        ),
+    ];
+    for (xml, reason) in cases {
+        let error = SessionParameters::parse(xml, Path::new("s.xml")).unwrap_err();
+        assert!(matches!(error, Error::Parse { .. }), "{xml}");
+        assert!(error.to_string().contains(reason), "{error}");
+    }
+}
+
+#[test]
+fn recordings_are_described_by_their_session_xml() {
+    for recording in [
+        "s/ec012ec.188/ec012ec.188.eeg",
+        "s/ec012ec.188/ec012ec.188.dat",
+        "s/ec012ec.188/ec012ec.188.res.1",
+        "s/ec012ec.188/ec012ec.188.fet.12",
+    ] {
+        assert_eq!(
+            session_xml_path(Path::new(recording)),
+            Path::new("s/ec012ec.188/ec012ec.188.xml")
+        );
+    }
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::SESSION;
+use lib::recording::{
+    parse_clu, parse_res, Error, SessionParameters, SpikeTrains, SpikeWaveforms, UnitId,
+    NOISE_CLUSTERS,
+};
+
+fn fixture() -> PathBuf {
+    common::session_fixture("xml")
+}
+
+#[test]
+fn units_are_keyed_by_shank_and_cluster() {
+    let xml = fixture();
+    let parameters = SessionParameters::read(&xml).unwrap();
+    let trains = SpikeTrains::read(&xml, &parameters, &NOISE_CLUSTERS).unwrap();
+
+    // Shank 2 has no .res/.clu in the fixture.
+    assert_eq!(trains.files.len(), 1);
+    assert_eq!(trains.missing_shanks, vec![2]);
+    assert_eq!(trains.shank_spikes.get(&1), Some(&6));
+    assert_eq!(
+        trains.units.keys().copied().collect::<Vec<_>>(),
+        vec![
+            UnitId {
+                shank: 1,
+                cluster: 2
+            },
+            UnitId {
+                shank: 1,
+                cluster: 3
+            }
+        ]
+    );
+
+    let unit = trains.unit(1, 2).unwrap();
+    assert_eq!(unit.times, vec![0.2, 1.75]);
+    assert_eq!(unit.spike_indices, vec![0, 4]);
+    assert_eq!(trains.unit(1, 3).unwrap().spike_indices, vec![2, 5]);
+    assert_eq!(trains.shank(1).count(), 2);
+
+    let files = trains.files(1).unwrap();
+    assert_eq!(files.spk, common::session_fixture("spk.1"));
+    assert_eq!(files.fet, common::session_fixture("fet.1"));
+}
+
+#[test]
+fn each_file_is_located_on_its_own() {
+    // The .clu in another directory, as when a mirror holds part of a session.
+    let directory = tempfile::tempdir().unwrap();
+    std::fs::copy(
+        common::session_fixture("clu.1"),
+        directory.path().join(format!("{SESSION}.clu.1")),
+    )
+    .unwrap();
+    let parameters = SessionParameters::read(&fixture()).unwrap();
+
+    let trains = SpikeTrains::read_with(&parameters, &NOISE_CLUSTERS, |extension| match extension
+        .starts_with("clu")
+    {
+        true => directory.path().join(format!("{SESSION}.{extension}")),
+        false => common::session_fixture(extension),
+    })
+    .unwrap();
+
+    assert_eq!(trains.unit(1, 2).unwrap().spike_indices, vec![0, 4]);
+    assert_eq!(
+        trains.files(1).unwrap().clu,
+        directory.path().join(format!("{SESSION}.clu.1"))
+    );
+    assert_eq!(
+        trains.files(1).unwrap().res,
+        common::session_fixture("res.1")
+    );
+    assert_eq!(trains.missing_shanks, vec![2]);
+}
+
+#[test]
+fn a_shank_is_read_without_the_others() {
+    let directory = tempfile::tempdir().unwrap();
+    for extension in ["xml", "res.1", "clu.1"] {
+        let copy = directory.path().join(format!("{SESSION}.{extension}"));
+        std::fs::copy(common::session_fixture(extension), copy).unwrap();
+    }
+    std::fs::write(directory.path().join(format!("{SESSION}.res.2")), "x\n").unwrap();
+    std::fs::write(directory.path().join(format!("{SESSION}.clu.2")), "1\n1\n").unwrap();
+    let xml = directory.path().join(format!("{SESSION}.xml"));
+    let parameters = SessionParameters::read(&xml).unwrap();
+    let locate = |extension: &str| directory.path().join(format!("{SESSION}.{extension}"));
+
+    assert!(SpikeTrains::read_with(&parameters, &NOISE_CLUSTERS, locate).is_err());
+
+    let mut trains = SpikeTrains::default();
+    trains
+        .read_shank(&parameters, 1, &NOISE_CLUSTERS, locate)
+        .unwrap();
+    assert_eq!(
+        trains.shank_spikes.keys().copied().collect::<Vec<_>>(),
+        vec![1]
+    );
+    assert_eq!(trains.unit(1, 2).unwrap().spike_indices, vec![0, 4]);
+    assert!(trains.missing_shanks.is_empty());
+}
+
+#[test]
+fn noise_clusters_can_be_kept() {
+    let xml = fixture();
+    let parameters = SessionParameters::read(&xml).unwrap();
+    let trains = SpikeTrains::read(&xml, &parameters, &[]).unwrap();
+
+    assert_eq!(trains.len(), 4);
+    assert_eq!(trains.unit(1, 0).unwrap().times, vec![0.205]);
+    assert_eq!(trains.unit(1, 1).unwrap().spike_indices, vec![3]);
+}
+
+#[test]
+fn inconsistent_files_are_errors() {
+    let path = Path::new("s.clu.1");
+    assert!(matches!(
+        parse_res("10\nx\n", path),
+        Err(Error::Parse { reason, .. }) if reason.contains("line 2")
+    ));
+    assert!(parse_clu("", path).is_err());
+    assert!(parse_clu("two\n1\n", path).is_err());
+    assert_eq!(parse_clu("2\r\n1\r\n\r\n2\r\n", path).unwrap(), vec![1, 2]);
+
+    let mut trains = SpikeTrains::default();
+    assert!(trains
+        .add_shank(1, &[10, 20], &[2], 20000.0, &NOISE_CLUSTERS)
+        .is_err());
+
+    // A .res without its .clu.
+    let directory = tempfile::tempdir().unwrap();
+    let xml = directory.path().join(format!("{SESSION}.xml"));
+    std::fs::copy(fixture(), &xml).unwrap();
+    std::fs::write(directory.path().join(format!("{SESSION}.res.2")), "10\n").unwrap();
+    let parameters = SessionParameters::read(&xml).unwrap();
+    let error = SpikeTrains::read(&xml, &parameters, &NOISE_CLUSTERS).unwrap_err();
+    assert!(
+        matches!(&error, Error::Io { path, .. } if path.ends_with(format!("{SESSION}.clu.2"))),
+        "{error}"
+    );
+}
+
+#[test]
+fn waveforms_are_found_by_spike_index() {
+    let parameters = SessionParameters::read(&fixture()).unwrap();
+    let group = parameters.spike_group(1).unwrap();
+    let (n_samples, n_channels) = (group.n_samples, group.channels.len());
+
+    // Every sample of spike `i` is `i`, then half a waveform.
+    let mut bytes = Vec::new();
+    for spike in 0..6i16 {
+        for _ in 0..n_samples * n_channels {
+            bytes.extend_from_slice(&spike.to_le_bytes());
+        }
+    }
+    bytes.extend_from_slice(&vec![0; n_samples * n_channels]);
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join(format!("{SESSION}.spk.1"));
+    std::fs::write(&filepath, bytes).unwrap();
+
+    let waveforms = SpikeWaveforms::open(&filepath, group, parameters.sampling_rate).unwrap();
+    assert_eq!(waveforms.n_spikes(), 6);
+
+    let trains = SpikeTrains::read(&fixture(), &parameters, &NOISE_CLUSTERS).unwrap();
+    for index in &trains.unit(1, 3).unwrap().spike_indices {
+        let waveform = waveforms.waveform(*index).unwrap();
+        assert_eq!(waveform.dim(), (n_samples, n_channels));
+        assert!(waveform.iter().all(|v| *v == *index as i16));
+    }
+    assert!(waveforms.waveform(6).is_err());
+}
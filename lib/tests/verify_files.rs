@@ -0,0 +1,78 @@
+mod common;
+
+use std::path::Path;
+
+use lib::files::{part_path, verify_file};
+use lib::types::{File, FileStatus};
+
+const BODY: &[u8] = b"0123456789";
+
+fn listed(local_path: &Path) -> File {
+    common::listed("ec012ec.188.res.1", BODY, local_path)
+}
+
+#[tokio::test]
+async fn matching_file_is_ok() {
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("a");
+    std::fs::write(&local, BODY).unwrap();
+
+    let file = verify_file(&listed(&local)).await.unwrap();
+
+    assert_eq!(file.status, FileStatus::Ok);
+    assert_eq!(file.local_size, BODY.len() as u64);
+    assert_eq!(file.local_md5, format!("{:x}", md5::compute(BODY)));
+}
+
+#[tokio::test]
+async fn different_content_is_a_mismatch() {
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("a");
+    std::fs::write(&local, b"9876543210").unwrap();
+
+    let file = verify_file(&listed(&local)).await.unwrap();
+
+    assert_eq!(file.status, FileStatus::Mismatch);
+}
+
+#[tokio::test]
+async fn different_size_is_a_mismatch() {
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("a");
+    std::fs::write(&local, b"012").unwrap();
+
+    let file = verify_file(&listed(&local)).await.unwrap();
+
+    assert_eq!(file.status, FileStatus::Mismatch);
+    assert_eq!(file.local_size, 3);
+}
+
+#[tokio::test]
+async fn absent_file_is_missing_or_partial() {
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("a");
+
+    let file = verify_file(&listed(&local)).await.unwrap();
+    assert_eq!(file.status, FileStatus::Missing);
+
+    std::fs::write(part_path(&local), &BODY[..4]).unwrap();
+    let file = verify_file(&listed(&local)).await.unwrap();
+    assert_eq!(file.status, FileStatus::Partial);
+}
+
+#[tokio::test]
+async fn unlisted_checksum_falls_back_to_the_size() {
+    let directory = tempfile::tempdir().unwrap();
+    let local = directory.path().join("a");
+    std::fs::write(&local, BODY).unwrap();
+
+    let mut file = listed(&local);
+    file.remote_md5 = String::new();
+    assert_eq!(verify_file(&file).await.unwrap().status, FileStatus::Ok);
+
+    file.remote_size = 0;
+    assert_eq!(
+        verify_file(&file).await.unwrap().status,
+        FileStatus::Unknown
+    );
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use lib::settings::{Settings, Workspace};
+use lib::types::State;
+
+fn settings() -> Settings {
+    let mut settings = Settings::default();
+    settings.add(Workspace::new("lab", "/srv/crcns"));
+    settings.add(Workspace::new("laptop", "/home/lens/crcns"));
+    settings
+}
+
+#[test]
+fn settings_round_trip_through_toml() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("crcns-lens/settings.toml");
+
+    let mut saved = settings();
+    saved.current = Some("laptop".to_string());
+    saved.save_to(&filepath).unwrap();
+
+    let content = std::fs::read_to_string(&filepath).unwrap();
+    assert!(content.contains("[[workspace]]"), "{content}");
+    assert!(content.contains("current = \"laptop\""), "{content}");
+    assert_eq!(Settings::load_from(&filepath).unwrap(), saved);
+}
+
+#[test]
+fn missing_settings_are_the_defaults_and_malformed_ones_an_error() {
+    let directory = tempfile::tempdir().unwrap();
+    let filepath = directory.path().join("settings.toml");
+
+    assert_eq!(Settings::load_from(&filepath).unwrap(), Settings::default());
+
+    std::fs::write(&filepath, "[[workspace]]\nname = 3\n").unwrap();
+    let error = Settings::load_from(&filepath).unwrap_err();
+    assert!(error.to_string().contains("settings.toml"), "{error}");
+}
+
+#[test]
+fn requested_then_current_then_first_workspace_is_opened() {
+    let fallback = Path::new("/tmp/somewhere");
+    let mut settings = settings();
+
+    assert_eq!(settings.resolve(None, fallback).unwrap().name, "lab");
+    assert_eq!(
+        settings.resolve(Some("laptop"), fallback).unwrap().name,
+        "laptop"
+    );
+    assert_eq!(settings.current.as_deref(), Some("laptop"));
+    assert_eq!(settings.resolve(None, fallback).unwrap().name, "laptop");
+
+    let error = settings.resolve(Some("cluster"), fallback).unwrap_err();
+    assert!(error.to_string().contains("lab, laptop"), "{error}");
+    assert_eq!(settings.current.as_deref(), Some("laptop"));
+}
+
+#[test]
+fn first_run_records_a_default_workspace() {
+    let mut settings = Settings::default();
+    let workspace = settings
+        .resolve(None, Path::new("/tmp/launched-here"))
+        .unwrap();
+
+    assert_eq!(workspace, Workspace::new("default", "/tmp/launched-here"));
+    assert_eq!(settings.workspaces, [workspace]);
+    assert_eq!(settings.current.as_deref(), Some("default"));
+}
+
+#[test]
+fn workspaces_are_replaced_by_name_and_removed() {
+    let mut settings = settings();
+    settings.current = Some("lab".to_string());
+
+    settings.add(Workspace::new(" lab ", "/mnt/lab"));
+    assert_eq!(settings.workspaces.len(), 2);
+    assert_eq!(
+        settings.workspace("lab").unwrap().root,
+        Path::new("/mnt/lab")
+    );
+
+    settings.remove("lab");
+    assert!(settings.workspace("lab").is_none());
+    assert_eq!(settings.current, None);
+}
+
+#[test]
+fn state_works_in_the_workspace_data_directory() {
+    let directory = tempfile::tempdir().unwrap();
+    let workspace = Workspace::new("lab", directory.path());
+
+    let state = State::for_workspace(workspace.clone());
+
+    assert_eq!(state.working_directory, directory.path());
+    assert_eq!(workspace.data_directory(), directory.path().join("data"));
+    assert_eq!(state.workspace, workspace);
+}
@@ -5,8 +5,55 @@ use tokio::time::sleep;
 
 use lib::global::LENS;
 use lib::gui::app::Lens;
+use lib::settings::Settings;
+use lib::types::State;
+
+const USAGE: &str = "Usage: CRCNS-lens [--workspace <name>]
+
+Workspaces are listed in the settings file, the one opened last is used when
+no name is given.";
+
+/// The workspace named on the command line, if any.
+fn requested_workspace() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    let mut workspace = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-w" | "--workspace" => match args.next() {
+                Some(name) => workspace = Some(name),
+                None => exit(&format!("{arg} needs a workspace name")),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}\n\nSettings: {}", Settings::filepath().display());
+                std::process::exit(0);
+            }
+            _ => match arg.strip_prefix("--workspace=") {
+                Some(name) => workspace = Some(name.to_string()),
+                None => exit(&format!("unknown argument {arg}")),
+            },
+        }
+    }
+    workspace
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
 
 fn main() -> eframe::Result<()> {
+    let requested = requested_workspace();
+    let settings_filepath = Settings::filepath();
+    let mut settings = Settings::load_from(&settings_filepath)
+        .unwrap_or_else(|e| exit(&format!("Unable to read the settings: {e}")));
+    let cwd = std::env::current_dir().expect("Unable to read the current directory.");
+    let workspace = settings
+        .resolve(requested.as_deref(), &cwd)
+        .unwrap_or_else(|e| exit(&e.to_string()));
+    if let Err(e) = settings.save_to(&settings_filepath) {
+        eprintln!("Unable to save {}: {e}", settings_filepath.display());
+    }
+
     let trt = Runtime::new().expect("Unable to initialize tokio runtime.");
     let trt = Arc::new(Mutex::new(trt));
 
@@ -19,7 +66,9 @@ fn main() -> eframe::Result<()> {
             }
         })
     });
-    let app = Lens::new(trt.clone());
+    let data_directory = workspace.data_directory();
+    std::fs::create_dir_all(data_directory).expect("Unable to create data directory.");
+    let app = Lens::new(trt.clone(), State::for_workspace(workspace));
 
     // std::thread::spawn(move || async {
     //     loop {
@@ -36,10 +85,16 @@ fn main() -> eframe::Result<()> {
     //     }
     // });
 
-    let data_directory = app.state.working_directory.join("data");
-    std::fs::create_dir_all(data_directory).expect("Unable to create data directory.");
+    let state = app.state.read().unwrap().clone();
+    match state.credential_store.load() {
+        Ok(credentials) => lib::credentials::set_current(credentials),
+        Err(e) => eprintln!("Unable to load the stored CRCNS account: {e}"),
+    }
 
     LENS.get_or_init(|| app.clone());
+    state.downloads.start(state.endpoints.clone());
+    lib::global::load_catalog();
+    lib::global::refresh_usage();
 
     eframe::run_native(
         "CRCNS - Lens",